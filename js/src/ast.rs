@@ -1,4 +1,7 @@
+use crate::common::stack_address;
 use crate::token::*;
+use crate::error::*;
+use crate::regexp::RegExp;
 
 /* ast stuff */
 #[allow(non_camel_case_types)]
@@ -13,6 +16,7 @@ pub enum AstType {
 	EXP_IDENTIFIER,
 	EXP_NUMBER,
 	EXP_STRING,
+	EXP_REGEXP,
 
 	/* literals */
 	EXP_UNDEF, /* for array elisions */
@@ -250,6 +254,23 @@ impl AstNode {
     }
 }
 
+// long lists, e.g. the statements of a big script, would be dropped recursively by default
+impl Drop for AstNode {
+    fn drop(&mut self) {
+        let mut nodes: Vec<Box<AstNode>> = Vec::new();
+        nodes.extend(self.a.take());
+        nodes.extend(self.b.take());
+        nodes.extend(self.c.take());
+        nodes.extend(self.d.take());
+        while let Some(mut node) = nodes.pop() {
+            nodes.extend(node.a.take());
+            nodes.extend(node.b.take());
+            nodes.extend(node.c.take());
+            nodes.extend(node.d.take());
+        }
+    }
+}
+
 fn tk_accept(tkr: &mut Tokenlizer, tkt: TokenType) -> Result<bool, String> {
     let ntk = tkr.forward()?;
     if ntk.tk_type != tkt {
@@ -275,6 +296,31 @@ fn tk_lookahead(tkr: &mut Tokenlizer, tkt: TokenType) -> Result<bool, String> {
     return Ok(false);
 }
 
+// nesting of statements and expressions, each term of a chain like a + b + c counts too because the
+// compiler recurses on the tree. Deeper source is a SyntaxError instead of a stack overflow, so is source
+// which takes the parser below its limit of the Rust stack, a bound reached earlier in debug builds.
+const AST_MAX_DEPTH: usize = 100;
+
+// bytes of the Rust stack the parser may use when its caller doesn't give a limit
+pub const AST_MAX_STACK: usize = 1 << 20;
+
+fn ast_deeper(tkr: &mut Tokenlizer) -> Result<(), String> {
+    if tkr.depth >= AST_MAX_DEPTH || stack_address() < tkr.stack_limit {
+        return Err("too deeply nested".to_string());
+    }
+    tkr.depth = tkr.depth + 1;
+    return Ok(());
+}
+
+// parse with f one level deeper
+fn ast_nested(tkr: &mut Tokenlizer, f: fn(&mut Tokenlizer) -> Result<AstNode, String>) -> Result<AstNode, String> {
+    let depth = tkr.depth;
+    ast_deeper(tkr)?;
+    let node = f(tkr);
+    tkr.depth = depth;
+    return node;
+}
+
 fn ast_identifier(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     let id = tk_expect(tkr, TokenType::TK_IDENTIFIER)?;
     let node = AstNode::new_string(AstType::AST_IDENTIFIER, tkr.line(), &id.tk_value.unwrap());
@@ -392,6 +438,20 @@ fn ast_primary(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
         let a = AstNode::new_number(AstType::EXP_NUMBER, tkr.line(), value);
         return Ok(a);
     }
+    if tk_accept(tkr, TokenType::TK_REGEXP)? {
+        // the literal is /pattern/flags
+        let literal = lookahead.tk_value.unwrap();
+        let split = literal.rfind('/').unwrap();
+        let pattern = &literal[1..split];
+        let flags = &literal[split+1..];
+        if let Err(msg) = RegExp::new(pattern, flags) {
+            return Err(format!("{} @ {}", msg, tkr.line()));
+        }
+        let a = AstNode::new_string(AstType::EXP_STRING, tkr.line(), pattern);
+        let b = AstNode::new_string(AstType::EXP_STRING, tkr.line(), flags);
+        let exp = AstNode::new_a_b(AstType::EXP_REGEXP, tkr.line(), a, b);
+        return Ok(exp);
+    }
     if tk_accept(tkr, TokenType::TK_THIS)? {
         let a = AstNode::new(AstType::EXP_THIS, tkr.line());
         return Ok(a);
@@ -435,7 +495,7 @@ fn ast_primary(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
         return Ok(a);
     }
 
-    panic!(format!("unexpected token in expression: {:?} @ {}", lookahead, tkr.line()));
+    return Err(format!("unexpected token in expression: {:?} @ {}", lookahead.tk_type, tkr.line()));
 }
 
 fn ast_arguments(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
@@ -465,15 +525,18 @@ fn ast_formula_funexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_formula_memberexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_newexp(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_POINT)? {
+            ast_deeper(tkr)?;
             let b = ast_identifier(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_MEMBER, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_BRACKET_LEFT)? {
+            ast_deeper(tkr)?;
             let b = ast_expression(tkr)?;
             tk_expect(tkr, TokenType::TK_BRACKET_RIGHT)?;
             a = AstNode::new_a_b(AstType::EXP_INDEX, tkr.line(), a, b);
@@ -481,12 +544,13 @@ fn ast_formula_memberexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_newexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     if tk_accept(tkr, TokenType::TK_NEW)? {
-        let a = ast_formula_memberexp(tkr)?;
+        let a = ast_nested(tkr, ast_formula_memberexp)?;
         if tk_accept(tkr, TokenType::TK_PAREN_LEFT)? {
             let b = ast_arguments(tkr)?;
             tk_expect(tkr, TokenType::TK_PAREN_RIGHT)?;
@@ -502,20 +566,24 @@ fn ast_formula_newexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_formula_callexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_newexp(tkr)?;
     loop {
         if tk_accept(tkr, TokenType::TK_POINT)? {
+            ast_deeper(tkr)?;
             let b = ast_identifier(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_MEMBER, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_BRACKET_LEFT)? {
+            ast_deeper(tkr)?;
             let b = ast_expression(tkr)?;
             tk_expect(tkr, TokenType::TK_BRACKET_RIGHT)?;
             a = AstNode::new_a_b(AstType::EXP_INDEX, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_PAREN_LEFT)? {
+            ast_deeper(tkr)?;
             let b = ast_arguments(tkr)?;
             tk_expect(tkr, TokenType::TK_PAREN_RIGHT)?;
             a = AstNode::new_a_b(AstType::EXP_CALL, tkr.line(), a, b);
@@ -523,6 +591,7 @@ fn ast_formula_callexp(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
@@ -543,47 +612,47 @@ fn ast_formula_postfix(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 
 fn ast_formula_unary(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     if tk_accept(tkr, TokenType::TK_DELETE)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_DELETE, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_VOID)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_VOID, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_TYPEOF)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_TYPEOF, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_INC)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_PREINC, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_DEC)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_PREDEC, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_BITNOT)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_BITNOT, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_NOT)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_LOGNOT, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_SUB)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_NEG, tkr.line(), a);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_ADD)? {
-        let a = ast_formula_unary(tkr)?;
+        let a = ast_nested(tkr, ast_formula_unary)?;
         let stm = AstNode::new_a(AstType::EXP_POS, tkr.line(), a);
         return Ok(stm);
     }
@@ -591,103 +660,124 @@ fn ast_formula_unary(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_formula_multiplicative(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_unary(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_MUL)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_unary(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_MUL, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_DIV)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_unary(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_DIV, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_MOD)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_unary(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_MOD, tkr.line(), a, b);
             continue;
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_additive(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_multiplicative(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_ADD)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_multiplicative(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_ADD, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_SUB)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_multiplicative(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_SUB, tkr.line(), a, b);
             continue;
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_shift(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_additive(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_SHL)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_additive(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_SHL, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_SHR)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_additive(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_SHR, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_USHR)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_additive(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_USHR, tkr.line(), a, b);
             continue;
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_relational(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_shift(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_LT)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_shift(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_LT, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_GT)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_shift(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_GT, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_LE)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_shift(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_LE, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_GE)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_shift(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_GE, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_INSTANCEOF)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_shift(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_INSTANCEOF, tkr.line(), a, b);
             continue;
         }
         if !tkr.notin {
             if tk_accept(tkr, TokenType::TK_IN)? {
+                ast_deeper(tkr)?;
                 let b = ast_formula_shift(tkr)?;
                 a = AstNode::new_a_b(AstType::EXP_IN, tkr.line(), a, b);
                 continue;
@@ -695,80 +785,102 @@ fn ast_formula_relational(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
         } 
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_eq(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_relational(tkr)?;
 
     loop {
         if tk_accept(tkr, TokenType::TK_EQ)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_relational(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_EQ, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_NE)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_relational(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_NE, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_STRICTEQ)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_relational(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_STRICTEQ, tkr.line(), a, b);
             continue;
         }
         if tk_accept(tkr, TokenType::TK_STRICTNE)? {
+            ast_deeper(tkr)?;
             let b = ast_formula_relational(tkr)?;
             a = AstNode::new_a_b(AstType::EXP_STRICTNE, tkr.line(), a, b);
             continue;
         }
         break;
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_bitand(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_eq(tkr)?;
     while tk_accept(tkr, TokenType::TK_AND)? {
+        ast_deeper(tkr)?;
         let b = ast_formula_eq(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_BITAND, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_bitxor(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_bitand(tkr)?;
     while tk_accept(tkr, TokenType::TK_XOR)? {
+        ast_deeper(tkr)?;
         let b = ast_formula_bitand(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_BITXOR, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_bitor(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_bitxor(tkr)?;
     while tk_accept(tkr, TokenType::TK_OR)? {
+        ast_deeper(tkr)?;
         let b = ast_formula_bitxor(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_BITOR, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_and(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_bitor(tkr)?;
     while tk_accept(tkr, TokenType::TK_AND_AND)? {
+        ast_deeper(tkr)?;
         let b = ast_formula_bitor(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_LOGAND, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
 fn ast_formula_or(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_formula_and(tkr)?;
     while tk_accept(tkr, TokenType::TK_OR_OR)? {
+        ast_deeper(tkr)?;
         let b = ast_formula_and(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_LOGOR, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
@@ -784,6 +896,10 @@ fn ast_formula(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_assignment(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    return ast_nested(tkr, ast_assignment_nested);
+}
+
+fn ast_assignment_nested(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     let a = ast_formula(tkr)?;
 
     if tk_accept(tkr, TokenType::TK_ASS)? {
@@ -839,11 +955,14 @@ fn ast_assignment(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_expression(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    let depth = tkr.depth;
     let mut a = ast_assignment(tkr)?;
     while tk_accept(tkr, TokenType::TK_COMMA)? {
+        ast_deeper(tkr)?;
         let b = ast_assignment(tkr)?;
         a = AstNode::new_a_b(AstType::EXP_COMMA, tkr.line(), a, b);
     }
+    tkr.depth = depth;
     return Ok(a);
 }
 
@@ -888,17 +1007,19 @@ fn ast_parameters(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_caseclause(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    // clauses are at the line of their label, not at the end of their statements
+    let line = tkr.line();
     if tk_accept(tkr, TokenType::TK_CASE)? {
         let a = ast_expression(tkr)?;
         tk_expect(tkr, TokenType::TK_COLON)?;
         let b = ast_statement_list(tkr)?;
-        let stm = AstNode::new_a_b(AstType::STM_CASE, tkr.line(), a, b);
+        let stm = AstNode::new_a_b(AstType::STM_CASE, line, a, b);
         return Ok(stm);
     }
     if tk_accept(tkr, TokenType::TK_DEFAULT)? {
         tk_expect(tkr, TokenType::TK_COLON)?;
         let a = ast_statement_list(tkr)?;
-        let stm = AstNode::new_a(AstType::STM_DEFAULT, tkr.line(), a);
+        let stm = AstNode::new_a(AstType::STM_DEFAULT, line, a);
         return Ok(stm);
     }
 
@@ -1029,6 +1150,10 @@ fn ast_block(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
 }
 
 fn ast_statement(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    return ast_nested(tkr, ast_statement_nested);
+}
+
+fn ast_statement_nested(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     if tkr.forward()?.tk_type == TokenType::TK_BRACE_LEFT {
         return ast_block(tkr);
 
@@ -1223,22 +1348,34 @@ fn ast_element(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
     return ast_statement(tkr);
 }
 
-pub fn build_ast_from_script(script: &str) -> Result<AstNode, String> {
-    let mut tkr = Tokenlizer::new(script);
-
-    if tk_accept(&mut tkr, TokenType::TK_EOF)? {
+fn ast_script(tkr: &mut Tokenlizer) -> Result<AstNode, String> {
+    if tk_accept(tkr, TokenType::TK_EOF)? {
         let empty = AstNode::new( AstType::AST_NULL, 0);
         return Ok(empty);
     }
 
-    let mut head = AstNode::new_list( ast_element(&mut tkr)?);
+    let mut head = AstNode::new_list( ast_element(tkr)?);
 
     let mut tail: &mut AstNode = &mut head;
-    while tk_accept(&mut tkr, TokenType::TK_EOF)? == false {
-        AstNode::list_tail_push(tail, ast_element(&mut tkr)?);
+    while tk_accept(tkr, TokenType::TK_EOF)? == false {
+        AstNode::list_tail_push(tail, ast_element(tkr)?);
         tail = tail.b.as_mut().unwrap();
     }
 
     return Ok(head);
 }
 
+pub fn build_ast_from_script(script: &str, stack_limit: usize) -> Result<AstNode, Error> {
+    let mut tkr = Tokenlizer::new(script);
+    tkr.stack_limit = stack_limit;
+
+    match ast_script(&mut tkr) {
+        Ok(ast) => Ok(ast),
+        Err(msg) => Err(Error::SyntaxError {
+            msg:    msg,
+            line:   tkr.line(),
+            column: tkr.column(),
+        }),
+    }
+}
+
@@ -6,6 +6,11 @@ use std::io;
 use std::io::Write;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+fn run(rt: &mut ezjs::runtime::JsRuntime, filename: &str, script: &str) -> Result<(), ezjs::Error> {
+    let vmf = ezjs::build_function_from_file(filename, script)?;
+    ezjs::run_script(rt, vmf)?;
+    return Ok(());
+}
 
 pub fn main() {
     let mut rt = ezjs::new_runtime();
@@ -14,8 +19,9 @@ pub fn main() {
     let args: Vec<String> = env::args().collect();
     for i in 1..args.len() {
         let content = fs::read_to_string(&args[i]).unwrap();
-        let vmf = ezjs::build_function_from_code(&content).unwrap();
-        ezjs::run_script(&mut rt, vmf).unwrap();
+        if let Err(err) = run(&mut rt, &args[i], &content) {
+            println!("{}", err);
+        }
     }
 
     loop {
@@ -23,12 +29,16 @@ pub fn main() {
         io::stdout().flush().unwrap();
         let mut line = String::new();
         match io::stdin().read_line(&mut line) {
+            Ok(0) => {
+                break;
+            },
             Ok(_n) => {
                 if line != "" {
                     let begin = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
                     
-                    let vmf = ezjs::build_function_from_code(&line).unwrap();
-                    let _ret = ezjs::run_script(&mut rt, vmf).unwrap();
+                    if let Err(err) = run(&mut rt, "[repl]", &line) {
+                        println!("{}", err);
+                    }
                     let end = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis();
 
                     println!("<{}>", end - begin);
@@ -1,172 +1,2253 @@
+use std::cmp;
+use std::rc::Rc;
 use std::collections::HashMap;
 
+use crate::common::*;
 use crate::bytecode::*;
 use crate::runtime::*;
+use crate::execute::*;
+use crate::regexp::{Captures, RegExp};
+use crate::date::*;
 
 // The Object class 
-fn object_constructor(rt: &mut JsRuntime) {
-    let value = rt.top(-1);
-    if value.is_something() {        
-        rt.push( value.duplicate() );
+fn object_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let value = args[0].clone();
+    if value.is_object() {
+        return Ok(value);
+    }
+    return Ok(SharedValue::new_vanilla(rt.prototypes.object_prototype.clone()));
+}
+
+// the object argument of Object's functions
+fn object_arg(v: &SharedValue, func: &str) -> Result<SharedObject, JsException> {
+    if v.is_object() {
+        return Ok(v.get_object());
+    }
+    return Err(JsException::new_type_error(format!("Object.{} called on non-object", func)));
+}
+
+// read a property like scripts do, getters and prototypes included
+fn get_value(rt: &mut JsRuntime, target: SharedObject, name: &str) -> Result<SharedValue, JsException> {
+    rt.getproperty(target, name)?;
+    return Ok(rt.stack.pop().unwrap());
+}
+
+// own property of object, elements of arrays and strings are reported as properties too
+fn own_property(rt: &JsRuntime, object: &JsObject, name: &str) -> Option<JsProperty> {
+    let mut prop = JsProperty::new();
+    match object.value {
+        JsClass::array(ref v) => {
+            let (writable, _, configurable) = object.elements_attr;
+            if name == "length" {
+                prop.value = SharedValue::new_number(v.len() as f64);
+                prop.fill_attr((writable, false, false));
+                return Some(prop);
+            }
+            if let Some(idx) = str_to_index(name) {
+                let e = v.get(idx)?;
+                prop.value = e.clone();
+                prop.fill_attr((writable, true, configurable));
+                return Some(prop);
+            }
+        },
+        JsClass::function(_) | JsClass::builtin(_) | JsClass::bound(_) => {
+            if (name == "length" || name == "name") && !object.properties.contains_key(name) {
+                prop.value = if name == "length" {
+                    SharedValue::new_number(object.func_length() as f64)
+                } else {
+                    rt.new_string(object.func_name())
+                };
+                prop.fill_attr((false, false, false));
+                return Some(prop);
+            }
+        },
+        JsClass::string(ref s) => {
+            let s: Vec<u16> = s.encode_utf16().collect();
+            if name == "length" {
+                prop.value = SharedValue::new_number(s.len() as f64);
+                prop.fill_attr((false, false, false));
+                return Some(prop);
+            }
+            if let Some(idx) = str_to_index(name) {
+                let c = s.get(idx)?;
+                prop.value = rt.new_string(String::from_utf16_lossy(&[*c]));
+                prop.fill_attr((false, true, false));
+                return Some(prop);
+            }
+        },
+        _ => {}
+    }
+    return object.properties.get(name).cloned();
+}
+
+// names of own properties in order, indices first
+fn own_keys(object: &JsObject, only_enumerable: bool) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    match object.value {
+        JsClass::array(ref v) => {
+            for i in 0..v.len() {
+                keys.push(i.to_string());
+            }
+        },
+        JsClass::string(ref s) => {
+            for i in 0..s.encode_utf16().count() {
+                keys.push(i.to_string());
+            }
+        },
+        _ => {}
+    }
+    for (k, prop) in object.properties.iter() {
+        if !only_enumerable || prop.enumerable() {
+            keys.push(k.clone());
+        }
+    }
+    return keys;
+}
+
+fn is_accessor(prop: &JsProperty) -> bool {
+    return prop.getter.is_some() || prop.setter.is_some();
+}
+
+// SameValue of ES5 9.12, NaN equals itself and +0 differs from -0
+fn same_value(x: &SharedValue, y: &SharedValue) -> bool {
+    if x.is_number() && y.is_number() {
+        let (a, b) = (x.to_number(), y.to_number());
+        if a.is_nan() && b.is_nan() {
+            return true;
+        }
+        return a == b && a.is_sign_negative() == b.is_sign_negative();
+    }
+    return x.strict_equals(y);
+}
+
+fn same_function(x: &Option<SharedObject>, y: &Option<SharedObject>) -> bool {
+    match (x, y) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+// property descriptor objects, missing fields are None
+struct PropertyDescriptor {
+    value:          Option<SharedValue>,
+    writable:       Option<bool>,
+    getter:         Option<Option<SharedObject>>,
+    setter:         Option<Option<SharedObject>>,
+    enumerable:     Option<bool>,
+    configurable:   Option<bool>,
+}
+
+impl PropertyDescriptor {
+    fn is_accessor(&self) -> bool {
+        return self.getter.is_some() || self.setter.is_some();
+    }
+    fn is_data(&self) -> bool {
+        return self.value.is_some() || self.writable.is_some();
+    }
+}
+
+fn descriptor_function(v: SharedValue, name: &str) -> Result<Option<SharedObject>, JsException> {
+    if v.is_undefined() {
+        return Ok(None);
+    }
+    if v.is_object() && v.get_object().borrow().callable() {
+        return Ok(Some(v.get_object()));
+    }
+    return Err(JsException::new_type_error(format!("Property descriptor's {} must be a function", name)));
+}
+
+// ToPropertyDescriptor of ES5 8.10.5
+fn to_descriptor(rt: &mut JsRuntime, v: &SharedValue) -> Result<PropertyDescriptor, JsException> {
+    if !v.is_object() {
+        return Err(JsException::new_type_error("Property description must be an object".to_string()));
+    }
+    let obj = v.get_object();
+    let mut fields: Vec<Option<SharedValue>> = Vec::new();
+    for name in ["value", "writable", "get", "set", "enumerable", "configurable"].iter() {
+        if obj.borrow().query_property(name).is_some() {
+            fields.push( Some(get_value(rt, obj.clone(), name)?) );
+        } else {
+            fields.push(None);
+        }
+    }
+
+    let desc = PropertyDescriptor {
+        value: fields[0].clone(),
+        writable: fields[1].as_ref().map(|v| v.to_boolean()),
+        getter: match fields[2].clone() {
+            Some(v) => Some(descriptor_function(v, "getter")?),
+            None => None,
+        },
+        setter: match fields[3].clone() {
+            Some(v) => Some(descriptor_function(v, "setter")?),
+            None => None,
+        },
+        enumerable: fields[4].as_ref().map(|v| v.to_boolean()),
+        configurable: fields[5].as_ref().map(|v| v.to_boolean()),
+    };
+    if desc.is_accessor() && desc.is_data() {
+        return Err(JsException::new_type_error("Invalid property descriptor, cannot both specify accessors and a value or writable attribute".to_string()));
+    }
+    return Ok(desc);
+}
+
+// FromPropertyDescriptor of ES5 8.10.4
+fn from_descriptor(rt: &mut JsRuntime, prop: &JsProperty) -> Result<SharedValue, JsException> {
+    let desc = SharedValue::new_vanilla(rt.prototypes.object_prototype.clone());
+    let obj = desc.get_object();
+    if is_accessor(prop) {
+        let getter = prop.getter.clone().map_or(SharedValue::new_undefined(), SharedValue::new_sobject);
+        let setter = prop.setter.clone().map_or(SharedValue::new_undefined(), SharedValue::new_sobject);
+        rt.defproperty(obj.clone(), "get", getter, JS_DEFAULT_ATTR, None, None)?;
+        rt.defproperty(obj.clone(), "set", setter, JS_DEFAULT_ATTR, None, None)?;
+    } else {
+        rt.defproperty(obj.clone(), "value", prop.value.clone(), JS_DEFAULT_ATTR, None, None)?;
+        rt.defproperty(obj.clone(), "writable", SharedValue::new_boolean(prop.attr_writable), JS_DEFAULT_ATTR, None, None)?;
+    }
+    rt.defproperty(obj.clone(), "enumerable", SharedValue::new_boolean(prop.attr_enumerable), JS_DEFAULT_ATTR, None, None)?;
+    rt.defproperty(obj, "configurable", SharedValue::new_boolean(prop.attr_configurable), JS_DEFAULT_ATTR, None, None)?;
+    return Ok(desc);
+}
+
+// elements and length of arrays are kept in the vector, only their values can be defined
+fn define_array_property(object: &mut JsObject, name: &str, desc: PropertyDescriptor) -> Result<(), JsException> {
+    let (writable, _, configurable) = object.elements_attr;
+    let reject = || JsException::new_type_error(format!("Can't redefine property '{}'", name));
+    if desc.is_accessor() {
+        return Err(reject());
+    }
+    if name == "length" {
+        if desc.enumerable == Some(true) || desc.configurable == Some(true) || desc.writable.is_some_and(|w| w != writable) {
+            return Err(reject());
+        }
+        if let Some(v) = desc.value {
+            let n = v.to_number();
+            if !((0.0..4294967296.0).contains(&n) && n.fract() == 0.0) {
+                return Err(JsException::new_range_error("Invalid array length".to_string()));
+            }
+            if n as usize > JS_ARRAY_MAX_LENGTH {
+                return Err(JsException::new_range_error("Array length is too large".to_string()));
+            }
+            let len = object.get_array().len();
+            if n as usize != len && (!writable || (!configurable && (n as usize) < len) || (!object.extensible && (n as usize) > len)) {
+                return Err(reject());
+            }
+            object.get_mut_array().resize_with(n as usize, SharedValue::new_undefined);
+        }
+        return Ok(());
+    }
+
+    let idx = str_to_index(name).unwrap();
+    if desc.enumerable == Some(false) || desc.writable.is_some_and(|w| w != writable) || desc.configurable.is_some_and(|c| c != configurable) {
+        return Err(reject());
+    }
+    let extensible = object.extensible;
+    let v = object.get_mut_array();
+    if idx >= v.len() {
+        if !extensible {
+            return Err(JsException::new_type_error(format!("Can't add property '{}', object is not extensible", name)));
+        }
+        if idx >= JS_ARRAY_MAX_LENGTH {
+            return Err(JsException::new_range_error("Array length is too large".to_string()));
+        }
+        v.resize_with(idx + 1, SharedValue::new_undefined);
+    }
+    if let Some(value) = desc.value {
+        if !writable && !same_value(&v[idx], &value) {
+            return Err(reject());
+        }
+        v[idx] = value;
+    }
+    return Ok(());
+}
+
+// [[DefineOwnProperty]] of ES5 8.12.9, invalid changes are rejected with exceptions
+fn define_own_property(target: &SharedObject, name: &str, mut desc: PropertyDescriptor) -> Result<(), JsException> {
+    // the value may be the target itself, it's copied before the target is borrowed
+    desc.value = desc.value.map(|v| v.duplicate());
+    let mut object = target.borrow_mut();
+    if object.is_array() && (name == "length" || str_to_index(name).is_some()) {
+        return define_array_property(&mut object, name, desc);
+    }
+
+    let current = object.properties.get(name).cloned();
+    let mut prop = match current {
+        None => {
+            if !object.extensible {
+                return Err(JsException::new_type_error(format!("Can't add property '{}', object is not extensible", name)));
+            }
+            let mut prop = JsProperty::new();
+            prop.fill_attr((false, false, false));
+            prop
+        },
+        Some(current) => {
+            if !current.configable() {
+                let reject = JsException::new_type_error(format!("Can't redefine property '{}'", name));
+                if desc.configurable == Some(true) || desc.enumerable.is_some_and(|e| e != current.attr_enumerable) {
+                    return Err(reject);
+                }
+                if (desc.is_accessor() && !is_accessor(&current)) || (desc.is_data() && is_accessor(&current)) {
+                    return Err(reject);
+                }
+                if is_accessor(&current) {
+                    if desc.getter.as_ref().is_some_and(|g| !same_function(g, &current.getter)) ||
+                       desc.setter.as_ref().is_some_and(|s| !same_function(s, &current.setter)) {
+                        return Err(reject);
+                    }
+                } else if !current.attr_writable {
+                    if desc.writable == Some(true) || desc.value.as_ref().is_some_and(|v| !same_value(v, &current.value)) {
+                        return Err(reject);
+                    }
+                }
+            }
+            current
+        }
+    };
+
+    // changing between data and accessor properties keeps only the common attributes
+    if desc.is_accessor() && !is_accessor(&prop) {
+        prop.value = SharedValue::new_undefined();
+        prop.attr_writable = false;
+    } else if desc.is_data() && is_accessor(&prop) {
+        prop.getter = None;
+        prop.setter = None;
+        prop.attr_writable = false;
+    }
+    if let Some(v) = desc.value {
+        prop.value = v;
+    }
+    if let Some(w) = desc.writable {
+        prop.attr_writable = w;
+    }
+    if let Some(g) = desc.getter {
+        prop.getter = g;
+    }
+    if let Some(s) = desc.setter {
+        prop.setter = s;
+    }
+    if let Some(e) = desc.enumerable {
+        prop.attr_enumerable = e;
+    }
+    if let Some(c) = desc.configurable {
+        prop.attr_configurable = c;
+    }
+    object.set_property(name, prop);
+    return Ok(());
+}
+
+fn define_properties(rt: &mut JsRuntime, target: &SharedObject, props: &SharedValue) -> Result<(), JsException> {
+    let props = object_arg(props, "defineProperties")?;
+    let keys = own_keys(&props.borrow(), true);
+
+    // all descriptors are checked before any property is defined
+    let mut descs = Vec::new();
+    for k in keys {
+        let v = get_value(rt, props.clone(), &k)?;
+        descs.push( (k, to_descriptor(rt, &v)?) );
+    }
+    for (k, desc) in descs {
+        define_own_property(target, &k, desc)?;
+    }
+    return Ok(());
+}
+
+fn object_keys(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "keys")?;
+    let keys = own_keys(&target.borrow(), true);
+    let keys = keys.into_iter().map(|k| rt.new_string(k)).collect();
+    return Ok(rt.new_array(keys));
+}
+
+fn object_create(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let proto = &args[0];
+    if !proto.is_object() && !proto.is_null() {
+        return Err(JsException::new_type_error("Object prototype may only be an Object or null".to_string()));
+    }
+    let mut object = JsObject::new();
+    if proto.is_object() {
+        object.__proto__ = Some(proto.get_object());
+    }
+    let target = SharedObject_new(object);
+
+    let props = &args[1];
+    if !props.is_undefined() {
+        define_properties(rt, &target, props)?;
+    }
+    return Ok(SharedValue::new_sobject(target));
+}
+
+fn object_defineproperty(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "defineProperty")?;
+    let name = rt.to_string(args[1].clone())?;
+    let desc = to_descriptor(rt, &args[2])?;
+    define_own_property(&target, &name, desc)?;
+    return Ok(args[0].clone());
+}
+
+fn object_defineproperties(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "defineProperties")?;
+    define_properties(rt, &target, &args[1])?;
+    return Ok(args[0].clone());
+}
+
+fn object_getownpropertydescriptor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "getOwnPropertyDescriptor")?;
+    let name = rt.to_string(args[1].clone())?;
+    let prop = own_property(rt, &target.borrow(), &name);
+    match prop {
+        Some(prop) => from_descriptor(rt, &prop),
+        None => Ok(SharedValue::new_undefined()),
+    }
+}
+
+fn object_getprototypeof(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "getPrototypeOf")?;
+    let proto = target.borrow().__proto__.clone();
+    match proto {
+        Some(proto) => Ok(SharedValue::new_sobject(proto)),
+        None => Ok(SharedValue::new_null()),
+    }
+}
+
+fn object_preventextensions(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let value = args[0].clone();
+    if value.is_object() {
+        value.get_object().borrow_mut().extensible = false;
+    }
+    return Ok(value);
+}
+
+fn object_isextensible(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = object_arg(&args[0], "isExtensible")?;
+    let b = target.borrow().extensible;
+    return Ok(SharedValue::new_boolean(b));
+}
+
+// make all own properties non-configurable, and read-only too when freezing
+fn object_lock(value: &SharedValue, func: &str, freeze: bool) -> Result<SharedValue, JsException> {
+    let target = object_arg(value, func)?;
+    let mut object = target.borrow_mut();
+    for e in object.properties.entries.iter_mut().flatten() {
+        let prop = &mut e.1;
+        prop.attr_configurable = false;
+        if freeze && !is_accessor(prop) {
+            prop.attr_writable = false;
+        }
+    }
+    object.elements_attr.2 = false;
+    if freeze {
+        object.elements_attr.0 = false;
+    }
+    object.extensible = false;
+    return Ok(value.clone());
+}
+
+fn object_islocked(value: &SharedValue, func: &str, frozen: bool) -> Result<SharedValue, JsException> {
+    let target = object_arg(value, func)?;
+    let object = target.borrow();
+    let mut locked = !object.extensible;
+    for (_, prop) in object.properties.iter() {
+        if prop.configable() || (frozen && !is_accessor(prop) && prop.attr_writable) {
+            locked = false;
+        }
+    }
+    if object.is_array() && (object.elements_attr.2 || (frozen && object.elements_attr.0)) {
+        locked = false;
+    }
+    return Ok(SharedValue::new_boolean(locked));
+}
+
+fn object_freeze(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return object_lock(&args[0], "freeze", true);
+}
+
+fn object_seal(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return object_lock(&args[0], "seal", false);
+}
+
+fn object_isfrozen(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return object_islocked(&args[0], "isFrozen", true);
+}
+
+fn object_issealed(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return object_islocked(&args[0], "isSealed", false);
+}
+
+fn object_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    // host objects are tagged with the name of their class
+    let this = rt.this_value();
+    if this.is_object() {
+        if let JsClass::host(ref h) = this.get_object().borrow().value {
+            return Ok(rt.new_string(format!("[object {}]", h.class.name)));
+        }
+    }
+    return Ok(rt.new_string("[object]".to_string()));
+}
+
+fn object_hasownproperty(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let name = rt.to_string(args[0].clone())?;
+    let this = rt.this_value();
+    let b = this.is_object() && own_property(rt, &this.get_object().borrow(), &name).is_some();
+    return Ok(SharedValue::new_boolean(b));
+}
+
+fn object_isprototypeof(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    let value = &args[0];
+    if !this.is_object() || !value.is_object() {
+        return Ok(SharedValue::new_boolean(false));
+    }
+    let this = this.get_object();
+    let mut proto = value.get_object().borrow().__proto__.clone();
+    while let Some(p) = proto {
+        if Rc::ptr_eq(&p, &this) {
+            return Ok(SharedValue::new_boolean(true));
+        }
+        proto = p.borrow().__proto__.clone();
+    }
+    return Ok(SharedValue::new_boolean(false));
+}
+
+fn object_propertyisenumerable(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let name = rt.to_string(args[0].clone())?;
+    let this = rt.this_value();
+    let b = this.is_object() && own_property(rt, &this.get_object().borrow(), &name).is_some_and(|p| p.enumerable());
+    return Ok(SharedValue::new_boolean(b));
+}
+
+fn object_setprototypeof(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let target = &args[0];
+    let proto = &args[1];
+    if !target.is_object() || !proto.is_object() {
+        return Ok(SharedValue::new_undefined());
+    }
+
+    target.get_object().borrow_mut().__proto__ = Some(proto.get_object());
+    return Ok(target.clone());
+}
+
+fn object_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(object_tostring, 0));
+    builtins.insert("hasOwnProperty".to_string(), JsBuiltinFunction::new(object_hasownproperty, 1));
+    builtins.insert("isPrototypeOf".to_string(), JsBuiltinFunction::new(object_isprototypeof, 1));
+    builtins.insert("propertyIsEnumerable".to_string(), JsBuiltinFunction::new(object_propertyisenumerable, 1));
+    return builtins;
+}
+
+fn object_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();   
+    builtins.insert("keys".to_string(), JsBuiltinFunction::new(object_keys, 1));
+    builtins.insert("create".to_string(), JsBuiltinFunction::new(object_create, 2));
+    builtins.insert("defineProperty".to_string(), JsBuiltinFunction::new(object_defineproperty, 3));
+    builtins.insert("defineProperties".to_string(), JsBuiltinFunction::new(object_defineproperties, 2));
+    builtins.insert("getOwnPropertyDescriptor".to_string(), JsBuiltinFunction::new(object_getownpropertydescriptor, 2));
+    builtins.insert("getPrototypeOf".to_string(), JsBuiltinFunction::new(object_getprototypeof, 1));
+    builtins.insert("preventExtensions".to_string(), JsBuiltinFunction::new(object_preventextensions, 1));
+    builtins.insert("isExtensible".to_string(), JsBuiltinFunction::new(object_isextensible, 1));
+    builtins.insert("freeze".to_string(), JsBuiltinFunction::new(object_freeze, 1));
+    builtins.insert("seal".to_string(), JsBuiltinFunction::new(object_seal, 1));
+    builtins.insert("isFrozen".to_string(), JsBuiltinFunction::new(object_isfrozen, 1));
+    builtins.insert("isSealed".to_string(), JsBuiltinFunction::new(object_issealed, 1));
+    builtins.insert("setPrototypeOf".to_string(), JsBuiltinFunction::new(object_setprototypeof, 2));
+    return builtins;
+}
+
+// The String class
+fn string_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let value = args[0].clone();
+    if value.is_string() {
+        return Ok(value);
+    }
+    if value.is_undefined() {
+        return Ok(rt.new_string("".to_string()));
+    }
+    let s = rt.to_string(value)?;
+    return Ok(rt.new_string(s));
+}
+
+fn string_fromcharcode(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut codes: Vec<u16> = Vec::new();
+    for v in args {
+        codes.push( to_uint32(v) as u16 );
+    }
+    return Ok(rt.new_string(String::from_utf16_lossy(&codes)));
+}
+
+fn string_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("fromCharCode".to_string(), JsBuiltinFunction::new(string_fromcharcode, JS_VARARGS));
+    return builtins;
+}
+
+/*
+ * strings are sequences of UTF-16 code units, but they are stored as UTF-8. Parts which split a
+ * surrogate pair can't be stored, e.g. "😀".slice(0, 1) gives U+FFFD, and indexes are found by
+ * walking the string from the start.
+ */
+fn utf16(s: &str) -> Vec<u16> {
+    return s.encode_utf16().collect();
+}
+
+fn from_utf16(v: &[u16]) -> String {
+    return String::from_utf16_lossy(v);
+}
+
+// the code unit at pos without encoding the whole string
+fn utf16_at(s: &str, pos: f64) -> Option<u16> {
+    if pos < 0.0 {
+        return None;
+    }
+    return s.encode_utf16().nth(pos as usize);
+}
+
+fn find_utf16(s: &[u16], search: &[u16], from: usize) -> Option<usize> {
+    if search.len() > s.len() {
+        return None;
+    }
+    for i in from..(s.len() - search.len() + 1) {
+        if &s[i..i+search.len()] == search {
+            return Some(i);
+        }
+    }
+    return None;
+}
+
+fn rfind_utf16(s: &[u16], search: &[u16], from: usize) -> Option<usize> {
+    if search.len() > s.len() {
+        return None;
+    }
+    let mut i = cmp::min(from, s.len() - search.len()) as isize;
+    while i >= 0 {
+        let n = i as usize;
+        if &s[n..n+search.len()] == search {
+            return Some(n);
+        }
+        i = i - 1;
+    }
+    return None;
+}
+
+// ToInteger of ES5, NaN is 0
+fn to_integer(v: &SharedValue) -> f64 {
+    let n = v.to_number();
+    if n.is_nan() {
+        return 0.0;
+    }
+    return n.trunc();
+}
+
+fn to_uint32(v: &SharedValue) -> u32 {
+    let n = v.to_number();
+    if !n.is_finite() {
+        return 0;
+    }
+    return (n.trunc() as i64 as u64 & 0xFFFFFFFF) as u32;
+}
+
+// position argument clamped into [0, len], negative counts from the end when relative
+fn clamp_index(v: &SharedValue, len: usize, relative: bool) -> usize {
+    let mut n = to_integer(v);
+    if relative && n < 0.0 {
+        n = n + len as f64;
+    }
+    if n < 0.0 {
+        return 0;
+    }
+    if n > len as f64 {
+        return len;
+    }
+    return n as usize;
+}
+
+// this value of string methods
+fn this_string(rt: &JsRuntime) -> String {
+    return rt.this_value().to_string();
+}
+
+// call a function from builtins, the stack is restored when an exception is raised
+fn call_function(rt: &mut JsRuntime, func: &SharedValue, thiz: SharedValue, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let top = rt.stack.len();
+    rt.push(func.clone());
+    rt.push(thiz);
+    for v in args {
+        rt.push(v.clone());
+    }
+    if let Err(e) = jscall(rt, args.len()) {
+        rt.stack.truncate(top);
+        return Err(e);
+    }
+    return Ok(rt.stack.pop().unwrap());
+}
+
+fn string_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let value = rt.this_value();
+    if !value.is_string() {
+        return Err(JsException::new_type_error("String.prototype.toString called on none string".to_string()));
+    }
+    return Ok(value.duplicate());
+}
+
+fn string_charat(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    match utf16_at(&s, to_integer(&args[0])) {
+        Some(c) => return Ok(rt.new_string(from_utf16(&[c]))),
+        None => return Ok(rt.new_string("".to_string())),
+    }
+}
+
+fn string_charcodeat(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    match utf16_at(&s, to_integer(&args[0])) {
+        Some(c) => return Ok(SharedValue::new_number(c as f64)),
+        None => return Ok(SharedValue::new_number(std::f64::NAN)),
+    }
+}
+
+fn string_concat(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut result = this_string(rt);
+    for v in args {
+        result.push_str( &v.to_string() );
+    }
+    return Ok(rt.new_string(result));
+}
+
+fn string_indexof(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let search = utf16(&args[0].to_string());
+    let from = clamp_index(&args[1], s.len(), false);
+    match find_utf16(&s, &search, from) {
+        Some(n) => Ok(SharedValue::new_number(n as f64)),
+        None => Ok(SharedValue::new_number(-1.0)),
+    }
+}
+
+fn string_lastindexof(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let search = utf16(&args[0].to_string());
+    let pos = args[1].to_number();
+    let from = if pos.is_nan() {
+        s.len()
+    } else {
+        clamp_index(&args[1], s.len(), false)
+    };
+    match rfind_utf16(&s, &search, from) {
+        Some(n) => Ok(SharedValue::new_number(n as f64)),
+        None => Ok(SharedValue::new_number(-1.0)),
+    }
+}
+
+fn string_localecompare(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    let that = args[0].to_string();
+    let r = match s.cmp(&that) {
+        cmp::Ordering::Less => -1.0,
+        cmp::Ordering::Equal => 0.0,
+        cmp::Ordering::Greater => 1.0,
+    };
+    return Ok(SharedValue::new_number(r));
+}
+
+fn string_match(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let input = this_string(rt);
+    let s = utf16(&input);
+    let obj = to_regexp(rt, &args[0])?;
+
+    let re = obj.borrow().get_regexp();
+    if !re.global {
+        match regexp_match(&obj, &s)? {
+            Some(caps) => return Ok(match_result(rt, &input, &s, &caps)),
+            None => return Ok(SharedValue::new_null()),
+        }
+    }
+
+    // all matches of a global regexp
+    let mut values = Vec::new();
+    let mut pos = 0;
+    while pos <= s.len() {
+        let (start, end) = match re.exec(&s, pos)? {
+            Some(caps) => caps[0].unwrap(),
+            None => break,
+        };
+        values.push( rt.new_string(from_utf16(&s[start..end])) );
+        pos = if end == start { end + 1 } else { end };
+    }
+    set_last_index(&obj, 0);
+    if values.len() == 0 {
+        return Ok(SharedValue::new_null());
+    }
+    return Ok(rt.new_array(values));
+}
+
+// expand $$, $&, $`, $' and $n in the replacement string, caps[0] is the whole match
+fn expand_replacement(replace: &str, s: &[u16], caps: &[Option<(usize, usize)>]) -> String {
+    let (start, end) = caps[0].unwrap();
+    let mut result = String::new();
+    let chars: Vec<char> = replace.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i = i + 1;
+        if c != '$' || i >= chars.len() {
+            result.push(c);
+            continue;
+        }
+        match chars[i] {
+            '$' => {
+                result.push('$');
+            },
+            '&' => {
+                result.push_str( &from_utf16(&s[start..end]) );
+            },
+            '`' => {
+                result.push_str( &from_utf16(&s[..start]) );
+            },
+            '\'' => {
+                result.push_str( &from_utf16(&s[end..]) );
+            },
+            '0'..='9' => {
+                // $nn is taken when it refers to a group, otherwise $n
+                let d1 = chars[i].to_digit(10).unwrap() as usize;
+                let d2 = chars.get(i + 1).and_then(|c| c.to_digit(10)).map(|d| d as usize);
+                let (n, len) = match d2 {
+                    Some(d2) if d1 * 10 + d2 >= 1 && d1 * 10 + d2 < caps.len() => (d1 * 10 + d2, 2),
+                    _ => (d1, 1),
+                };
+                if n == 0 || n >= caps.len() {
+                    result.push('$');
+                    continue;
+                }
+                if let Some((a, b)) = caps[n] {
+                    result.push_str( &from_utf16(&s[a..b]) );
+                }
+                i = i + len;
+                continue;
+            },
+            _ => {
+                result.push('$');
+                continue;
+            }
+        }
+        i = i + 1;
+    }
+    return result;
+}
+
+// the replaced string of one match
+fn replacement(rt: &mut JsRuntime, replace: &SharedValue, input: &str, s: &[u16], caps: &[Option<(usize, usize)>]) -> Result<String, JsException> {
+    if replace.is_object() && replace.get_object().borrow().callable() {
+        let mut args = capture_values(rt, s, caps);
+        args.push( SharedValue::new_number(caps[0].unwrap().0 as f64) );
+        args.push( rt.new_string(input.to_string()) );
+        let r = call_function(rt, replace, SharedValue::new_undefined(), &args)?;
+        return Ok(r.to_string());
+    }
+    return Ok(expand_replacement(&replace.to_string(), s, caps));
+}
+
+// a string pattern replaces the first match only, global regexps replace all matches
+fn string_replace(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this_str = this_string(rt);
+    let s = utf16(&this_str);
+    let pattern = &args[0];
+    let replace = &args[1];
+
+    let mut matches: Vec<Vec<Option<(usize, usize)>>> = Vec::new();
+    if pattern.is_object() && pattern.get_object().borrow().is_regexp() {
+        let obj = pattern.get_object();
+        let re = obj.borrow().get_regexp();
+        if re.global {
+            let mut pos = 0;
+            while pos <= s.len() {
+                let caps = match re.exec(&s, pos)? {
+                    Some(caps) => caps,
+                    None => break,
+                };
+                let (start, end) = caps[0].unwrap();
+                pos = if end == start { end + 1 } else { end };
+                matches.push(caps);
+            }
+            set_last_index(&obj, 0);
+        } else if let Some(caps) = re.exec(&s, 0)? {
+            matches.push(caps);
+        }
+    } else {
+        let search = utf16(&pattern.to_string());
+        if let Some(start) = find_utf16(&s, &search, 0) {
+            matches.push( vec![Some((start, start + search.len()))] );
+        }
+    }
+
+    let mut result = String::new();
+    let mut last = 0;
+    for caps in matches {
+        let (start, end) = caps[0].unwrap();
+        let new_str = replacement(rt, replace, &this_str, &s, &caps)?;
+        result.push_str( &from_utf16(&s[last..start]) );
+        result.push_str(&new_str);
+        last = end;
+    }
+    result.push_str( &from_utf16(&s[last..]) );
+    return Ok(rt.new_string(result));
+}
+
+// the index of the first match, lastIndex and global are ignored
+fn string_search(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let obj = to_regexp(rt, &args[0])?;
+    let re = obj.borrow().get_regexp();
+    match re.exec(&s, 0)? {
+        Some(caps) => Ok(SharedValue::new_number(caps[0].unwrap().0 as f64)),
+        None => Ok(SharedValue::new_number(-1.0)),
+    }
+}
+
+fn string_slice(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let start = clamp_index(&args[0], s.len(), true);
+    let end = if args[1].is_undefined() {
+        s.len()
+    } else {
+        clamp_index(&args[1], s.len(), true)
+    };
+    if start >= end {
+        return Ok(rt.new_string("".to_string()));
+    }
+    return Ok(rt.new_string(from_utf16(&s[start..end])));
+}
+
+// split by a regexp separator, captured groups are spliced into the result
+fn split_regexp(rt: &JsRuntime, re: &RegExp, s: &[u16], limit: usize, parts: &mut Vec<SharedValue>) -> Result<(), JsException> {
+    if s.len() == 0 {
+        if re.exec(s, 0)?.is_none() {
+            parts.push( rt.new_string(String::new()) );
+        }
+        return Ok(());
+    }
+
+    let mut p = 0;
+    let mut q = 0;
+    while q < s.len() {
+        let caps = match re.exec(s, q)? {
+            Some(caps) => caps,
+            None => break,
+        };
+        let (start, end) = caps[0].unwrap();
+        if start >= s.len() {
+            break;
+        }
+        // an empty match at the end of the last separator
+        if end == p {
+            q = start + 1;
+            continue;
+        }
+        parts.push( rt.new_string(from_utf16(&s[p..start])) );
+        if parts.len() == limit {
+            return Ok(());
+        }
+        for v in capture_values(rt, s, &caps[1..]) {
+            parts.push(v);
+            if parts.len() == limit {
+                return Ok(());
+            }
+        }
+        p = end;
+        q = p;
+    }
+    parts.push( rt.new_string(from_utf16(&s[p..])) );
+    return Ok(());
+}
+
+fn string_split(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let limit = if args[1].is_undefined() {
+        u32::MAX as usize
+    } else {
+        to_uint32(&args[1]) as usize
+    };
+
+    let mut parts: Vec<SharedValue> = Vec::new();
+    if limit == 0 {
+        return Ok(rt.new_array(parts));
+    }
+    if args[0].is_undefined() {
+        parts.push( rt.new_string(from_utf16(&s)) );
+        return Ok(rt.new_array(parts));
+    }
+
+    let separator = &args[0];
+    if separator.is_object() && separator.get_object().borrow().is_regexp() {
+        let re = separator.get_object().borrow().get_regexp();
+        split_regexp(rt, &re, &s, limit, &mut parts)?;
+        return Ok(rt.new_array(parts));
+    }
+
+    let sep = utf16(&separator.to_string());
+    if sep.len() == 0 {
+        for i in 0..cmp::min(s.len(), limit) {
+            parts.push( rt.new_string(from_utf16(&s[i..i+1])) );
+        }
+    } else {
+        let mut begin = 0;
+        while parts.len() < limit {
+            match find_utf16(&s, &sep, begin) {
+                Some(n) => {
+                    parts.push( rt.new_string(from_utf16(&s[begin..n])) );
+                    begin = n + sep.len();
+                },
+                None => {
+                    parts.push( rt.new_string(from_utf16(&s[begin..])) );
+                    break;
+                }
+            }
+        }
+    }
+    return Ok(rt.new_array(parts));
+}
+
+fn string_substring(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let start = clamp_index(&args[0], s.len(), false);
+    let end = if args[1].is_undefined() {
+        s.len()
+    } else {
+        clamp_index(&args[1], s.len(), false)
+    };
+    let (from, to) = (cmp::min(start, end), cmp::max(start, end));
+    return Ok(rt.new_string(from_utf16(&s[from..to])));
+}
+
+fn string_substr(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = utf16(&this_string(rt));
+    let start = clamp_index(&args[0], s.len(), true);
+    let len = if args[1].is_undefined() {
+        s.len() as f64
+    } else {
+        to_integer(&args[1])
+    };
+    let end = (s.len() as f64).min(start as f64 + len.max(0.0)) as usize;
+    if start >= end {
+        return Ok(rt.new_string("".to_string()));
+    }
+    return Ok(rt.new_string(from_utf16(&s[start..end])));
+}
+
+fn string_tolowercase(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    return Ok(rt.new_string(s.to_lowercase()));
+}
+
+fn string_touppercase(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    return Ok(rt.new_string(s.to_uppercase()));
+}
+
+fn string_trim(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = this_string(rt);
+    let r = s.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    return Ok(rt.new_string(r.to_string()));
+}
+
+fn string_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(string_tostring, 0));
+    builtins.insert("valueOf".to_string(), JsBuiltinFunction::new(string_tostring, 0));
+    builtins.insert("charAt".to_string(), JsBuiltinFunction::new(string_charat, 1));
+    builtins.insert("charCodeAt".to_string(), JsBuiltinFunction::new(string_charcodeat, 1));
+    builtins.insert("concat".to_string(), JsBuiltinFunction::new(string_concat, JS_VARARGS));
+    builtins.insert("indexOf".to_string(), JsBuiltinFunction::new(string_indexof, 2));
+    builtins.insert("lastIndexOf".to_string(), JsBuiltinFunction::new(string_lastindexof, 2));
+    builtins.insert("localeCompare".to_string(), JsBuiltinFunction::new(string_localecompare, 1));
+    builtins.insert("match".to_string(), JsBuiltinFunction::new(string_match, 1));
+    builtins.insert("replace".to_string(), JsBuiltinFunction::new(string_replace, 2));
+    builtins.insert("search".to_string(), JsBuiltinFunction::new(string_search, 1));
+    builtins.insert("slice".to_string(), JsBuiltinFunction::new(string_slice, 2));
+    builtins.insert("split".to_string(), JsBuiltinFunction::new(string_split, 2));
+    builtins.insert("substring".to_string(), JsBuiltinFunction::new(string_substring, 2));
+    builtins.insert("substr".to_string(), JsBuiltinFunction::new(string_substr, 2));
+    builtins.insert("toLowerCase".to_string(), JsBuiltinFunction::new(string_tolowercase, 0));
+    builtins.insert("toLocaleLowerCase".to_string(), JsBuiltinFunction::new(string_tolowercase, 0));
+    builtins.insert("toUpperCase".to_string(), JsBuiltinFunction::new(string_touppercase, 0));
+    builtins.insert("toLocaleUpperCase".to_string(), JsBuiltinFunction::new(string_touppercase, 0));
+    builtins.insert("trim".to_string(), JsBuiltinFunction::new(string_trim, 0));
+    return builtins;
+}
+
+// The RegExp class
+fn regexp_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let pattern = &args[0];
+    let flags = &args[1];
+
+    let (source, flags) = if pattern.is_object() && pattern.get_object().borrow().is_regexp() {
+        if !flags.is_undefined() {
+            return Err(JsException::new_type_error("Cannot supply flags when constructing one RegExp from another".to_string()));
+        }
+        let re = pattern.get_object().borrow().get_regexp();
+        (re.source.clone(), re.flags())
+    } else {
+        let source = if pattern.is_undefined() { String::new() } else { pattern.to_string() };
+        let flags = if flags.is_undefined() { String::new() } else { flags.to_string() };
+        (source, flags)
+    };
+
+    return rt.new_regexp(&source, &flags);
+}
+
+fn this_regexp(this: &SharedValue) -> Result<SharedObject, JsException> {
+    if this.is_object() && this.get_object().borrow().is_regexp() {
+        return Ok(this.get_object());
+    }
+    return Err(JsException::new_type_error("RegExp method called on none regexp".to_string()));
+}
+
+fn get_last_index(obj: &SharedObject) -> f64 {
+    match obj.borrow().properties.get("lastIndex") {
+        Some(prop) => to_integer(&prop.value),
+        None => 0.0,
+    }
+}
+
+fn set_last_index(obj: &SharedObject, n: usize) {
+    if let Some(prop) = obj.borrow_mut().properties.get_mut("lastIndex") {
+        prop.value = SharedValue::new_number(n as f64);
+    }
+}
+
+// RegExp.prototype.exec without building the result, lastIndex is used and updated for global regexps
+fn regexp_match(obj: &SharedObject, s: &[u16]) -> Result<Option<Captures>, JsException> {
+    let re = obj.borrow().get_regexp();
+    let mut start = 0;
+    if re.global {
+        let i = get_last_index(obj);
+        if i < 0.0 || i > s.len() as f64 {
+            set_last_index(obj, 0);
+            return Ok(None);
+        }
+        start = i as usize;
+    }
+    match re.exec(s, start)? {
+        Some(caps) => {
+            if re.global {
+                set_last_index(obj, caps[0].unwrap().1);
+            }
+            return Ok(Some(caps));
+        },
+        None => {
+            if re.global {
+                set_last_index(obj, 0);
+            }
+            return Ok(None);
+        }
+    }
+}
+
+// captured substrings, undefined for groups not participating
+fn capture_values(rt: &JsRuntime, s: &[u16], caps: &[Option<(usize, usize)>]) -> Vec<SharedValue> {
+    let mut values = Vec::new();
+    for cap in caps {
+        match cap {
+            Some((start, end)) => values.push( rt.new_string(from_utf16(&s[*start..*end])) ),
+            None => values.push( SharedValue::new_undefined() ),
+        }
+    }
+    return values;
+}
+
+// the array returned by exec and match, with index and input
+fn match_result(rt: &mut JsRuntime, input: &str, s: &[u16], caps: &[Option<(usize, usize)>]) -> SharedValue {
+    let values = capture_values(rt, s, caps);
+    let result = rt.new_array(values);
+    let obj = result.get_object();
+    let index = caps[0].unwrap().0;
+    let _ = rt.defproperty(obj.clone(), "index", SharedValue::new_number(index as f64), JS_DEFAULT_ATTR, None, None);
+    let _ = rt.defproperty(obj, "input", rt.new_string(input.to_string()), JS_DEFAULT_ATTR, None, None);
+    return result;
+}
+
+// the argument of match and search, strings are converted to regexps
+fn to_regexp(rt: &mut JsRuntime, v: &SharedValue) -> Result<SharedObject, JsException> {
+    if v.is_object() && v.get_object().borrow().is_regexp() {
+        return Ok(v.get_object());
+    }
+    let source = if v.is_undefined() { String::new() } else { v.to_string() };
+    let re = rt.new_regexp(&source, "")?;
+    return Ok(re.get_object());
+}
+
+fn regexp_exec(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let obj = this_regexp(&rt.this_value())?;
+    let input = args[0].to_string();
+    let s = utf16(&input);
+    match regexp_match(&obj, &s)? {
+        Some(caps) => Ok(match_result(rt, &input, &s, &caps)),
+        None => Ok(SharedValue::new_null()),
+    }
+}
+
+fn regexp_test(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let obj = this_regexp(&rt.this_value())?;
+    let s = utf16(&args[0].to_string());
+    let found = regexp_match(&obj, &s)?.is_some();
+    return Ok(SharedValue::new_boolean(found));
+}
+
+fn regexp_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let obj = this_regexp(&rt.this_value())?;
+    let s = SharedValue::new_sobject(obj).to_string();
+    return Ok(rt.new_string(s));
+}
+
+fn regexp_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("exec".to_string(), JsBuiltinFunction::new(regexp_exec, 1));
+    builtins.insert("test".to_string(), JsBuiltinFunction::new(regexp_test, 1));
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(regexp_tostring, 0));
+    return builtins;
+}
+
+// The Date class
+fn new_date(rt: &JsRuntime, t: f64) -> SharedValue {
+    let obj = JsObject::new_with(rt.prototypes.date_prototype.clone(), JsClass::date(t));
+    return SharedValue::new_object(obj);
+}
+
+// time value from components in local time, Date(y, m, ...) and Date.UTC
+fn date_from_components(args: &[SharedValue]) -> f64 {
+    let mut fields = [f64::NAN, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    for (i, v) in args.iter().take(7).enumerate() {
+        fields[i] = v.to_number();
+    }
+    let mut year = fields[0];
+    if !year.is_nan() && year.trunc() >= 0.0 && year.trunc() <= 99.0 {
+        year = 1900.0 + year.trunc();
+    }
+    let day = make_day(year, fields[1], fields[2]);
+    return make_date(day, make_time(fields[3], fields[4], fields[5], fields[6]));
+}
+
+fn date_parse_string(rt: &JsRuntime, s: &str) -> f64 {
+    match parse_date(s) {
+        Some((t, true)) => return time_clip(t),
+        Some((t, false)) => return time_clip(rt.utc_time(t)),
+        None => return f64::NAN,
+    }
+}
+
+fn date_to_string(rt: &JsRuntime, t: f64) -> String {
+    if t.is_nan() {
+        return "Invalid Date".to_string();
+    }
+    let local = rt.local_time(t);
+    return format!("{} {}", date_string(local), time_string(local, local - t));
+}
+
+// new Date() creates a date, Date() returns the current time as a string
+fn date_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let now = rt.clock.now();
+    if !rt.this_value().is_null() {
+        let s = date_to_string(rt, time_clip(now));
+        return Ok(rt.new_string(s));
+    }
+
+    let t = if args.len() == 0 {
+        time_clip(now)
+    } else if args.len() == 1 {
+        let v = &args[0];
+        if v.is_object() && v.get_object().borrow().is_date() {
+            v.get_object().borrow().get_date()
+        } else if v.is_string() {
+            date_parse_string(rt, &v.to_string())
+        } else {
+            time_clip(v.to_number())
+        }
+    } else {
+        time_clip( rt.utc_time(date_from_components(args)) )
+    };
+    return Ok(new_date(rt, t));
+}
+
+fn date_now(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let now = time_clip(rt.clock.now());
+    return Ok(SharedValue::new_number(now));
+}
+
+fn date_parse(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = args[0].to_string();
+    let t = date_parse_string(rt, &s);
+    return Ok(SharedValue::new_number(t));
+}
+
+fn date_utc(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return Ok(SharedValue::new_number( time_clip(date_from_components(args)) ));
+}
+
+fn date_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("now".to_string(), JsBuiltinFunction::new(date_now, 0));
+    builtins.insert("parse".to_string(), JsBuiltinFunction::new(date_parse, 1));
+    builtins.insert("UTC".to_string(), JsBuiltinFunction::new(date_utc, JS_VARARGS));
+    return builtins;
+}
+
+fn this_date(this: &SharedValue) -> Result<SharedObject, JsException> {
+    if this.is_object() && this.get_object().borrow().is_date() {
+        return Ok(this.get_object());
+    }
+    return Err(JsException::new_type_error("this is not a Date object".to_string()));
+}
+
+// the time value of this
+fn this_time(rt: &JsRuntime) -> Result<f64, JsException> {
+    return Ok(this_date(&rt.this_value())?.borrow().get_date());
+}
+
+// getters of the time fields, NaN for invalid dates
+macro_rules! date_getter {
+    ($name:ident, $local:expr, $field:expr) => {
+        fn $name(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+            let t = this_time(rt)?;
+            if t.is_nan() {
+                return Ok(SharedValue::new_number(f64::NAN));
+            }
+            let t = if $local { rt.local_time(t) } else { t };
+            return Ok(SharedValue::new_number( $field(t) ));
+        }
+    }
+}
+
+date_getter!(date_getfullyear, true, year_from_time);
+date_getter!(date_getmonth, true, month_from_time);
+date_getter!(date_getdate, true, date_from_time);
+date_getter!(date_getday, true, week_day);
+date_getter!(date_gethours, true, hour_from_time);
+date_getter!(date_getminutes, true, min_from_time);
+date_getter!(date_getseconds, true, sec_from_time);
+date_getter!(date_getmilliseconds, true, ms_from_time);
+date_getter!(date_getutcfullyear, false, year_from_time);
+date_getter!(date_getutcmonth, false, month_from_time);
+date_getter!(date_getutcdate, false, date_from_time);
+date_getter!(date_getutcday, false, week_day);
+date_getter!(date_getutchours, false, hour_from_time);
+date_getter!(date_getutcminutes, false, min_from_time);
+date_getter!(date_getutcseconds, false, sec_from_time);
+date_getter!(date_getutcmilliseconds, false, ms_from_time);
+
+fn date_gettime(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    return Ok(SharedValue::new_number(t));
+}
+
+fn date_gettimezoneoffset(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if t.is_nan() {
+        return Ok(SharedValue::new_number(f64::NAN));
+    }
+    return Ok(SharedValue::new_number( (t - rt.local_time(t)) / 60000.0 ));
+}
+
+fn date_settime(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let obj = this_date(&rt.this_value())?;
+    let t = time_clip(args[0].to_number());
+    obj.borrow_mut().value = JsClass::date(t);
+    return Ok(SharedValue::new_number(t));
+}
+
+// setters replace the fields from `first` of year, month, date, hours, minutes, seconds and milliseconds,
+// date setters go down to the date, time setters to the milliseconds
+fn date_set_fields(rt: &mut JsRuntime, args: &[SharedValue], first: usize, local: bool) -> Result<SharedValue, JsException> {
+    let obj = this_date(&rt.this_value())?;
+    let mut t = obj.borrow().get_date();
+    if t.is_nan() {
+        // only setFullYear works on invalid dates
+        if first != 0 {
+            return Ok(SharedValue::new_number(f64::NAN));
+        }
+        t = 0.0;
+    } else if local {
+        t = rt.local_time(t);
+    }
+
+    let mut fields = [year_from_time(t), month_from_time(t), date_from_time(t),
+        hour_from_time(t), min_from_time(t), sec_from_time(t), ms_from_time(t)];
+    let last = if first <= 2 { 2 } else { 6 };
+    for i in first..=last {
+        if i == first {
+            fields[i] = args.first().map_or(f64::NAN, |v| v.to_number());
+        } else if let Some(v) = args.get(i - first) {
+            fields[i] = v.to_number();
+        }
+    }
+
+    let mut t = make_date(make_day(fields[0], fields[1], fields[2]), make_time(fields[3], fields[4], fields[5], fields[6]));
+    if local {
+        t = rt.utc_time(t);
+    }
+    let t = time_clip(t);
+    obj.borrow_mut().value = JsClass::date(t);
+    return Ok(SharedValue::new_number(t));
+}
+
+macro_rules! date_setter {
+    ($name:ident, $first:expr, $local:expr) => {
+        fn $name(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+            return date_set_fields(rt, args, $first, $local);
+        }
+    }
+}
+
+date_setter!(date_setfullyear, 0, true);
+date_setter!(date_setmonth, 1, true);
+date_setter!(date_setdate, 2, true);
+date_setter!(date_sethours, 3, true);
+date_setter!(date_setminutes, 4, true);
+date_setter!(date_setseconds, 5, true);
+date_setter!(date_setmilliseconds, 6, true);
+date_setter!(date_setutcfullyear, 0, false);
+date_setter!(date_setutcmonth, 1, false);
+date_setter!(date_setutcdate, 2, false);
+date_setter!(date_setutchours, 3, false);
+date_setter!(date_setutcminutes, 4, false);
+date_setter!(date_setutcseconds, 5, false);
+date_setter!(date_setutcmilliseconds, 6, false);
+
+fn date_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    let s = date_to_string(rt, t);
+    return Ok(rt.new_string(s));
+}
+
+fn date_todatestring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if t.is_nan() {
+        return Ok(rt.new_string("Invalid Date".to_string()));
+    }
+    let s = date_string(rt.local_time(t));
+    return Ok(rt.new_string(s));
+}
+
+fn date_totimestring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if t.is_nan() {
+        return Ok(rt.new_string("Invalid Date".to_string()));
+    }
+    let local = rt.local_time(t);
+    return Ok(rt.new_string(time_string(local, local - t)));
+}
+
+fn date_toutcstring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if t.is_nan() {
+        return Ok(rt.new_string("Invalid Date".to_string()));
+    }
+    return Ok(rt.new_string(utc_string(t)));
+}
+
+fn date_toisostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if t.is_nan() {
+        return Err(JsException::new_range_error("Invalid time value".to_string()));
+    }
+    return Ok(rt.new_string(iso_string(t)));
+}
+
+fn date_tojson(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let t = this_time(rt)?;
+    if !t.is_finite() {
+        return Ok(SharedValue::new_null());
+    }
+    return Ok(rt.new_string(iso_string(t)));
+}
+
+fn date_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("getTime".to_string(), JsBuiltinFunction::new(date_gettime, 0));
+    builtins.insert("valueOf".to_string(), JsBuiltinFunction::new(date_gettime, 0));
+    builtins.insert("getTimezoneOffset".to_string(), JsBuiltinFunction::new(date_gettimezoneoffset, 0));
+    builtins.insert("setTime".to_string(), JsBuiltinFunction::new(date_settime, 1));
+
+    builtins.insert("getFullYear".to_string(), JsBuiltinFunction::new(date_getfullyear, 0));
+    builtins.insert("getMonth".to_string(), JsBuiltinFunction::new(date_getmonth, 0));
+    builtins.insert("getDate".to_string(), JsBuiltinFunction::new(date_getdate, 0));
+    builtins.insert("getDay".to_string(), JsBuiltinFunction::new(date_getday, 0));
+    builtins.insert("getHours".to_string(), JsBuiltinFunction::new(date_gethours, 0));
+    builtins.insert("getMinutes".to_string(), JsBuiltinFunction::new(date_getminutes, 0));
+    builtins.insert("getSeconds".to_string(), JsBuiltinFunction::new(date_getseconds, 0));
+    builtins.insert("getMilliseconds".to_string(), JsBuiltinFunction::new(date_getmilliseconds, 0));
+    builtins.insert("getUTCFullYear".to_string(), JsBuiltinFunction::new(date_getutcfullyear, 0));
+    builtins.insert("getUTCMonth".to_string(), JsBuiltinFunction::new(date_getutcmonth, 0));
+    builtins.insert("getUTCDate".to_string(), JsBuiltinFunction::new(date_getutcdate, 0));
+    builtins.insert("getUTCDay".to_string(), JsBuiltinFunction::new(date_getutcday, 0));
+    builtins.insert("getUTCHours".to_string(), JsBuiltinFunction::new(date_getutchours, 0));
+    builtins.insert("getUTCMinutes".to_string(), JsBuiltinFunction::new(date_getutcminutes, 0));
+    builtins.insert("getUTCSeconds".to_string(), JsBuiltinFunction::new(date_getutcseconds, 0));
+    builtins.insert("getUTCMilliseconds".to_string(), JsBuiltinFunction::new(date_getutcmilliseconds, 0));
+
+    builtins.insert("setFullYear".to_string(), JsBuiltinFunction::new(date_setfullyear, JS_VARARGS));
+    builtins.insert("setMonth".to_string(), JsBuiltinFunction::new(date_setmonth, JS_VARARGS));
+    builtins.insert("setDate".to_string(), JsBuiltinFunction::new(date_setdate, JS_VARARGS));
+    builtins.insert("setHours".to_string(), JsBuiltinFunction::new(date_sethours, JS_VARARGS));
+    builtins.insert("setMinutes".to_string(), JsBuiltinFunction::new(date_setminutes, JS_VARARGS));
+    builtins.insert("setSeconds".to_string(), JsBuiltinFunction::new(date_setseconds, JS_VARARGS));
+    builtins.insert("setMilliseconds".to_string(), JsBuiltinFunction::new(date_setmilliseconds, JS_VARARGS));
+    builtins.insert("setUTCFullYear".to_string(), JsBuiltinFunction::new(date_setutcfullyear, JS_VARARGS));
+    builtins.insert("setUTCMonth".to_string(), JsBuiltinFunction::new(date_setutcmonth, JS_VARARGS));
+    builtins.insert("setUTCDate".to_string(), JsBuiltinFunction::new(date_setutcdate, JS_VARARGS));
+    builtins.insert("setUTCHours".to_string(), JsBuiltinFunction::new(date_setutchours, JS_VARARGS));
+    builtins.insert("setUTCMinutes".to_string(), JsBuiltinFunction::new(date_setutcminutes, JS_VARARGS));
+    builtins.insert("setUTCSeconds".to_string(), JsBuiltinFunction::new(date_setutcseconds, JS_VARARGS));
+    builtins.insert("setUTCMilliseconds".to_string(), JsBuiltinFunction::new(date_setutcmilliseconds, JS_VARARGS));
+
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(date_tostring, 0));
+    builtins.insert("toDateString".to_string(), JsBuiltinFunction::new(date_todatestring, 0));
+    builtins.insert("toTimeString".to_string(), JsBuiltinFunction::new(date_totimestring, 0));
+    builtins.insert("toLocaleString".to_string(), JsBuiltinFunction::new(date_tostring, 0));
+    builtins.insert("toLocaleDateString".to_string(), JsBuiltinFunction::new(date_todatestring, 0));
+    builtins.insert("toLocaleTimeString".to_string(), JsBuiltinFunction::new(date_totimestring, 0));
+    builtins.insert("toUTCString".to_string(), JsBuiltinFunction::new(date_toutcstring, 0));
+    builtins.insert("toGMTString".to_string(), JsBuiltinFunction::new(date_toutcstring, 0));
+    builtins.insert("toISOString".to_string(), JsBuiltinFunction::new(date_toisostring, 0));
+    builtins.insert("toJSON".to_string(), JsBuiltinFunction::new(date_tojson, 1));
+    return builtins;
+}
+
+// The Number class, numbers are primitive values without wrapper objects
+fn number_constructor(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = match args.first() {
+        Some(v) => v.to_number(),
+        None => 0.0,
+    };
+    return Ok(SharedValue::new_number(n));
+}
+
+// this value of number methods
+fn this_number(this: &SharedValue) -> Result<f64, JsException> {
+    if this.is_number() {
+        return Ok(this.to_number());
+    }
+    return Err(JsException::new_type_error("Number method called on none number".to_string()));
+}
+
+fn number_tostring(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = this_number(&rt.this_value())?;
+    let radix = if args[0].is_undefined() {
+        10.0
+    } else {
+        to_integer(&args[0])
+    };
+    if !(2.0..=36.0).contains(&radix) {
+        return Err(JsException::new_range_error("toString() radix must be between 2 and 36".to_string()));
+    }
+    if radix == 10.0 {
+        return Ok(rt.new_string(number_to_string(n)));
+    }
+    return Ok(rt.new_string(number_to_radix(n, radix as u32)));
+}
+
+fn number_valueof(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = this_number(&rt.this_value())?;
+    return Ok(SharedValue::new_number(n));
+}
+
+fn number_tofixed(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = this_number(&rt.this_value())?;
+    let f = to_integer(&args[0]);
+    if !(0.0..=20.0).contains(&f) {
+        return Err(JsException::new_range_error("toFixed() digits must be between 0 and 20".to_string()));
+    }
+    if !n.is_finite() || n.abs() >= 1e21 {
+        return Ok(rt.new_string(number_to_string(n)));
+    }
+    return Ok(rt.new_string(number_to_fixed(n, f as usize)));
+}
+
+fn number_toexponential(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = this_number(&rt.this_value())?;
+    let f = to_integer(&args[0]);
+    if !n.is_finite() {
+        return Ok(rt.new_string(number_to_string(n)));
+    }
+    if !(0.0..=20.0).contains(&f) {
+        return Err(JsException::new_range_error("toExponential() digits must be between 0 and 20".to_string()));
+    }
+    let fraction = if args[0].is_undefined() {
+        None
+    } else {
+        Some(f as usize)
+    };
+    return Ok(rt.new_string(number_to_exponential(n, fraction)));
+}
+
+fn number_toprecision(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = this_number(&rt.this_value())?;
+    if args[0].is_undefined() || !n.is_finite() {
+        return Ok(rt.new_string(number_to_string(n)));
+    }
+    let p = to_integer(&args[0]);
+    if !(1.0..=21.0).contains(&p) {
+        return Err(JsException::new_range_error("toPrecision() argument must be between 1 and 21".to_string()));
+    }
+    return Ok(rt.new_string(number_to_precision(n, p as usize)));
+}
+
+fn number_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(number_tostring, 1));
+    builtins.insert("toLocaleString".to_string(), JsBuiltinFunction::new(number_tostring, 1));
+    builtins.insert("valueOf".to_string(), JsBuiltinFunction::new(number_valueof, 0));
+    builtins.insert("toFixed".to_string(), JsBuiltinFunction::new(number_tofixed, 1));
+    builtins.insert("toExponential".to_string(), JsBuiltinFunction::new(number_toexponential, 1));
+    builtins.insert("toPrecision".to_string(), JsBuiltinFunction::new(number_toprecision, 1));
+    return builtins;
+}
+
+// The Boolean class, booleans are primitive values as numbers
+fn boolean_constructor(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return Ok(SharedValue::new_boolean(args[0].to_boolean()));
+}
+
+// this value of boolean methods
+fn this_boolean(this: &SharedValue) -> Result<bool, JsException> {
+    if this.is_boolean() {
+        return Ok(this.to_boolean());
+    }
+    return Err(JsException::new_type_error("Boolean method called on none boolean".to_string()));
+}
+
+fn boolean_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let b = this_boolean(&rt.this_value())?;
+    return Ok(rt.new_string(b.to_string()));
+}
+
+fn boolean_valueof(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let b = this_boolean(&rt.this_value())?;
+    return Ok(SharedValue::new_boolean(b));
+}
+
+fn boolean_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(boolean_tostring, 0));
+    builtins.insert("valueOf".to_string(), JsBuiltinFunction::new(boolean_valueof, 0));
+    return builtins;
+}
+
+// global functions for numbers
+fn global_parseint(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = args[0].to_string();
+    let mut radix = to_integer(&args[1]) as i64;
+
+    let s = s.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+    let (sign, mut s) = if let Some(r) = s.strip_prefix('-') {
+        (-1.0, r)
+    } else if let Some(r) = s.strip_prefix('+') {
+        (1.0, r)
+    } else {
+        (1.0, s)
+    };
+    if (radix == 0 || radix == 16) && (s.starts_with("0x") || s.starts_with("0X")) {
+        s = &s[2..];
+        radix = 16;
+    }
+    if radix == 0 {
+        radix = 10;
     }
-    rt.push( SharedValue::new_vanilla(rt.prototypes.object_prototype.clone()) );
+    if !(2..=36).contains(&radix) {
+        return Ok(SharedValue::new_number(f64::NAN));
+    }
+
+    let mut v: f64 = 0.0;
+    let mut count = 0;
+    for c in s.chars() {
+        match c.to_digit(radix as u32) {
+            Some(d) => {
+                v = v * radix as f64 + d as f64;
+                count = count + 1;
+            },
+            None => break,
+        }
+    }
+    if count == 0 {
+        return Ok(SharedValue::new_number(f64::NAN));
+    }
+    return Ok(SharedValue::new_number(sign * v));
 }
 
-fn object_preventextensions(rt: &mut JsRuntime) {
-    let value = rt.top(-1);
-    if value.is_object() {
-        value.get_object().borrow_mut().extensible = false;
+fn global_parsefloat(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let s = args[0].to_string();
+    let s = s.trim_start_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+
+    // the longest prefix of a decimal literal
+    let bytes = s.as_bytes();
+    let mut end = 0;
+    if end < bytes.len() && (bytes[end] == b'+' || bytes[end] == b'-') {
+        end = end + 1;
     }
-    rt.push(value);
+    if s[end..].starts_with("Infinity") {
+        return Ok(SharedValue::new_number( string_to_number(&s[..end + 8]) ));
+    }
+    let mut digits = 0;
+    while end < bytes.len() && bytes[end].is_ascii_digit() {
+        end = end + 1;
+        digits = digits + 1;
+    }
+    if end < bytes.len() && bytes[end] == b'.' {
+        end = end + 1;
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end = end + 1;
+            digits = digits + 1;
+        }
+    }
+    if digits == 0 {
+        return Ok(SharedValue::new_number(f64::NAN));
+    }
+    if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+        let mut e = end + 1;
+        if e < bytes.len() && (bytes[e] == b'+' || bytes[e] == b'-') {
+            e = e + 1;
+        }
+        if e < bytes.len() && bytes[e].is_ascii_digit() {
+            while e < bytes.len() && bytes[e].is_ascii_digit() {
+                e = e + 1;
+            }
+            end = e;
+        }
+    }
+    return Ok(SharedValue::new_number( string_to_number(&s[..end]) ));
 }
 
-fn object_tostring(rt: &mut JsRuntime)  {
-    rt.push_string( "[object]".to_string() );
+fn global_isnan(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = args[0].to_number();
+    return Ok(SharedValue::new_boolean(n.is_nan()));
 }
 
-fn object_setprototypeof(rt: &mut JsRuntime) {
-    let target = rt.top(-2);
-    if !target.is_object() {
-        rt.push_undefined();    
-        return;
-    }
+fn global_isfinite(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let n = args[0].to_number();
+    return Ok(SharedValue::new_boolean(n.is_finite()));
+}
+
+fn global_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("parseInt".to_string(), JsBuiltinFunction::new(global_parseint, 2));
+    builtins.insert("parseFloat".to_string(), JsBuiltinFunction::new(global_parsefloat, 1));
+    builtins.insert("isNaN".to_string(), JsBuiltinFunction::new(global_isnan, 1));
+    builtins.insert("isFinite".to_string(), JsBuiltinFunction::new(global_isfinite, 1));
+    return builtins;
+}
 
-    let proto = rt.top(-1);
-    if !proto.is_object() {
-        rt.push_undefined();
-        return;    
+// The Array class
+fn array_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut values: Vec<SharedValue> = Vec::new();
+    if args.len() == 1 && args[0].is_number() {
+        let n = args[0].to_number();
+        if !(n >= 0.0 && n < 4294967296.0 && n.fract() == 0.0) {
+            return Err(JsException::new_range_error("Invalid array length".to_string()));
+        }
+        if n as usize > JS_ARRAY_MAX_LENGTH {
+            return Err(JsException::new_range_error("Array length is too large".to_string()));
+        }
+        values.resize_with(n as usize, SharedValue::new_undefined);
+    } else {
+        for v in args {
+            values.push(v.duplicate());
+        }
     }
+    return Ok(rt.new_array(values));
+}
 
-    target.get_object().borrow_mut().__proto__ = Some(proto.get_object());
-    rt.push(target);
+fn array_isarray(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let value = &args[0];
+    let b = value.is_object() && value.get_object().borrow().is_array();
+    return Ok(SharedValue::new_boolean(b));
 }
 
-fn object_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+fn array_builtins() -> HashMap<String, JsBuiltinFunction> {
     let mut builtins = HashMap::new();
-    builtins.insert("toString".to_string(), JsBuiltinFunction::new(object_tostring, 0));
-    //builtins.insert("preventExtensions".to_string(), JsBuiltinFunction::new(object_preventextensions, 1));
-    //builtins.insert("setPrototypeOf".to_string(), JsBuiltinFunction::new(object_setprototypeof, 2));
+    builtins.insert("isArray".to_string(), JsBuiltinFunction::new(array_isarray, 1));
     return builtins;
 }
 
-fn object_builtins() -> HashMap<String, JsBuiltinFunction> {
-    let mut builtins = HashMap::new();   
-    builtins.insert("preventExtensions".to_string(), JsBuiltinFunction::new(object_preventextensions, 1));
-    builtins.insert("setPrototypeOf".to_string(), JsBuiltinFunction::new(object_setprototypeof, 2));
-    return builtins;
+// the array object of this, or an exception is returned
+fn this_array(this: &SharedValue) -> Result<SharedObject, JsException> {
+    if this.is_object() && this.get_object().borrow().is_array() {
+        return Ok(this.get_object());
+    }
+    return Err(JsException::new_type_error("Array method called on none array object".to_string()));
 }
 
-// The String class
-fn string_constructor(rt: &mut JsRuntime) {
-    let value = rt.top(-1);
-    if value.is_string() {
-        rt.push(value);
-        return;
+// copy of the elements, callbacks may change the array
+fn array_values(a: &SharedObject) -> Vec<SharedValue> {
+    return a.borrow().get_array().clone();
+}
+
+fn array_get(a: &SharedObject, i: usize) -> Option<SharedValue> {
+    return a.borrow().get_array().get(i).cloned();
+}
+
+// frozen arrays can't be changed, sealed ones can't grow or shrink
+fn array_mutable(a: &SharedObject, grow: bool, shrink: bool) -> Result<(), JsException> {
+    let object = a.borrow();
+    let (writable, _, configurable) = object.elements_attr;
+    if !writable || (grow && !object.extensible) || (shrink && !configurable) {
+        return Err(JsException::new_type_error("Can't modify a frozen or sealed array".to_string()));
     }
-    if value.is_something() {
-        object_tostring(rt);
-        return;
+    return Ok(());
+}
+
+// an array which is already being joined, e.g. one containing itself, is joined as ""
+fn array_join_object(rt: &mut JsRuntime, a: &SharedObject, sep: &str) -> Result<String, JsException> {
+    if rt.joining.iter().any(|o| Rc::ptr_eq(o, a)) {
+        return Ok(String::new());
     }
-    rt.push_string("".to_string());
+    let values = array_values(a);
+    rt.joining.push(a.clone());
+    let result = array_join_values(rt, &values, sep);
+    rt.joining.pop();
+    return result;
 }
 
-fn string_tostring(rt: &mut JsRuntime) {
-    let value = rt.top(-1).duplicate();     // this object
-    assert!(value.is_string());
-    rt.push(value);
+fn array_join_values(rt: &mut JsRuntime, values: &[SharedValue], sep: &str) -> Result<String, JsException> {
+    let mut result = String::new();
+    for i in 0..values.len() {
+        if i > 0 {
+            result.push_str(sep);
+        }
+        if values[i].is_something() {
+            let s = rt.to_string(values[i].clone())?;
+            result.push_str(&s);
+        }
+    }
+    return Ok(result);
 }
 
-fn string_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
-    // TODO
-    let mut builtins = HashMap::new();
-    builtins.insert("toString".to_string(), JsBuiltinFunction::new(string_tostring, 0));    
-    return builtins;
+fn array_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let s = array_join_object(rt, &a, ",")?;
+    return Ok(rt.new_string(s));
 }
 
-// The Array class
-fn array_constructor(rt: &mut JsRuntime) {
-    let a = JsClass::array(Vec::new());
-    let obj = JsObject::new_with(rt.prototypes.array_prototype.clone(), a);
-    let jv = SharedValue::new_object(obj);
-    rt.push(jv);
+fn array_join(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let sep = if args[0].is_undefined() {
+        ",".to_string()
+    } else {
+        args[0].to_string()
+    };
+    let s = array_join_object(rt, &a, &sep)?;
+    return Ok(rt.new_string(s));
 }
 
-fn array_tostring(rt: &mut JsRuntime) {
-    let value = rt.top(-1);
-    assert!(value.is_object());
-    let sobj = value.get_object();
-    let object = sobj.borrow();
-    assert!(object.is_array());
+fn array_push(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    array_mutable(&a, true, false)?;
+    let values: Vec<SharedValue> = args.iter().map(|v| v.duplicate()).collect();
+    a.borrow_mut().get_mut_array().extend(values);
+    let len = a.borrow().get_array().len();
+    return Ok(SharedValue::new_number(len as f64));
+}
 
-    let mut result = String::new();
-    let v = object.get_array();
-    for i in 0..v.len() {        
-        result.push_str( &v[i].to_string() );
-        if i != v.len() - 1 {
-            result.push_str(", ");
+fn array_pop(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    array_mutable(&a, false, true)?;
+    let r = a.borrow_mut().get_mut_array().pop();
+    return Ok(r.unwrap_or_else(SharedValue::new_undefined));
+}
+
+fn array_shift(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    array_mutable(&a, false, true)?;
+    if a.borrow().get_array().len() == 0 {
+        return Ok(SharedValue::new_undefined());
+    }
+    let v = a.borrow_mut().get_mut_array().remove(0);
+    return Ok(v);
+}
+
+fn array_unshift(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    array_mutable(&a, true, false)?;
+    let values: Vec<SharedValue> = args.iter().map(|v| v.duplicate()).collect();
+    a.borrow_mut().get_mut_array().splice(0..0, values);
+    let len = a.borrow().get_array().len();
+    return Ok(SharedValue::new_number(len as f64));
+}
+
+fn array_slice(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let values = array_values(&a);
+    let start = clamp_index(&args[0], values.len(), true);
+    let end = if args[1].is_undefined() {
+        values.len()
+    } else {
+        clamp_index(&args[1], values.len(), true)
+    };
+    let result = if start < end {
+        values[start..end].to_vec()
+    } else {
+        Vec::new()
+    };
+    return Ok(rt.new_array(result));
+}
+
+fn array_splice(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    array_mutable(&a, true, true)?;
+    let len = a.borrow().get_array().len();
+
+    let start = match args.get(0) {
+        Some(v) => clamp_index(v, len, true),
+        None => len,
+    };
+    let count = match args.get(1) {
+        Some(v) => clamp_index(v, len - start, false),
+        None => len - start,
+    };
+    let mut items: Vec<SharedValue> = Vec::new();
+    for i in 2..cmp::max(args.len(), 2) {
+        items.push(args[i].duplicate());
+    }
+
+    let removed: Vec<SharedValue> = a.borrow_mut().get_mut_array().splice(start..start+count, items).collect();
+    return Ok(rt.new_array(removed));
+}
+
+fn array_concat(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let mut result = array_values(&a);
+    for v in args {
+        if v.is_object() && v.get_object().borrow().is_array() {
+            result.extend( array_values(&v.get_object()) );
+        } else {
+            result.push(v.duplicate());
         }
     }
-    rt.push_string(result);
+    return Ok(rt.new_array(result));
 }
 
-fn array_push(rt: &mut JsRuntime) {
-    let target = rt.top(-2);
-    assert!(target.is_object());
-    let sobj = target.get_object();
-    let mut object = sobj.borrow_mut();
-    assert!(object.is_array());
-   
-    let value = rt.top(-1).duplicate();
-    object.get_mut_array().push(value);
-    
-    rt.push_number(object.get_array().len() as f64);
+fn array_reverse(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    let a = this_array(&this)?;
+    array_mutable(&a, false, false)?;
+    a.borrow_mut().get_mut_array().reverse();
+    return Ok(this);
+}
+
+fn array_indexof(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let values = array_values(&a);
+    let search = &args[0];
+    let from = clamp_index(&args[1], values.len(), true);
+    for i in from..values.len() {
+        if values[i].strict_equals(search) {
+            return Ok(SharedValue::new_number(i as f64));
+        }
+    }
+    return Ok(SharedValue::new_number(-1.0));
+}
+
+fn array_lastindexof(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let a = this_array(&rt.this_value())?;
+    let values = array_values(&a);
+    if values.len() == 0 {
+        return Ok(SharedValue::new_number(-1.0));
+    }
+    let search = args.get(0).cloned().unwrap_or_else(SharedValue::new_undefined);
+    let mut from = values.len() as f64 - 1.0;
+    if let Some(v) = args.get(1) {
+        let n = to_integer(v);
+        from = if n < 0.0 { values.len() as f64 + n } else { n.min(from) };
+    }
+    let mut i = from as isize;
+    while i >= 0 {
+        if values[i as usize].strict_equals(&search) {
+            return Ok(SharedValue::new_number(i as f64));
+        }
+        i = i - 1;
+    }
+    return Ok(SharedValue::new_number(-1.0));
+}
+
+// compare two elements for sort, undefined elements are sorted to the end
+fn array_sort_compare(rt: &mut JsRuntime, comparefn: &SharedValue, x: &SharedValue, y: &SharedValue) -> Result<cmp::Ordering, JsException> {
+    if x.is_undefined() || y.is_undefined() {
+        return Ok( y.is_undefined().cmp(&x.is_undefined()).reverse() );
+    }
+    if comparefn.is_undefined() {
+        let xs = rt.to_string(x.clone())?;
+        let ys = rt.to_string(y.clone())?;
+        return Ok( utf16(&xs).cmp(&utf16(&ys)) );
+    }
+    let r = call_function(rt, comparefn, SharedValue::new_undefined(), &[x.clone(), y.clone()])?;
+    let n = r.to_number();
+    if n < 0.0 {
+        return Ok(cmp::Ordering::Less);
+    }
+    if n > 0.0 {
+        return Ok(cmp::Ordering::Greater);
+    }
+    return Ok(cmp::Ordering::Equal);
+}
+
+// stable merge sort, the comparator may be inconsistent or throw
+fn array_merge_sort(rt: &mut JsRuntime, comparefn: &SharedValue, values: Vec<SharedValue>) -> Result<Vec<SharedValue>, JsException> {
+    if values.len() <= 1 {
+        return Ok(values);
+    }
+    let mut left = values;
+    let right = left.split_off(left.len() / 2);
+    let left = array_merge_sort(rt, comparefn, left)?;
+    let right = array_merge_sort(rt, comparefn, right)?;
+
+    let mut result = Vec::with_capacity(left.len() + right.len());
+    let (mut i, mut j) = (0, 0);
+    while i < left.len() && j < right.len() {
+        if array_sort_compare(rt, comparefn, &left[i], &right[j])? == cmp::Ordering::Greater {
+            result.push(right[j].clone());
+            j = j + 1;
+        } else {
+            result.push(left[i].clone());
+            i = i + 1;
+        }
+    }
+    result.extend_from_slice(&left[i..]);
+    result.extend_from_slice(&right[j..]);
+    return Ok(result);
+}
+
+fn array_sort(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    let a = this_array(&this)?;
+    let comparefn = &args[0];
+    if comparefn.is_something() && !(comparefn.is_object() && comparefn.get_object().borrow().callable()) {
+        return Err(JsException::new_type_error("The comparison function must be callable".to_string()));
+    }
+    array_mutable(&a, false, false)?;
+    let values = array_values(&a);
+    let sorted = array_merge_sort(rt, comparefn, values)?;
+    *a.borrow_mut().get_mut_array() = sorted;
+    return Ok(this);
+}
+
+// the callback of iteration methods
+fn array_callback(f: &SharedValue) -> Result<SharedValue, JsException> {
+    if f.is_object() && f.get_object().borrow().callable() {
+        return Ok(f.clone());
+    }
+    return Err(JsException::new_type_error("Array callback must be a function".to_string()));
+}
+
+// call callback(element, index, array) for each element till the callback returns stop
+fn array_iterate(rt: &mut JsRuntime, args: &[SharedValue], stop: Option<bool>, results: &mut Vec<(SharedValue, SharedValue)>) -> Result<bool, JsException> {
+    let this = rt.this_value();
+    let a = this_array(&this)?;
+    let callback = array_callback(&args[0])?;
+    let thiz = &args[1];
+
+    let len = a.borrow().get_array().len();
+    for i in 0..len {
+        let v = match array_get(&a, i) {
+            Some(v) => v,
+            None => break,
+        };
+        let args = [v.clone(), SharedValue::new_number(i as f64), this.clone()];
+        let r = call_function(rt, &callback, thiz.clone(), &args)?;
+        if let Some(b) = stop {
+            if r.to_boolean() == b {
+                return Ok(true);
+            }
+        }
+        results.push((v, r));
+    }
+    return Ok(false);
+}
+
+fn array_foreach(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut results = Vec::new();
+    array_iterate(rt, args, None, &mut results)?;
+    return Ok(SharedValue::new_undefined());
+}
+
+fn array_map(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut results = Vec::new();
+    array_iterate(rt, args, None, &mut results)?;
+    let values = results.into_iter().map(|(_, r)| r.duplicate()).collect();
+    return Ok(rt.new_array(values));
+}
+
+fn array_filter(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut results = Vec::new();
+    array_iterate(rt, args, None, &mut results)?;
+    let values = results.into_iter().filter(|(_, r)| r.to_boolean()).map(|(v, _)| v).collect();
+    return Ok(rt.new_array(values));
+}
+
+fn array_some(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut results = Vec::new();
+    let found = array_iterate(rt, args, Some(true), &mut results)?;
+    return Ok(SharedValue::new_boolean(found));
+}
+
+fn array_every(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut results = Vec::new();
+    let found = array_iterate(rt, args, Some(false), &mut results)?;
+    return Ok(SharedValue::new_boolean(!found));
+}
+
+fn array_reduce(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    let a = this_array(&this)?;
+    let callback = match args.get(0) {
+        Some(f) => array_callback(f)?,
+        None => {
+            return Err(JsException::new_type_error("Array callback must be a function".to_string()));
+        }
+    };
+
+    let len = a.borrow().get_array().len();
+    let mut i = 0;
+    let mut acc = match args.get(1) {
+        Some(v) => v.clone(),
+        None => {
+            if len == 0 {
+                return Err(JsException::new_type_error("Reduce of empty array with no initial value".to_string()));
+            }
+            i = 1;
+            array_get(&a, 0).unwrap()
+        }
+    };
+    while i < len {
+        let v = match array_get(&a, i) {
+            Some(v) => v,
+            None => break,
+        };
+        let args = [acc, v, SharedValue::new_number(i as f64), this.clone()];
+        acc = call_function(rt, &callback, SharedValue::new_undefined(), &args)?;
+        i = i + 1;
+    }
+    return Ok(acc);
 }
 
 fn array_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
     let mut builtins = HashMap::new();
     builtins.insert("toString".to_string(), JsBuiltinFunction::new(array_tostring, 0));
-    builtins.insert("push".to_string(), JsBuiltinFunction::new(array_push, 1));
+    builtins.insert("join".to_string(), JsBuiltinFunction::new(array_join, 1));
+    builtins.insert("push".to_string(), JsBuiltinFunction::new(array_push, JS_VARARGS));
+    builtins.insert("pop".to_string(), JsBuiltinFunction::new(array_pop, 0));
+    builtins.insert("shift".to_string(), JsBuiltinFunction::new(array_shift, 0));
+    builtins.insert("unshift".to_string(), JsBuiltinFunction::new(array_unshift, JS_VARARGS));
+    builtins.insert("slice".to_string(), JsBuiltinFunction::new(array_slice, 2));
+    builtins.insert("splice".to_string(), JsBuiltinFunction::new(array_splice, JS_VARARGS));
+    builtins.insert("concat".to_string(), JsBuiltinFunction::new(array_concat, JS_VARARGS));
+    builtins.insert("reverse".to_string(), JsBuiltinFunction::new(array_reverse, 0));
+    builtins.insert("sort".to_string(), JsBuiltinFunction::new(array_sort, 1));
+    builtins.insert("indexOf".to_string(), JsBuiltinFunction::new(array_indexof, 2));
+    builtins.insert("lastIndexOf".to_string(), JsBuiltinFunction::new(array_lastindexof, JS_VARARGS));
+    builtins.insert("forEach".to_string(), JsBuiltinFunction::new(array_foreach, 2));
+    builtins.insert("map".to_string(), JsBuiltinFunction::new(array_map, 2));
+    builtins.insert("filter".to_string(), JsBuiltinFunction::new(array_filter, 2));
+    builtins.insert("some".to_string(), JsBuiltinFunction::new(array_some, 2));
+    builtins.insert("every".to_string(), JsBuiltinFunction::new(array_every, 2));
+    builtins.insert("reduce".to_string(), JsBuiltinFunction::new(array_reduce, JS_VARARGS));
     return builtins;
 }
 
 // The Function class
-fn function_constructor(rt: &mut JsRuntime) {
+fn function_constructor(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
     let vmf = SharedFunction_new(VMFunction::new_anonymous());
     let mut fobj = JsObject::new_function(vmf, rt.cenv.clone());
     fobj.__proto__ = Some(rt.prototypes.function_prototype.clone());
-    rt.push(SharedValue::new_object(fobj));
+    return Ok(SharedValue::new_object(fobj));
+}
+
+fn function_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return Ok(rt.new_string("function(...) {...}".to_string()));
+}
+
+fn this_function(this: &SharedValue) -> Result<SharedValue, JsException> {
+    if this.is_object() && this.get_object().borrow().callable() {
+        return Ok(this.clone());
+    }
+    return Err(JsException::new_type_error("Function method called on none function object".to_string()));
+}
+
+// the arguments list of apply, from arrays or array-like objects
+fn apply_arguments(rt: &mut JsRuntime, v: &SharedValue) -> Result<Vec<SharedValue>, JsException> {
+    if !v.is_something() {
+        return Ok(Vec::new());
+    }
+    if !v.is_object() {
+        return Err(JsException::new_type_error("Arguments list of apply must be an object".to_string()));
+    }
+    let obj = v.get_object();
+    if obj.borrow().is_array() {
+        return Ok(array_values(&obj));
+    }
+    // the list goes on the value stack, a huge length is an error before anything is allocated
+    const MAX_APPLY_ARGS: usize = 1 << 16;
+    let limit = if rt.limits.max_stack > 0 { cmp::min(rt.limits.max_stack, MAX_APPLY_ARGS) } else { MAX_APPLY_ARGS };
+    let len = to_uint32(&get_value(rt, obj.clone(), "length")?) as usize;
+    if len > limit {
+        return Err(JsException::new_range_error("Too many arguments for apply".to_string()));
+    }
+    let mut args = Vec::with_capacity(len);
+    for i in 0..len {
+        args.push( get_value(rt, obj.clone(), &i.to_string())? );
+    }
+    return Ok(args);
+}
+
+fn function_call(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let func = this_function(&rt.this_value())?;
+    let this = args.first().cloned().unwrap_or_else(SharedValue::new_undefined);
+    let rest = if args.is_empty() { args } else { &args[1..] };
+    return call_function(rt, &func, this, rest);
+}
+
+fn function_apply(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let func = this_function(&rt.this_value())?;
+    let list = apply_arguments(rt, &args[1])?;
+    return call_function(rt, &func, args[0].clone(), &list);
 }
 
-fn function_tostring(rt: &mut JsRuntime) {
-    rt.push_string("function(...) {...}".to_string());
+fn function_bind(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let func = this_function(&rt.this_value())?;
+    let bound = JsBoundFunction {
+        target: func.get_object(),
+        this: args.first().cloned().unwrap_or_else(SharedValue::new_undefined),
+        args: args.iter().skip(1).cloned().collect(),
+    };
+    let fobj = JsObject::new_with(rt.prototypes.function_prototype.clone(), JsClass::bound(bound));
+    return Ok(SharedValue::new_object(fobj));
 }
 
 fn function_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
-    // TODO
     let mut builtins = HashMap::new();
     builtins.insert("toString".to_string(), JsBuiltinFunction::new(function_tostring, 0));    
+    builtins.insert("call".to_string(), JsBuiltinFunction::new(function_call, JS_VARARGS));
+    builtins.insert("apply".to_string(), JsBuiltinFunction::new(function_apply, 2));
+    builtins.insert("bind".to_string(), JsBuiltinFunction::new(function_bind, JS_VARARGS));
     return builtins;
 }
 
 // The Exception class
-fn exception_constructor(rt: &mut JsRuntime) {
-    
-    let value = rt.top(-1);    
-    let msg = value.to_string();
+fn exception_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let msg = args[0].to_string();
 
-    let exp = JsException::new(msg);
-    let value = SharedValue::new_object(JsObject::new_exception(rt.prototypes.exception_prototype.clone(), exp));
-    rt.push(value);
+    let mut exp = JsException::new_error(JsErrorType::Exception, msg);
+    rt.trace_exception(&mut exp);
+    return Ok(rt.new_exception(exp));
 }
 
-fn exception_tostring(rt: &mut JsRuntime) {
-    rt.push_string("exception(...) {...}".to_string());
+fn exception_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return Ok(rt.new_string("exception(...) {...}".to_string()));
 }
 
-fn exception_message(rt: &mut JsRuntime) {
-    let exp_object = rt.top(-1).get_object();
-    let exp = exp_object.borrow().get_exception();
-    rt.push_string(exp.msg);
+fn exception_message(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    if !(this.is_object() && this.get_object().borrow().is_exception()) {
+        return Err(JsException::new_type_error("Exception method called on none exception".to_string()));
+    }
+    let exp = this.get_object().borrow().get_exception();
+    return Ok(rt.new_string(exp.msg));
 }
 
 fn exception_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
@@ -177,13 +2258,280 @@ fn exception_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
     return builtins;
 }
 
+// The Error classes, Error.prototype inherits from Exception.prototype
+fn error_construct(rt: &mut JsRuntime, args: &[SharedValue], kind: JsErrorType) -> Result<SharedValue, JsException> {
+    let value = &args[0];
+    let msg = if value.is_undefined() {
+        "".to_string()
+    } else {
+        rt.to_string(value.clone())?
+    };
+
+    let mut exp = JsException::new_error(kind, msg);
+    rt.trace_exception(&mut exp);
+    return Ok(rt.new_exception(exp));
+}
+
+fn error_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return error_construct(rt, args, JsErrorType::Error);
+}
+
+fn type_error_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return error_construct(rt, args, JsErrorType::TypeError);
+}
+
+fn range_error_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return error_construct(rt, args, JsErrorType::RangeError);
+}
+
+fn reference_error_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return error_construct(rt, args, JsErrorType::ReferenceError);
+}
+
+fn syntax_error_constructor(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    return error_construct(rt, args, JsErrorType::SyntaxError);
+}
+
+// Error.prototype.toString of ES5 15.11.4.4
+fn error_tostring(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let this = rt.this_value();
+    if !this.is_object() {
+        return Err(JsException::new_type_error("Error.prototype.toString called on none object".to_string()));
+    }
+    let name = get_value(rt, this.get_object(), "name")?;
+    let name = if name.is_undefined() {
+        "Error".to_string()
+    } else {
+        rt.to_string(name)?
+    };
+    let msg = get_value(rt, this.get_object(), "message")?;
+    let msg = if msg.is_undefined() {
+        "".to_string()
+    } else {
+        rt.to_string(msg)?
+    };
+
+    let s = if name.is_empty() {
+        msg
+    } else if msg.is_empty() {
+        name
+    } else {
+        format!("{}: {}", name, msg)
+    };
+    return Ok(rt.new_string(s));
+}
+
+fn error_proto_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("toString".to_string(), JsBuiltinFunction::new(error_tostring, 0));
+    return builtins;
+}
+
+// The Math object
+fn math_abs(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.abs()));
+}
+
+fn math_acos(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.acos()));
+}
+
+fn math_asin(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.asin()));
+}
+
+fn math_atan(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.atan()));
+}
+
+fn math_atan2(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let y = args[0].to_number();
+    let x = args[1].to_number();
+    return Ok(SharedValue::new_number(y.atan2(x)));
+}
+
+fn math_ceil(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.ceil()));
+}
+
+fn math_cos(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.cos()));
+}
+
+fn math_exp(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.exp()));
+}
+
+fn math_floor(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.floor()));
+}
+
+fn math_log(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.ln()));
+}
+
+fn math_max(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut r = std::f64::NEG_INFINITY;
+    for v in args {
+        let x = v.to_number();
+        if x.is_nan() {
+            r = x;
+            break;
+        }
+        // +0 is larger than -0
+        if x > r || (x == 0.0 && r == 0.0 && r.is_sign_negative()) {
+            r = x;
+        }
+    }
+    return Ok(SharedValue::new_number(r));
+}
+
+fn math_min(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let mut r = std::f64::INFINITY;
+    for v in args {
+        let x = v.to_number();
+        if x.is_nan() {
+            r = x;
+            break;
+        }
+        // -0 is smaller than +0
+        if x < r || (x == 0.0 && r == 0.0 && x.is_sign_negative()) {
+            r = x;
+        }
+    }
+    return Ok(SharedValue::new_number(r));
+}
+
+fn math_pow(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    let y = args[1].to_number();
+    // differs from powf: pow(1, NaN) and pow(-1, Infinity) are NaN
+    if y.is_nan() || (x.abs() == 1.0 && y.is_infinite()) {
+        return Ok(SharedValue::new_number(std::f64::NAN));
+    }
+    return Ok(SharedValue::new_number(x.powf(y)));
+}
+
+fn math_random(rt: &mut JsRuntime, _args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let r = rt.random();
+    return Ok(SharedValue::new_number(r));
+}
+
+fn math_round(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    if !x.is_finite() || x == 0.0 {
+        return Ok(SharedValue::new_number(x));
+    }
+    // halfway cases are rounded towards +Infinity
+    let mut r = x.floor();
+    if x - r >= 0.5 {
+        r = r + 1.0;
+    }
+    if r == 0.0 && x < 0.0 {
+        r = -0.0;
+    }
+    return Ok(SharedValue::new_number(r));
+}
+
+fn math_sin(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.sin()));
+}
+
+fn math_sqrt(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.sqrt()));
+}
+
+fn math_tan(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let x = args[0].to_number();
+    return Ok(SharedValue::new_number(x.tan()));
+}
+
+fn math_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("abs".to_string(), JsBuiltinFunction::new(math_abs, 1));
+    builtins.insert("acos".to_string(), JsBuiltinFunction::new(math_acos, 1));
+    builtins.insert("asin".to_string(), JsBuiltinFunction::new(math_asin, 1));
+    builtins.insert("atan".to_string(), JsBuiltinFunction::new(math_atan, 1));
+    builtins.insert("atan2".to_string(), JsBuiltinFunction::new(math_atan2, 2));
+    builtins.insert("ceil".to_string(), JsBuiltinFunction::new(math_ceil, 1));
+    builtins.insert("cos".to_string(), JsBuiltinFunction::new(math_cos, 1));
+    builtins.insert("exp".to_string(), JsBuiltinFunction::new(math_exp, 1));
+    builtins.insert("floor".to_string(), JsBuiltinFunction::new(math_floor, 1));
+    builtins.insert("log".to_string(), JsBuiltinFunction::new(math_log, 1));
+    builtins.insert("max".to_string(), JsBuiltinFunction::new(math_max, JS_VARARGS));
+    builtins.insert("min".to_string(), JsBuiltinFunction::new(math_min, JS_VARARGS));
+    builtins.insert("pow".to_string(), JsBuiltinFunction::new(math_pow, 2));
+    builtins.insert("random".to_string(), JsBuiltinFunction::new(math_random, 0));
+    builtins.insert("round".to_string(), JsBuiltinFunction::new(math_round, 1));
+    builtins.insert("sin".to_string(), JsBuiltinFunction::new(math_sin, 1));
+    builtins.insert("sqrt".to_string(), JsBuiltinFunction::new(math_sqrt, 1));
+    builtins.insert("tan".to_string(), JsBuiltinFunction::new(math_tan, 1));
+    return builtins;
+}
+
+fn math_init(rt: &mut JsRuntime) {
+    let math_obj = SharedObject_new(JsObject::new());
+    math_obj.borrow_mut().__proto__ = Some(rt.prototypes.object_prototype.clone());
+    create_class_functions(math_obj.clone(), math_builtins());
+
+    let constants = [
+        ("E", std::f64::consts::E),
+        ("LN10", std::f64::consts::LN_10),
+        ("LN2", std::f64::consts::LN_2),
+        ("LOG10E", std::f64::consts::LOG10_E),
+        ("LOG2E", std::f64::consts::LOG2_E),
+        ("PI", std::f64::consts::PI),
+        ("SQRT1_2", std::f64::consts::FRAC_1_SQRT_2),
+        ("SQRT2", std::f64::consts::SQRT_2),
+    ];
+    create_class_constants(math_obj.clone(), &constants);
+
+    set_global_class(rt, "Math", math_obj);
+}
+
+// The JSON object
+fn json_parse(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    let text = args[0].to_string();
+    return rt.json_parse(&text, Some(args[1].clone()));
+}
+
+fn json_stringify(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+    match rt.json_stringify(args[0].clone(), Some(args[1].clone()), Some(args[2].clone()))? {
+        Some(s) => Ok(rt.new_string(s)),
+        None => Ok(SharedValue::new_undefined()),
+    }
+}
+
+fn json_builtins() -> HashMap<String, JsBuiltinFunction> {
+    let mut builtins = HashMap::new();
+    builtins.insert("parse".to_string(), JsBuiltinFunction::new(json_parse, 2));
+    builtins.insert("stringify".to_string(), JsBuiltinFunction::new(json_stringify, 3));
+    return builtins;
+}
+
+fn json_init(rt: &mut JsRuntime) {
+    let json_obj = SharedObject_new(JsObject::new());
+    json_obj.borrow_mut().__proto__ = Some(rt.prototypes.object_prototype.clone());
+    create_class_functions(json_obj.clone(), json_builtins());
+    set_global_class(rt, "JSON", json_obj);
+}
+
 // build class's global functions
 fn create_class_functions( target: SharedObject, properties: HashMap<String, JsBuiltinFunction>) {
     let mut class_obj = target.borrow_mut();
     for (k, v) in properties {
-        let f = v.f;
-        let argc = v.argc; 
-        let func_obj = JsObject::new_builtin(f, argc);
+        let func_obj = JsObject::new_builtin(v);
         
         let mut prop = JsProperty::new();
         prop.fill_attr(JS_READONLY_ATTR);
@@ -193,6 +2541,17 @@ fn create_class_functions( target: SharedObject, properties: HashMap<String, JsB
     }
 }
 
+// build class's constant numbers
+fn create_class_constants(target: SharedObject, constants: &[(&str, f64)]) {
+    let mut class_obj = target.borrow_mut();
+    for (name, v) in constants.iter() {
+        let mut prop = JsProperty::new();
+        prop.fill_attr(JS_READONLY_ATTR);
+        prop.value = SharedValue::new_number(*v);
+        class_obj.properties.insert(name.to_string(), prop);
+    }
+}
+
 // build prototypes chian
 fn create_builtin_class(constructor: JsBuiltinFunction, properties: HashMap<String, JsBuiltinFunction>, top: Option<SharedObject>) -> (SharedObject, SharedObject) {
     let mut class_obj = JsObject::new();
@@ -203,9 +2562,7 @@ fn create_builtin_class(constructor: JsBuiltinFunction, properties: HashMap<Stri
     let mut prototype_obj = JsObject::new();
     prototype_obj.extensible = false;
     for (k, v) in properties {
-        let f = v.f;
-        let argc = v.argc; 
-        let func_obj = JsObject::new_builtin(f, argc);
+        let func_obj = JsObject::new_builtin(v);
         
         let mut prop = JsProperty::new();
         prop.fill_attr(JS_READONLY_ATTR);
@@ -228,6 +2585,20 @@ fn create_builtin_class(constructor: JsBuiltinFunction, properties: HashMap<Stri
     
     return (class_obj, prototype_obj);
 }
+// error classes have name and message on their prototypes, both can be changed by scripts
+fn create_error_class(rt: &mut JsRuntime, name: &str, constructor: fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException>, properties: HashMap<String, JsBuiltinFunction>, top: SharedObject) -> SharedObject {
+    let (class_obj, prototype_obj) = create_builtin_class(JsBuiltinFunction::new(constructor, 1), properties, Some(top));
+    let values = [("name", name), ("message", "")];
+    for (k, v) in values.iter() {
+        let mut prop = JsProperty::new();
+        prop.fill_attr((true, false, true));
+        prop.value = rt.new_string(v.to_string());
+        prototype_obj.borrow_mut().properties.insert(k.to_string(), prop);
+    }
+    set_global_class(rt, name, class_obj);
+    return prototype_obj;
+}
+
 fn set_global_class(rt: &mut JsRuntime, name: &str, class_obj: SharedObject) {
     let mut prop = JsProperty::new();
     prop.fill_attr(JS_READONLY_ATTR);
@@ -244,11 +2615,42 @@ pub fn prototypes_init(rt: &mut JsRuntime) {
     
     // String
     let (string_classs_object, string_prototype) = create_builtin_class( JsBuiltinFunction::new(string_constructor, 1), string_proto_builtins(), Some(top_prototype.clone()));
+    create_class_functions(string_classs_object.clone(), string_builtins());
     set_global_class(rt, "String", string_classs_object.clone());
     rt.prototypes.string_prototype = string_prototype;
 
+    // RegExp
+    let (regexp_class_object, regexp_prototype) = create_builtin_class( JsBuiltinFunction::new(regexp_constructor, 2), regexp_proto_builtins(), Some(top_prototype.clone()));
+    set_global_class(rt, "RegExp", regexp_class_object.clone());
+    rt.prototypes.regexp_prototype = regexp_prototype;
+
+    // Date
+    let (date_class_object, date_prototype) = create_builtin_class( JsBuiltinFunction::new(date_constructor, JS_VARARGS), date_proto_builtins(), Some(top_prototype.clone()));
+    create_class_functions(date_class_object.clone(), date_builtins());
+    set_global_class(rt, "Date", date_class_object.clone());
+    rt.prototypes.date_prototype = date_prototype;
+
+    // Number
+    let (number_class_object, number_prototype) = create_builtin_class( JsBuiltinFunction::new(number_constructor, JS_VARARGS), number_proto_builtins(), Some(top_prototype.clone()));
+    let constants = [
+        ("MAX_VALUE", f64::MAX),
+        ("MIN_VALUE", 5e-324),
+        ("NaN", f64::NAN),
+        ("NEGATIVE_INFINITY", f64::NEG_INFINITY),
+        ("POSITIVE_INFINITY", f64::INFINITY),
+    ];
+    create_class_constants(number_class_object.clone(), &constants);
+    set_global_class(rt, "Number", number_class_object.clone());
+    rt.prototypes.number_prototype = number_prototype;
+
+    // Boolean
+    let (boolean_class_object, boolean_prototype) = create_builtin_class( JsBuiltinFunction::new(boolean_constructor, 1), boolean_proto_builtins(), Some(top_prototype.clone()));
+    set_global_class(rt, "Boolean", boolean_class_object.clone());
+    rt.prototypes.boolean_prototype = boolean_prototype;
+
     // Array
-    let (array_classs_object, array_prototype) = create_builtin_class( JsBuiltinFunction::new(array_constructor, 0), array_proto_builtins(), Some(top_prototype.clone()));
+    let (array_classs_object, array_prototype) = create_builtin_class( JsBuiltinFunction::new(array_constructor, JS_VARARGS), array_proto_builtins(), Some(top_prototype.clone()));
+    create_class_functions(array_classs_object.clone(), array_builtins());
     set_global_class(rt, "Array", array_classs_object.clone());
     rt.prototypes.array_prototype = array_prototype;
 
@@ -260,34 +2662,55 @@ pub fn prototypes_init(rt: &mut JsRuntime) {
     // Exception
     let (exp_classs_object, exp_prototype) = create_builtin_class( JsBuiltinFunction::new(exception_constructor, 1), exception_proto_builtins(), Some(top_prototype.clone()));
     set_global_class(rt, "Exception", exp_classs_object.clone());
-    rt.prototypes.exception_prototype = exp_prototype;
+    rt.prototypes.exception_prototype = exp_prototype.clone();
+
+    // Error and its native types
+    let error_prototype = create_error_class(rt, "Error", error_constructor, error_proto_builtins(), exp_prototype);
+    rt.prototypes.error_prototype = error_prototype.clone();
+    rt.prototypes.type_error_prototype = create_error_class(rt, "TypeError", type_error_constructor, HashMap::new(), error_prototype.clone());
+    rt.prototypes.range_error_prototype = create_error_class(rt, "RangeError", range_error_constructor, HashMap::new(), error_prototype.clone());
+    rt.prototypes.reference_error_prototype = create_error_class(rt, "ReferenceError", reference_error_constructor, HashMap::new(), error_prototype.clone());
+    rt.prototypes.syntax_error_prototype = create_error_class(rt, "SyntaxError", syntax_error_constructor, HashMap::new(), error_prototype);
+}
+
+// calls of a runtime's own eval are dispatched by the vm, the builtin is only called with the
+// eval of another realm. It runs the code in the global scope of its realm, held weakly since
+// the global scope holds the eval.
+pub fn eval_builtin(genv: &SharedScope) -> JsBuiltinFunction {
+    let realm = Rc::downgrade(genv);
+    return JsBuiltinFunction::new(move |rt, args| {
+        if let Some(genv) = realm.upgrade() {
+            return jseval_global(rt, args[0].clone(), genv);
+        }
+        return Ok(SharedValue::new_undefined());
+    }, 1);
 }
 
 pub fn builtin_init(runtime: &mut JsRuntime) {
     // global functions for runtime 
-    fn assert(rt: &mut JsRuntime) {    
-        let b = rt.top(-2).to_boolean();
-        if !b {
-            let info = rt.top(-1).to_string();
-            panic!("ASSERT: {}", info);
+    fn assert(_rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+        if !args[0].to_boolean() {
+            panic!("ASSERT: {}", args[1].to_string());
         }
-        rt.push_undefined();
+        return Ok(SharedValue::new_undefined());
     }
 
-    fn println(rt: &mut JsRuntime) {
-        let info = rt.to_string( rt.top(-1) );
-        if let Ok(msg) = info {
-            println!("{}", msg);
-            rt.push_undefined();
-            return;
-        } 
-        if let Err(e) = info {
-            rt.new_exception(e);
-        }
+    fn println(rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+        let msg = rt.to_string(args[0].clone())?;
+        println!("{}", msg);
+        return Ok(SharedValue::new_undefined());
     }
-    // TODO : isFinite() isNaN() parseFloat() parseInt()
-
     // register some basic builtin functions
-    runtime.genv.borrow_mut().init_var("assert", SharedValue::new_object(JsObject::new_builtin(assert, 2)) );
-    runtime.genv.borrow_mut().init_var("println", SharedValue::new_object(JsObject::new_builtin(println, 1)) );
+    runtime.register_function("assert", assert, 2);
+    runtime.register_function("println", println, 1);
+
+    runtime.eval_function = SharedObject_new(JsObject::new_builtin(eval_builtin(&runtime.genv)));
+    runtime.genv.borrow_mut().init_var("eval", SharedValue::new_sobject(runtime.eval_function.clone()) );
+
+    for (k, v) in global_builtins() {
+        runtime.genv.borrow_mut().init_var(&k, SharedValue::new_object(JsObject::new_builtin(v)) );
+    }
+
+    math_init(runtime);
+    json_init(runtime);
 }
\ No newline at end of file
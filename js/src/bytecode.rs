@@ -22,6 +22,7 @@ pub enum OpcodeType {
 
 	OP_NEWARRAY,
 	OP_NEWOBJECT,
+	OP_NEWREGEXP,	/* <pattern> <flags> -- <regexp> */
 
 	OP_UNDEF,
 	OP_NULL,
@@ -50,7 +51,7 @@ pub enum OpcodeType {
 	OP_ITERATOR,	/* <obj> -- <iobj> */
 	OP_NEXTITER,	/* <iobj> -- ( <iobj> <name> true | false ) */
 
-	OP_EVAL,	/* <args...> -(numargs)- <returnvalue> */
+	OP_EVAL,	/* <closure> <this> <args...> -(numargs)- <returnvalue> */
 	OP_CALL,	/* <closure> <this> <args...> -(numargs)- <returnvalue> */
 	OP_NEW,		/* <closure> <args...> -(numargs)- <returnvalue> */
 
@@ -122,6 +123,7 @@ impl TryFrom<u16> for OpcodeType {
 			x if x == OpcodeType::OP_STRING as u16 => Ok(OpcodeType::OP_STRING),
 			x if x == OpcodeType::OP_CLOSURE as u16 => Ok(OpcodeType::OP_CLOSURE),
 			x if x == OpcodeType::OP_NEWARRAY as u16 => Ok(OpcodeType::OP_NEWARRAY),
+			x if x == OpcodeType::OP_NEWREGEXP as u16 => Ok(OpcodeType::OP_NEWREGEXP),
 			x if x == OpcodeType::OP_NEWOBJECT as u16 => Ok(OpcodeType::OP_NEWOBJECT),
 			x if x == OpcodeType::OP_UNDEF as u16 => Ok(OpcodeType::OP_UNDEF),
 			x if x == OpcodeType::OP_NULL as u16 => Ok(OpcodeType::OP_NULL),
@@ -223,6 +225,7 @@ pub struct VMJumpTable {
 #[allow(non_camel_case_types)]
 pub struct VMFunction {
 	pub name:		Option<String>,
+	pub filename:	String,
 	pub script:		bool,
 	pub numparams:	usize,
 	pub numvars:	usize,
@@ -231,6 +234,7 @@ pub struct VMFunction {
 	pub num_tab:	Vec<f64>,
 	pub str_tab:	Vec<String>,
 	pub func_tab:	Vec<Rc<Box<VMFunction>>>,
+	pub lines:		Vec<(usize, u32)>,		// (pc, line) sorted by pc, source map of code
 
 	pub jumps:		Vec<VMJumpTable>,
 }
@@ -23,3 +23,321 @@ pub fn str_to_number(symbol: &str) -> Option<f64> {
 	return None;
 }
 
+
+// array index of a property name, only the canonical form "0", "1", ... is an index
+pub fn str_to_index(symbol: &str) -> Option<usize> {
+	if let Ok(v) = symbol.parse::<u32>() {
+		if v != u32::MAX && v.to_string() == symbol {
+			return Some(v as usize);
+		}
+	}
+	return None;
+}
+
+/* number conversions of ES5 */
+
+// StringToNumber, white spaces around are ignored and the empty string is 0
+pub fn string_to_number(s: &str) -> f64 {
+	let s = s.trim_matches(|c: char| c.is_whitespace() || c == '\u{feff}');
+	if s.len() == 0 {
+		return 0.0;
+	}
+	if s.starts_with("0x") || s.starts_with("0X") {
+		let mut v: f64 = 0.0;
+		for c in s[2..].chars() {
+			match c.to_digit(16) {
+				Some(d) => v = v * 16.0 + d as f64,
+				None => return f64::NAN,
+			}
+		}
+		if s.len() == 2 {
+			return f64::NAN;
+		}
+		return v;
+	}
+	let (sign, body) = match s.as_bytes()[0] {
+		b'+' => (1.0, &s[1..]),
+		b'-' => (-1.0, &s[1..]),
+		_ => (1.0, s),
+	};
+	if body == "Infinity" {
+		return sign * f64::INFINITY;
+	}
+	// only decimal literals, Rust accepts "inf" and "nan" too
+	if body.len() == 0 || !body.bytes().all(|b| b.is_ascii_digit() || b == b'.' || b == b'e' || b == b'E' || b == b'+' || b == b'-') {
+		return f64::NAN;
+	}
+	match body.parse::<f64>() {
+		Ok(v) => {
+			return sign * v;
+		},
+		Err(_) => {
+			return f64::NAN;
+		}
+	}
+}
+
+// decimal digits and exponent of a positive finite number, value = 0.d1d2d3... * 10^n
+// the shortest digits which round trip are used
+fn shortest_digits(v: f64) -> (Vec<u8>, i32) {
+	let s = format!("{:e}", v);
+	let (mantissa, exp) = s.split_at(s.find('e').unwrap());
+	let exp: i32 = exp[1..].parse().unwrap();
+	let digits: Vec<u8> = mantissa.bytes().filter(|b| b.is_ascii_digit()).map(|b| b - b'0').collect();
+	return (digits, exp + 1);
+}
+
+// all decimal digits of a positive finite number, value = 0.d1d2d3... * 10^n
+fn exact_digits(v: f64) -> (Vec<u8>, i32) {
+	// binary fractions have at most 1074 decimal digits
+	let s = format!("{:.1100}", v);
+	let point = s.find('.').unwrap();
+	let mut digits: Vec<u8> = s.bytes().filter(|b| b.is_ascii_digit()).map(|b| b - b'0').collect();
+	let mut n = point as i32;
+	while digits.len() > 0 && digits[0] == 0 {
+		digits.remove(0);
+		n = n - 1;
+	}
+	while digits.len() > 0 && digits[digits.len() - 1] == 0 {
+		digits.pop();
+	}
+	return (digits, n);
+}
+
+// keep first count digits, rounding half up
+// a carry adds a leading digit, the exponent grows and count + 1 digits are returned
+fn round_digits(digits: &[u8], n: i32, count: usize) -> (Vec<u8>, i32) {
+	let mut r: Vec<u8> = digits.iter().take(count).cloned().collect();
+	while r.len() < count {
+		r.push(0);
+	}
+	if digits.len() > count && digits[count] >= 5 {
+		let mut i = count;
+		loop {
+			if i == 0 {
+				r.insert(0, 1);
+				return (r, n + 1);
+			}
+			i = i - 1;
+			if r[i] == 9 {
+				r[i] = 0;
+			} else {
+				r[i] = r[i] + 1;
+				break;
+			}
+		}
+	}
+	return (r, n);
+}
+
+// significant digits of the number, value = 0.d1d2d3... * 10^n
+fn precision_digits(v: f64, count: usize) -> (Vec<u8>, i32) {
+	if v == 0.0 {
+		return (vec![0; count], 1);
+	}
+	let (digits, n) = exact_digits(v);
+	let (mut r, n) = round_digits(&digits, n, count);
+	r.truncate(count);
+	return (r, n);
+}
+
+fn digits_string(digits: &[u8]) -> String {
+	return digits.iter().map(|d| (b'0' + d) as char).collect();
+}
+
+// Number::toString of ES5 9.8.1
+pub fn number_to_string(v: f64) -> String {
+	if v.is_nan() {
+		return "NaN".to_string();
+	}
+	if v == 0.0 {
+		return "0".to_string();
+	}
+	if v < 0.0 {
+		return format!("-{}", number_to_string(-v));
+	}
+	if v.is_infinite() {
+		return "Infinity".to_string();
+	}
+
+	let (digits, n) = shortest_digits(v);
+	let k = digits.len() as i32;
+	let s = digits_string(&digits);
+	if k <= n && n <= 21 {
+		return format!("{}{}", s, "0".repeat((n - k) as usize));
+	}
+	if 0 < n && n <= 21 {
+		return format!("{}.{}", &s[..n as usize], &s[n as usize..]);
+	}
+	if -6 < n && n <= 0 {
+		return format!("0.{}{}", "0".repeat((-n) as usize), s);
+	}
+	let e = n - 1;
+	let sign = if e < 0 { "-" } else { "+" };
+	if k == 1 {
+		return format!("{}e{}{}", s, sign, e.abs());
+	}
+	return format!("{}.{}e{}{}", &s[..1], &s[1..], sign, e.abs());
+}
+
+// Number.prototype.toFixed for |v| < 1e21
+pub fn number_to_fixed(v: f64, fraction: usize) -> String {
+	if v < 0.0 {
+		let s = number_to_fixed(-v, fraction);
+		if s.bytes().all(|b| b == b'0' || b == b'.') {
+			return s;
+		}
+		return format!("-{}", s);
+	}
+
+	// digits of the integer v * 10^fraction
+	let (digits, n) = exact_digits(v);
+	let count = n + fraction as i32;
+	let mut s = if count < 0 {
+		String::new()
+	} else {
+		digits_string( &round_digits(&digits, n, count as usize).0 )
+	};
+	if s.len() < fraction + 1 {
+		s = format!("{}{}", "0".repeat(fraction + 1 - s.len()), s);
+	}
+	if fraction == 0 {
+		return s;
+	}
+	let point = s.len() - fraction;
+	return format!("{}.{}", &s[..point], &s[point..]);
+}
+
+// Number.prototype.toExponential, None of fraction for as many digits as necessary
+pub fn number_to_exponential(v: f64, fraction: Option<usize>) -> String {
+	if v < 0.0 {
+		return format!("-{}", number_to_exponential(-v, fraction));
+	}
+	let (digits, n) = match fraction {
+		Some(f) => precision_digits(v, f + 1),
+		None => {
+			if v == 0.0 {
+				(vec![0], 1)
+			} else {
+				shortest_digits(v)
+			}
+		}
+	};
+	let s = digits_string(&digits);
+	let e = if v == 0.0 { 0 } else { n - 1 };
+	let sign = if e < 0 { "-" } else { "+" };
+	if s.len() == 1 {
+		return format!("{}e{}{}", s, sign, e.abs());
+	}
+	return format!("{}.{}e{}{}", &s[..1], &s[1..], sign, e.abs());
+}
+
+// Number.prototype.toPrecision
+pub fn number_to_precision(v: f64, precision: usize) -> String {
+	if v < 0.0 {
+		return format!("-{}", number_to_precision(-v, precision));
+	}
+	let (digits, n) = precision_digits(v, precision);
+	let e = n - 1;
+	if e < -6 || e >= precision as i32 {
+		return number_to_exponential(v, Some(precision - 1));
+	}
+	let s = digits_string(&digits);
+	if n <= 0 {
+		return format!("0.{}{}", "0".repeat((-n) as usize), s);
+	}
+	if n as usize >= s.len() {
+		return s;
+	}
+	return format!("{}.{}", &s[..n as usize], &s[n as usize..]);
+}
+
+// digits of an integral value, exact at any magnitude: the value is divided as a big number of 32 bit limbs
+fn integer_to_radix(v: f64, radix: u32) -> String {
+	let bits = v.to_bits();
+	let exponent = ((bits >> 52) & 0x7ff) as i64;
+	if exponent == 0 {
+		return "0".to_string();
+	}
+	let mantissa = (bits & ((1 << 52) - 1)) | (1 << 52);
+	let shift = exponent - 1075;
+
+	// least significant limb first
+	let mut limbs: Vec<u32> = Vec::new();
+	if shift < 0 {
+		let n = mantissa >> (-shift);
+		limbs.push(n as u32);
+		limbs.push((n >> 32) as u32);
+	} else {
+		limbs.resize((shift / 32) as usize, 0);
+		let n = (mantissa as u128) << (shift % 32);
+		limbs.push(n as u32);
+		limbs.push((n >> 32) as u32);
+		limbs.push((n >> 64) as u32);
+	}
+
+	let mut digits: Vec<char> = Vec::new();
+	loop {
+		while limbs.last() == Some(&0) {
+			limbs.pop();
+		}
+		if limbs.is_empty() {
+			break;
+		}
+		let mut rem: u64 = 0;
+		for limb in limbs.iter_mut().rev() {
+			let n = (rem << 32) | *limb as u64;
+			*limb = (n / radix as u64) as u32;
+			rem = n % radix as u64;
+		}
+		digits.push(std::char::from_digit(rem as u32, radix).unwrap());
+	}
+	if digits.is_empty() {
+		digits.push('0');
+	}
+	return digits.iter().rev().collect();
+}
+
+// Number.prototype.toString with radix other than 10
+pub fn number_to_radix(v: f64, radix: u32) -> String {
+	if v.is_nan() {
+		return "NaN".to_string();
+	}
+	if v.is_infinite() {
+		if v < 0.0 {
+			return "-Infinity".to_string();
+		}
+		return "Infinity".to_string();
+	}
+	if v < 0.0 {
+		return format!("-{}", number_to_radix(-v, radix));
+	}
+
+	let int_part = v.trunc();
+	let mut frac_part = v - int_part;
+
+	let mut result = integer_to_radix(int_part, radix);
+	if frac_part > 0.0 {
+		result.push('.');
+		// digits beyond the precision of f64 are meaningless, they are counted from the first non-zero digit
+		let max_digits = (52.0 / (radix as f64).log2()).ceil() as usize + 1;
+		let mut count = if int_part > 0.0 { result.len() - 1 } else { 0 };
+		while frac_part > 0.0 && count < max_digits {
+			frac_part = frac_part * radix as f64;
+			let d = frac_part.trunc() as u32;
+			result.push(std::char::from_digit(d, radix).unwrap());
+			frac_part = frac_part - d as f64;
+			if d > 0 || count > 0 {
+				count = count + 1;
+			}
+		}
+	}
+	return result;
+}
+
+// an address in the caller's frame, the Rust stack grows down so deeper calls have smaller addresses
+#[inline(always)]
+pub fn stack_address() -> usize {
+	let here = 0u8;
+	return &here as *const u8 as usize;
+}
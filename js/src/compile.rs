@@ -3,6 +3,22 @@ use std::rc::Rc;
 use crate::ast::*;
 use crate::bytecode::*;
 
+/* compiling errors with the source line */
+#[derive(Clone, Debug)]
+pub struct CompileError {
+    pub msg:    String,
+    pub line:   u32,
+}
+
+impl CompileError {
+    fn new(node: &AstNode, msg: &str) -> Self {
+        CompileError {
+            msg:    msg.to_string(),
+            line:   node.src_line,
+        }
+    }
+}
+
 /* Local help function and struct */
 struct AstListIterator<'a> {
     cursor: Option<&'a AstNode>
@@ -126,6 +142,7 @@ impl VMFunction {
     fn new(script: bool) -> Self {
         VMFunction {
             name:   None,
+            filename:   String::new(),
             script: script,
             numparams: 0,
             numvars: 0,
@@ -133,11 +150,39 @@ impl VMFunction {
             num_tab:    Vec::new(),
             str_tab:    Vec::new(),           
             func_tab:   Vec::new(),
+            lines:      Vec::new(),
 
             jumps:      Vec::new(),
         }
     }
 
+    pub fn set_filename(&mut self, filename: &str) {
+        self.filename = filename.to_string();
+        for sub in self.func_tab.iter_mut() {
+            if let Some(sub) = Rc::get_mut(sub) {
+                sub.set_filename(filename);
+            }
+        }
+    }
+
+    // the following code is compiled from the line
+    fn mark_line(&mut self, line: u32) {
+        if line == 0 {
+            return;
+        }
+        let pc = self.code.len();
+        if let Some(last) = self.lines.last_mut() {
+            if last.1 == line {
+                return;
+            }
+            if last.0 == pc {
+                last.1 = line;
+                return;
+            }
+        }
+        self.lines.push((pc, line));
+    }
+
     fn emit(&mut self, value: u16) {
         self.code.push(value);
     }
@@ -347,12 +392,12 @@ impl VMFunction {
         }
     }
 
-    fn parsing_fundec(&mut self, lst: &AstNode) {
+    fn parsing_fundec(&mut self, lst: &AstNode) -> Result<(), CompileError> {
         if lst.is_list() {
             let it = lst.iter();
             for n in it {
                 if n.ast_type == AstType::AST_FUNDEC {
-                    let newfunc = compile_func( n.a(), n.b(), n.c(), false).unwrap();
+                    let newfunc = compile_func( n.a(), n.b(), n.c(), false)?;
                     let fid = self.addfunc(newfunc);
                     self.emitop(OpcodeType::OP_CLOSURE);
                     self.emit(fid);
@@ -364,15 +409,15 @@ impl VMFunction {
                     self.emitop(OpcodeType::OP_POP);
                 }
             }
-            return;
         }
+        return Ok(());
     }
 }
 
 /* Expressions */
-fn compile_object(f: &mut VMFunction, lst: &AstNode) {
+fn compile_object(f: &mut VMFunction, lst: &AstNode) -> Result<(), CompileError> {
     if lst.is_null() {
-        return;
+        return Ok(());
     }
 
     let it = lst.iter();
@@ -388,7 +433,7 @@ fn compile_object(f: &mut VMFunction, lst: &AstNode) {
                 f.emitnumber(prop_number);
             },
             _ => {
-                panic!("invalid property name in object initializer");
+                return Err(CompileError::new(prop, "invalid property name in object initializer"));
             }
         }
 
@@ -398,31 +443,32 @@ fn compile_object(f: &mut VMFunction, lst: &AstNode) {
         */
         match kv.ast_type {
             AstType::EXP_PROP_VAL => {
-                compile_exp(f, kv.b());
+                compile_exp(f, kv.b())?;
                 f.emitop(OpcodeType::OP_INITPROP);
             },
             AstType::EXP_PROP_GET => {
                 let null = AstNode::null();
-                let func = compile_func( &null, &null, kv.c(), false).unwrap();
+                let func = compile_func( &null, &null, kv.c(), false)?;
                 f.emitfunction(func);
                 f.emitop(OpcodeType::OP_INITGETTER);                
             },
             AstType::EXP_PROP_SET => {
                 let null = AstNode::null();
-                let func = compile_func( &null, kv.b(), kv.c(), false).unwrap();
+                let func = compile_func( &null, kv.b(), kv.c(), false)?;
                 f.emitfunction(func);
                 f.emitop(OpcodeType::OP_INITSETTER);  
             },
             _ => {
-                panic!("invalid value type in object initializer");
+                return Err(CompileError::new(kv, "invalid value type in object initializer"));
             }
         }
     }
+    return Ok(());
 }
 
-fn compile_array(f: &mut VMFunction, lst: &AstNode) {
+fn compile_array(f: &mut VMFunction, lst: &AstNode) -> Result<(), CompileError> {
     if lst.is_null() {
-        return;
+        return Ok(());
     }
     let mut i:u16 = 0;
     let it = lst.iter();
@@ -430,58 +476,63 @@ fn compile_array(f: &mut VMFunction, lst: &AstNode) {
         if n.ast_type != AstType::EXP_UNDEF {
             f.emitop(OpcodeType::OP_INTEGER);
             f.emit(i);
-            compile_exp(f, n);
+            compile_exp(f, n)?;
             f.emitop(OpcodeType::OP_INITPROP);
         }
         i = i + 1;
     }
+    return Ok(());
 }
 
-fn compile_delete(f: &mut VMFunction, exp: &AstNode) {
+fn compile_delete(f: &mut VMFunction, exp: &AstNode) -> Result<(), CompileError> {
     let arg = exp.a();
     match arg.ast_type {
 
         AstType::EXP_INDEX => {
-            compile_exp(f, arg.a());
-            compile_exp(f, arg.b());
+            compile_exp(f, arg.a())?;
+            compile_exp(f, arg.b())?;
             f.emitop(OpcodeType::OP_DELPROP);
         },
         AstType::EXP_MEMBER => {
-            compile_exp(f, arg.a());
+            compile_exp(f, arg.a())?;
             let member_str = arg.b().str();
             f.emitstring(OpcodeType::OP_DELPROP_S, member_str);
         },
         AstType::EXP_IDENTIFIER => {
-            panic!("delete on an unqualified name is not allowed in strict mode");
+            return Err(CompileError::new(arg, "delete on an unqualified name is not allowed in strict mode"));
         },
         _ => {
-            panic!("invalid l-value in delete expression");
+            return Err(CompileError::new(arg, "invalid l-value in delete expression"));
         }
     }
+    return Ok(());
 }
 
-fn compile_typeof(f: &mut VMFunction, exp: &AstNode) {
+fn compile_typeof(f: &mut VMFunction, exp: &AstNode) -> Result<(), CompileError> {
     if exp.a().ast_type == AstType::EXP_IDENTIFIER {
         let var_str = exp.a().str();
         f.emitstring(OpcodeType::OP_HASVAR, var_str);
     } else {
-        compile_exp(f, exp.a());
+        compile_exp(f, exp.a())?;
     }
     f.emitop(OpcodeType::OP_TYPEOF);
+    return Ok(());
 }
 
-fn compile_unary(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) {
-    compile_exp(f, exp.a());
+fn compile_unary(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) -> Result<(), CompileError> {
+    compile_exp(f, exp.a())?;
     f.emitop(op);
+    return Ok(());
 }
 
-fn compile_binary(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) {
-    compile_exp(f, exp.a());
-    compile_exp(f, exp.b());
+fn compile_binary(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) -> Result<(), CompileError> {
+    compile_exp(f, exp.a())?;
+    compile_exp(f, exp.b())?;
     f.emitop(op);
+    return Ok(());
 }
 
-fn compile_assignop(f: &mut VMFunction, var: &AstNode, op: OpcodeType, is_post: bool) {
+fn compile_assignop(f: &mut VMFunction, var: &AstNode, op: OpcodeType, is_post: bool) -> Result<(), CompileError> {
     match var.ast_type {
         AstType::EXP_IDENTIFIER => {
             let id_str = var.str();
@@ -496,8 +547,8 @@ fn compile_assignop(f: &mut VMFunction, var: &AstNode, op: OpcodeType, is_post:
             }
         },
         AstType::EXP_INDEX => {
-            compile_exp(f, var.a());
-            compile_exp(f, var.b());
+            compile_exp(f, var.a())?;
+            compile_exp(f, var.b())?;
             f.emitop(OpcodeType::OP_DUP2);
             f.emitop(OpcodeType::OP_GETPROP);
             f.emitop(op);
@@ -510,7 +561,7 @@ fn compile_assignop(f: &mut VMFunction, var: &AstNode, op: OpcodeType, is_post:
             }
         },
         AstType::EXP_MEMBER => {
-            compile_exp(f, var.a());
+            compile_exp(f, var.a())?;
             f.emitop(OpcodeType::OP_DUP);
             let member_str = var.b().str();
             f.emitstring(OpcodeType::OP_GETPROP_S, member_str);
@@ -524,12 +575,13 @@ fn compile_assignop(f: &mut VMFunction, var: &AstNode, op: OpcodeType, is_post:
             }
         },
         _ => {
-            panic!("invalid l-value in assignment");
+            return Err(CompileError::new(var, "invalid l-value in assignment"));
         }
     }
+    return Ok(());
 }
 
-fn compile_assignwith(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) {
+fn compile_assignwith(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) -> Result<(), CompileError> {
     let var = exp.a();
     let rhs = exp.b();
 
@@ -537,106 +589,114 @@ fn compile_assignwith(f: &mut VMFunction, exp: &AstNode, op: OpcodeType) {
         AstType::EXP_IDENTIFIER => {
             let id_str = var.str();
             f.emitstring(OpcodeType::OP_GETVAR, id_str);
-            compile_exp(f, rhs);
+            compile_exp(f, rhs)?;
             f.emitop(op);
             f.emitstring(OpcodeType::OP_SETVAR, id_str);
         },
         AstType::EXP_INDEX => {
-            compile_exp(f, var.a());
-            compile_exp(f, var.b());
+            compile_exp(f, var.a())?;
+            compile_exp(f, var.b())?;
             f.emitop(OpcodeType::OP_DUP2);
             f.emitop(OpcodeType::OP_GETPROP);
-            compile_exp(f, rhs);
+            compile_exp(f, rhs)?;
             f.emitop(op);
             f.emitop(OpcodeType::OP_SETPROP);
         },
         AstType::EXP_MEMBER => {
-            compile_exp(f, var.a());
+            compile_exp(f, var.a())?;
             f.emitop(OpcodeType::OP_DUP);
             let member_str = var.b().str();
             f.emitstring(OpcodeType::OP_GETPROP_S, member_str);
-            compile_exp(f, rhs);
+            compile_exp(f, rhs)?;
             f.emitop(op);
             f.emitstring(OpcodeType::OP_SETPROP_S, member_str);
         },
         _ => {
-            panic!("invalid l-value in assignment");
+            return Err(CompileError::new(var, "invalid l-value in assignment"));
         }
     }
+    return Ok(());
 }
 
-fn compile_assign(f: &mut VMFunction, exp: &AstNode) {
+fn compile_assign(f: &mut VMFunction, exp: &AstNode) -> Result<(), CompileError> {
     let var = exp.a();
     let rhs = exp.b();
 
     match var.ast_type {
         AstType::EXP_IDENTIFIER => {
             let id_str = var.str();
-            compile_exp(f, rhs);
+            compile_exp(f, rhs)?;
             f.emitstring(OpcodeType::OP_SETVAR, id_str);
         },
         AstType::EXP_INDEX => {
-            compile_exp(f, var.a());
-            compile_exp(f, var.b());
-            compile_exp(f, rhs);
+            compile_exp(f, var.a())?;
+            compile_exp(f, var.b())?;
+            compile_exp(f, rhs)?;
             f.emitop(OpcodeType::OP_SETPROP);
         },
         AstType::EXP_MEMBER => {            
             let member_str = var.b().str();
-            compile_exp(f, var.a());
-            compile_exp(f, rhs);
+            compile_exp(f, var.a())?;
+            compile_exp(f, rhs)?;
             f.emitstring(OpcodeType::OP_SETPROP_S, member_str);
         },
         _ => {
-            panic!("invalid l-value in assignment");
+            return Err(CompileError::new(var, "invalid l-value in assignment"));
         }
     }
+    return Ok(());
 }
 
-fn compile_args(f: &mut VMFunction, lst: &AstNode) -> u16 {
+fn compile_args(f: &mut VMFunction, lst: &AstNode) -> Result<u16, CompileError> {
     if lst.is_null() {
-        return 0;
+        return Ok(0);
     }
     let mut num:u16 = 0;
     let it = lst.iter();
     for n in it {
-        compile_exp(f, n);
+        compile_exp(f, n)?;
         num = num + 1;
     }
-    return num;
+    return Ok(num);
 }
 
-fn compile_call(f: &mut VMFunction, exp: &AstNode) {
+fn compile_call(f: &mut VMFunction, exp: &AstNode) -> Result<(), CompileError> {
     let fun = exp.a();
     let args = exp.b();
 
     match fun.ast_type {
         AstType::EXP_INDEX => {
-            compile_exp(f, fun.a());
+            compile_exp(f, fun.a())?;
             f.emitop(OpcodeType::OP_DUP);
-            compile_exp(f, fun.b());
+            compile_exp(f, fun.b())?;
             f.emitop(OpcodeType::OP_GETPROP);
             f.emitop(OpcodeType::OP_ROT2);
         },
         AstType::EXP_MEMBER => {
-            compile_exp(f, fun.a());
+            compile_exp(f, fun.a())?;
             f.emitop(OpcodeType::OP_DUP);
             let member = fun.b().str();
             f.emitstring(OpcodeType::OP_GETPROP_S, member);            
             f.emitop(OpcodeType::OP_ROT2);      // function object | this object
         },
         _ => {
-            compile_exp(f, fun);
+            compile_exp(f, fun)?;
             f.emitop(OpcodeType::OP_UNDEF);     // this object is UNDEFINED
         }
     }
 
-    let n = compile_args(f, args);
-    f.emitop(OpcodeType::OP_CALL);
+    let n = compile_args(f, args)?;
+    if fun.ast_type == AstType::EXP_IDENTIFIER && fun.str() == "eval" {
+        f.emitop(OpcodeType::OP_EVAL);
+    } else {
+        f.emitop(OpcodeType::OP_CALL);
+    }
     f.emit(n);
+    return Ok(());
 }
 
-fn compile_exp(f: &mut VMFunction, exp: &AstNode) {
+fn compile_exp(f: &mut VMFunction, exp: &AstNode) -> Result<(), CompileError> {
+    f.mark_line(exp.src_line);
     match exp.ast_type {
         /* immediately value*/ 
         AstType::EXP_STRING => {
@@ -663,24 +723,30 @@ fn compile_exp(f: &mut VMFunction, exp: &AstNode) {
             f.emitop(OpcodeType::OP_THIS);
         },
 
+        AstType::EXP_REGEXP => {
+            f.emitstring(OpcodeType::OP_STRING, exp.a().str());
+            f.emitstring(OpcodeType::OP_STRING, exp.b().str());
+            f.emitop(OpcodeType::OP_NEWREGEXP);
+        },
+
         /* complex value*/
         AstType::EXP_OBJECT => {
             f.emitop(OpcodeType::OP_NEWOBJECT);
-            compile_object(f, exp.a());
+            compile_object(f, exp.a())?;
         },
 
         AstType::EXP_ARRAY => {
             f.emitop(OpcodeType::OP_NEWARRAY);
-            compile_array(f, exp.a());
+            compile_array(f, exp.a())?;
         },
 
         AstType::EXP_FUN => {
-            let func = compile_func( exp.a(), exp.b(), exp.c(), false).unwrap();
+            let func = compile_func( exp.a(), exp.b(), exp.c(), false)?;
             f.emitfunction(func);
         },
 
         AstType::EXP_VOID => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             f.emitop(OpcodeType::OP_POP);
             f.emitop(OpcodeType::OP_UNDEF);
         }
@@ -691,208 +757,216 @@ fn compile_exp(f: &mut VMFunction, exp: &AstNode) {
         },
 
         AstType::EXP_INDEX => {
-            compile_exp(f, exp.a());
-            compile_exp(f, exp.b());
+            compile_exp(f, exp.a())?;
+            compile_exp(f, exp.b())?;
             f.emitop(OpcodeType::OP_GETPROP);
         },
 
         AstType::EXP_MEMBER => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             let prop_str = exp.b().str();
             f.emitstring(OpcodeType::OP_GETPROP_S, prop_str);
         },
 
         AstType::EXP_CALL => {
-            compile_call(f, exp);
+            compile_call(f, exp)?;
         },
 
         AstType::EXP_NEW => {
-            compile_exp(f, exp.a());
-            let n = compile_args(f, exp.b());
+            compile_exp(f, exp.a())?;
+            // the arguments list is optional, 'new F' is 'new F()'
+            let n = if exp.has_b() {
+                compile_args(f, exp.b())?
+            } else {
+                0
+            };
             f.emitop(OpcodeType::OP_NEW);
             f.emit(n);
         },
         
         // multiple exps 
         AstType::EXP_COMMA => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             f.emitop(OpcodeType::OP_POP);
-            compile_exp(f, exp.b());
+            compile_exp(f, exp.b())?;
         },
         
         AstType::EXP_LOGOR => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             f.emitop(OpcodeType::OP_DUP);
             let end = f.emitjump(OpcodeType::OP_JTRUE);
             f.emitop(OpcodeType::OP_POP);
-            compile_exp(f, exp.b());
+            compile_exp(f, exp.b())?;
             f.label_current_to(end);
         },
 
         AstType::EXP_LOGAND => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             f.emitop(OpcodeType::OP_DUP);
             let end = f.emitjump(OpcodeType::OP_JFALSE);
             f.emitop(OpcodeType::OP_POP);
-            compile_exp(f, exp.b());
+            compile_exp(f, exp.b())?;
             f.label_current_to(end);
         },
         
         AstType::EXP_COND => {
-            compile_exp(f, exp.a());
+            compile_exp(f, exp.a())?;
             let then = f.emitjump(OpcodeType::OP_JTRUE);
-            compile_exp(f, exp.c());
+            compile_exp(f, exp.c())?;
             let end = f.emitjump(OpcodeType::OP_JUMP);
             f.label_current_to(then);
-            compile_exp(f, exp.b());
+            compile_exp(f, exp.b())?;
             f.label_current_to(end);
         },
 
         // Unary operation
         AstType::EXP_DELETE => {
-            compile_delete(f, exp);
+            compile_delete(f, exp)?;
         },
         AstType::EXP_PREINC => {
-            compile_assignop(f, exp.a(), OpcodeType::OP_INC, false);
+            compile_assignop(f, exp.a(), OpcodeType::OP_INC, false)?;
         },
         AstType::EXP_PREDEC => {
-            compile_assignop(f, exp.a(), OpcodeType::OP_DEC, false);
+            compile_assignop(f, exp.a(), OpcodeType::OP_DEC, false)?;
         },
         AstType::EXP_POSTINC => {
-            compile_assignop(f, exp.a(), OpcodeType::OP_POSTINC, true);
+            compile_assignop(f, exp.a(), OpcodeType::OP_POSTINC, true)?;
         },
         AstType::EXP_POSTDEC => {
-            compile_assignop(f, exp.a(), OpcodeType::OP_POSTDEC, true);
+            compile_assignop(f, exp.a(), OpcodeType::OP_POSTDEC, true)?;
         },
         AstType::EXP_TYPEOF => {
-            compile_typeof(f, exp);
+            compile_typeof(f, exp)?;
         },
         AstType::EXP_POS => {
-            compile_unary(f, exp,  OpcodeType::OP_POS);
+            compile_unary(f, exp,  OpcodeType::OP_POS)?;
         },
         AstType::EXP_NEG => {
-            compile_unary(f, exp,  OpcodeType::OP_NEG);
+            compile_unary(f, exp,  OpcodeType::OP_NEG)?;
         },
         AstType::EXP_BITNOT => {
-            compile_unary(f, exp,  OpcodeType::OP_BITNOT);
+            compile_unary(f, exp,  OpcodeType::OP_BITNOT)?;
         },
         AstType::EXP_LOGNOT => {
-            compile_unary(f, exp,  OpcodeType::OP_LOGNOT);
+            compile_unary(f, exp,  OpcodeType::OP_LOGNOT)?;
         },
 
         // Binary operation
         AstType::EXP_BITOR => {
-            compile_binary(f, exp,  OpcodeType::OP_BITOR);
+            compile_binary(f, exp,  OpcodeType::OP_BITOR)?;
         },
         AstType::EXP_BITXOR => {
-            compile_binary(f, exp,  OpcodeType::OP_BITXOR);
+            compile_binary(f, exp,  OpcodeType::OP_BITXOR)?;
         },
         AstType::EXP_BITAND => {
-            compile_binary(f, exp,  OpcodeType::OP_BITAND);
+            compile_binary(f, exp,  OpcodeType::OP_BITAND)?;
         },
         AstType::EXP_EQ => {
-            compile_binary(f, exp,  OpcodeType::OP_EQ);
+            compile_binary(f, exp,  OpcodeType::OP_EQ)?;
         },
         AstType::EXP_NE => {
-            compile_binary(f, exp,  OpcodeType::OP_NE);
+            compile_binary(f, exp,  OpcodeType::OP_NE)?;
         },
         AstType::EXP_STRICTEQ => {
-            compile_binary(f, exp,  OpcodeType::OP_STRICTEQ);
+            compile_binary(f, exp,  OpcodeType::OP_STRICTEQ)?;
         },
         AstType::EXP_STRICTNE => {
-            compile_binary(f, exp,  OpcodeType::OP_STRICTNE);
+            compile_binary(f, exp,  OpcodeType::OP_STRICTNE)?;
         },
         AstType::EXP_LT => {
-            compile_binary(f, exp,  OpcodeType::OP_LT);
+            compile_binary(f, exp,  OpcodeType::OP_LT)?;
         },
         AstType::EXP_GT => {
-            compile_binary(f, exp,  OpcodeType::OP_GT);
+            compile_binary(f, exp,  OpcodeType::OP_GT)?;
         },
         AstType::EXP_LE => {
-            compile_binary(f, exp,  OpcodeType::OP_LE);
+            compile_binary(f, exp,  OpcodeType::OP_LE)?;
         },
         AstType::EXP_GE => {
-            compile_binary(f, exp,  OpcodeType::OP_GE);
+            compile_binary(f, exp,  OpcodeType::OP_GE)?;
         },
         AstType::EXP_INSTANCEOF => {
-            compile_binary(f, exp,  OpcodeType::OP_INSTANCEOF);
+            compile_binary(f, exp,  OpcodeType::OP_INSTANCEOF)?;
         },
         AstType::EXP_IN => {
-            compile_binary(f, exp,  OpcodeType::OP_IN);
+            compile_binary(f, exp,  OpcodeType::OP_IN)?;
         },
         AstType::EXP_SHL => {
-            compile_binary(f, exp,  OpcodeType::OP_SHL);
+            compile_binary(f, exp,  OpcodeType::OP_SHL)?;
         },
         AstType::EXP_SHR => {
-            compile_binary(f, exp,  OpcodeType::OP_SHR);
+            compile_binary(f, exp,  OpcodeType::OP_SHR)?;
         },
         AstType::EXP_USHR => {
-            compile_binary(f, exp,  OpcodeType::OP_USHR);
+            compile_binary(f, exp,  OpcodeType::OP_USHR)?;
         },
         AstType::EXP_ADD => {
-            compile_binary(f, exp,  OpcodeType::OP_ADD);
+            compile_binary(f, exp,  OpcodeType::OP_ADD)?;
         },
         AstType::EXP_SUB => {
-            compile_binary(f, exp,  OpcodeType::OP_SUB);
+            compile_binary(f, exp,  OpcodeType::OP_SUB)?;
         },
         AstType::EXP_MUL => {
-            compile_binary(f, exp,  OpcodeType::OP_MUL);
+            compile_binary(f, exp,  OpcodeType::OP_MUL)?;
         },
         AstType::EXP_DIV => {
-            compile_binary(f, exp,  OpcodeType::OP_DIV);
+            compile_binary(f, exp,  OpcodeType::OP_DIV)?;
         },
         AstType::EXP_MOD => {
-            compile_binary(f, exp,  OpcodeType::OP_MOD);
+            compile_binary(f, exp,  OpcodeType::OP_MOD)?;
         },
 
         // assignments 
         AstType::EXP_ASS => {
-            compile_assign(f, exp);
+            compile_assign(f, exp)?;
         },
         AstType::EXP_ASS_MUL => {
-            compile_assignwith(f, exp, OpcodeType::OP_MUL);
+            compile_assignwith(f, exp, OpcodeType::OP_MUL)?;
         },
         AstType::EXP_ASS_DIV => {
-            compile_assignwith(f, exp, OpcodeType::OP_DIV);
+            compile_assignwith(f, exp, OpcodeType::OP_DIV)?;
         },
         AstType::EXP_ASS_MOD => {
-            compile_assignwith(f, exp, OpcodeType::OP_MOD);
+            compile_assignwith(f, exp, OpcodeType::OP_MOD)?;
         },
         AstType::EXP_ASS_ADD => {
-            compile_assignwith(f, exp, OpcodeType::OP_ADD);
+            compile_assignwith(f, exp, OpcodeType::OP_ADD)?;
         },
         AstType::EXP_ASS_SUB => {
-            compile_assignwith(f, exp, OpcodeType::OP_SUB);
+            compile_assignwith(f, exp, OpcodeType::OP_SUB)?;
         },
         AstType::EXP_ASS_SHL => {
-            compile_assignwith(f, exp, OpcodeType::OP_SHL);
+            compile_assignwith(f, exp, OpcodeType::OP_SHL)?;
         },
         AstType::EXP_ASS_SHR => {
-            compile_assignwith(f, exp, OpcodeType::OP_SHR);
+            compile_assignwith(f, exp, OpcodeType::OP_SHR)?;
         },
         AstType::EXP_ASS_USHR => {
-            compile_assignwith(f, exp, OpcodeType::OP_USHR);
+            compile_assignwith(f, exp, OpcodeType::OP_USHR)?;
         },
         AstType::EXP_ASS_BITAND => {
-            compile_assignwith(f, exp, OpcodeType::OP_BITAND);
+            compile_assignwith(f, exp, OpcodeType::OP_BITAND)?;
         },
         AstType::EXP_ASS_BITXOR => {
-            compile_assignwith(f, exp, OpcodeType::OP_BITXOR);
+            compile_assignwith(f, exp, OpcodeType::OP_BITXOR)?;
         },
         AstType::EXP_ASS_BITOR => {
-            compile_assignwith(f, exp, OpcodeType::OP_BITOR);
+            compile_assignwith(f, exp, OpcodeType::OP_BITOR)?;
         },
 
         _ => {
-            panic!("unknown expression: ({:?})", exp);
+            let msg = format!("unknown expression: ({:?})", exp.ast_type);
+            return Err(CompileError::new(exp, &msg));
         }
     }
+    return Ok(());
 }
 
 /* Emit code to rebalance stack and scopes during an abrupt exit */
-fn compile_exit(f: &mut VMFunction, scope_index: usize, jump_type: AstType) {
+fn compile_exit(f: &mut VMFunction, stm: &AstNode, scope_index: usize) -> Result<(), CompileError> {
+    let jump_type = stm.ast_type;
     if f.jumps.len() == 0 {
-        return;
+        return Ok(());
     }
     for i in (scope_index .. f.jumps.len()).rev() {
         let scope_type = f.jumps[i].scope.clone();
@@ -900,12 +974,22 @@ fn compile_exit(f: &mut VMFunction, scope_index: usize, jump_type: AstType) {
             VMJumpScope::TryScope(stm_d) => {
                 f.emitop(OpcodeType::OP_ENDTRY);
                 if stm_d.is_some() {
-                    compile_stm(f, stm_d.as_ref().unwrap());
+                    compile_stm(f, stm_d.as_ref().unwrap())?;
                 }
             },
             VMJumpScope::CatchScope => {
                 f.emitop(OpcodeType::OP_ENDCATCH);
             },
+            VMJumpScope::ForInLoop if f.script => {
+                if jump_type == AstType::STM_BREAK || (jump_type == AstType::STM_CONTINUE && scope_index != i) {
+                    /* pop the iterator, save the completion value */
+                    f.emitop(OpcodeType::OP_ROT2);
+                    f.emitop(OpcodeType::OP_POP);
+                } else if jump_type == AstType::STM_CONTINUE {
+                    /* put the iterator back on top */
+                    f.emitop(OpcodeType::OP_ROT2);
+                }
+            },
             VMJumpScope::ForInLoop => {
                 if jump_type == AstType::STM_BREAK {
                     /* pop the iterator */
@@ -920,7 +1004,7 @@ fn compile_exit(f: &mut VMFunction, scope_index: usize, jump_type: AstType) {
                     f.emitop(OpcodeType::OP_ROT2);
                     f.emitop(OpcodeType::OP_POP);
                 } else {
-                    panic!("compile_exit error: only break/continue/return supported!");
+                    return Err(CompileError::new(stm, "compile_exit error: only break/continue/return supported!"));
                 }
             },
             _ => {
@@ -928,10 +1012,11 @@ fn compile_exit(f: &mut VMFunction, scope_index: usize, jump_type: AstType) {
             }
         }
     }
+    return Ok(());
 }
 
 /* Try/catch/finally */
-fn compile_trycatchfinally(f: &mut VMFunction, try_block: &AstNode, catch_var: &AstNode, catch_block: &AstNode, finally_block: &AstNode) {
+fn compile_trycatchfinally(f: &mut VMFunction, try_block: &AstNode, catch_var: &AstNode, catch_block: &AstNode, finally_block: &AstNode) -> Result<(), CompileError> {
     let l1:usize;
     let l2:usize;
     let l3:usize;
@@ -943,7 +1028,7 @@ fn compile_trycatchfinally(f: &mut VMFunction, try_block: &AstNode, catch_var: &
         l2 = f.emitjump(OpcodeType::OP_TRY);
         {
             /* if we get here, we have caught an exception in the catch block */
-            compile_stm(f, finally_block);  /* inline finally block */
+            compile_stm(f, finally_block)?;  /* inline finally block */
             f.emitop(OpcodeType::OP_THROW);
         }
         f.label_current_to(l2);
@@ -952,7 +1037,7 @@ fn compile_trycatchfinally(f: &mut VMFunction, try_block: &AstNode, catch_var: &
         f.new_scope(VMJumpScope::CatchScope);
         {
             f.emitstring(OpcodeType::OP_CATCH, catchvar);
-            compile_stm(f, catch_block);
+            compile_stm(f, catch_block)?;
             f.emitop(OpcodeType::OP_ENDCATCH);
         }
         f.delete_scope();
@@ -961,15 +1046,16 @@ fn compile_trycatchfinally(f: &mut VMFunction, try_block: &AstNode, catch_var: &
         l3 = f.emitjump(OpcodeType::OP_JUMP);
     }
     f.label_current_to(l1);
-    compile_stm(f, try_block);
+    compile_stm(f, try_block)?;
     f.emitop(OpcodeType::OP_ENDTRY);
     f.delete_scope();
 
     f.label_current_to(l3);
-    compile_stm(f, finally_block);
+    compile_stm(f, finally_block)?;
+    return Ok(());
 } 
 
-fn compile_trycatch(f: &mut VMFunction, a: &AstNode, b: &AstNode, c: &AstNode) {
+fn compile_trycatch(f: &mut VMFunction, a: &AstNode, b: &AstNode, c: &AstNode) -> Result<(), CompileError> {
     let l1:usize;
     let l2:usize;
 
@@ -981,42 +1067,44 @@ fn compile_trycatch(f: &mut VMFunction, a: &AstNode, b: &AstNode, c: &AstNode) {
         f.new_scope(VMJumpScope::CatchScope);
         {
             f.emitstring(OpcodeType::OP_CATCH, catchvar);
-            compile_stm(f, c);
+            compile_stm(f, c)?;
             f.emitop(OpcodeType::OP_ENDCATCH);
         }
         f.delete_scope();
         l2 = f.emitjump(OpcodeType::OP_JUMP);
     }
     f.label_current_to(l1);
-    compile_stm(f, a);
+    compile_stm(f, a)?;
     f.emitop(OpcodeType::OP_ENDTRY);
     f.delete_scope();    
     f.label_current_to(l2);
+    return Ok(());
 }
 
-fn compile_finally(f: &mut VMFunction, a: &AstNode, b: &AstNode) {
+fn compile_finally(f: &mut VMFunction, a: &AstNode, b: &AstNode) -> Result<(), CompileError> {
     let l1:usize;
 
     l1 = f.emitjump(OpcodeType::OP_TRY);
     f.new_scope(VMJumpScope::TryScope(Some(b.clone())));
     {
         /* if we get here, we have caught an exception in the try block */
-        compile_stm(f, b);
+        compile_stm(f, b)?;
         f.emitop(OpcodeType::OP_THROW);
     }
     f.label_current_to(l1);
-    compile_stm(f, a);
+    compile_stm(f, a)?;
     f.emitop(OpcodeType::OP_ENDTRY);
     f.delete_scope();
 
-    compile_stm(f, b);
+    compile_stm(f, b)?;
+    return Ok(());
 } 
 
 /* Switch */
-fn compile_switch(f: &mut VMFunction, stm: &AstNode) {
+fn compile_switch(f: &mut VMFunction, stm: &AstNode) -> Result<(), CompileError> {
     let mut def = None;
 
-    compile_exp(f, stm.a());
+    compile_exp(f, stm.a())?;
 
     let mut case_jumps = Vec::new();
 
@@ -1024,17 +1112,16 @@ fn compile_switch(f: &mut VMFunction, stm: &AstNode) {
         let it = stm.b().iter();
         for clause in it {            
             if clause.ast_type == AstType::STM_CASE {
-                compile_exp(f, clause.a());                
+                compile_exp(f, clause.a())?;                
                 let addr = f.emitjump(OpcodeType::OP_JCASE);
                 case_jumps.push(addr);
             } else if clause.ast_type == AstType::STM_DEFAULT {
                 if !def.is_none() {
-                    panic!("more than one default label in switch");
+                    return Err(CompileError::new(clause, "more than one default label in switch"));
                 }
                 def = Some(clause);
             } else {
-                panic!("Case list only support STM_CASE and STM_DEFAULT!");
-
+                return Err(CompileError::new(clause, "Case list only support STM_CASE and STM_DEFAULT!"));
             }
         }
     }
@@ -1050,11 +1137,11 @@ fn compile_switch(f: &mut VMFunction, stm: &AstNode) {
             if clause.ast_type == AstType::STM_CASE {
                 let addr = case_jumps[i];
                 f.label_current_to(addr);
-                compile_stmlist(f, clause.b());
+                compile_stmlist(f, clause.b())?;
                 i = i + 1;
             } else if clause.ast_type == AstType::STM_DEFAULT {
                 f.label_current_to(last_jump);
-                compile_stmlist(f, clause.a());
+                compile_stmlist(f, clause.a())?;
             }
         }
     }
@@ -1062,72 +1149,75 @@ fn compile_switch(f: &mut VMFunction, stm: &AstNode) {
     if def.is_none() {
         f.label_current_to(last_jump);
     }
+    return Ok(());
 }
 
 /* Statements */
-fn compile_varinit(f: &mut VMFunction, lst: &AstNode) {
+fn compile_varinit(f: &mut VMFunction, lst: &AstNode) -> Result<(), CompileError> {
     let it = lst.iter();
     for n in it {
         if n.has_b() {
-            compile_exp(f, n.b());
+            compile_exp(f, n.b())?;
             let var_str = n.a().str();
             f.emitstring(OpcodeType::OP_SETVAR, var_str); 
             f.emitop(OpcodeType::OP_POP);
         }
     }
+    return Ok(());
 }
 
-fn compile_assignforin(f: &mut VMFunction, stm: &AstNode) {
+fn compile_assignforin(f: &mut VMFunction, stm: &AstNode) -> Result<(), CompileError> {
     let lhs = stm.a();
     if stm.ast_type == AstType::STM_FOR_IN_VAR {
         if !lhs.is_list() {
-            panic!("for var in statement must include an var list!");
+            return Err(CompileError::new(stm, "for var in statement must include an var list!"));
         }
         if lhs.has_b() {
-            panic!("more than one loop variable in for-in statement");
+            return Err(CompileError::new(stm, "more than one loop variable in for-in statement"));
         }
         let var = lhs.a().a().str();    /* list(var-init(ident)) */
         f.emitstring(OpcodeType::OP_SETVAR, var);
         f.emitop(OpcodeType::OP_POP);
-        return;
+        return Ok(());
     }
 
     if lhs.ast_type != AstType::EXP_IDENTIFIER {
-        panic!("invalid l-value in for-in loop assignment");
+        return Err(CompileError::new(lhs, "invalid l-value in for-in loop assignment"));
     }
 
     let var = lhs.str();
     f.emitstring(OpcodeType::OP_SETVAR, var);
     f.emitop(OpcodeType::OP_POP);
-    return;
+    return Ok(());
 }
 
-fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
+fn compile_stm(f: &mut VMFunction, stm: &AstNode) -> Result<(), CompileError> {
+    f.mark_line(stm.src_line);
     match stm.ast_type {
         AstType::STM_BLOCK => {
             let block = stm.a.as_ref().unwrap();
-            compile_stmlist(f, block);
+            compile_stmlist(f, block)?;
         },
         AstType::STM_EMPTY => {
             // do nothing
         },
         AstType::STM_VAR => {            
             assert!( stm.a().ast_type == AstType::AST_LIST);
-            compile_varinit(f, stm.a());
+            compile_varinit(f, stm.a())?;
         },
         AstType::STM_IF => {
             if stm.c.is_some() {
-                compile_exp(f, stm.a.as_ref().unwrap());
+                compile_exp(f, stm.a.as_ref().unwrap())?;
                 let then = f.emitjump(OpcodeType::OP_JTRUE);
-                compile_stm(f, stm.c.as_ref().unwrap());
+                compile_stm(f, stm.c.as_ref().unwrap())?;
                 let end = f.emitjump(OpcodeType::OP_JUMP);
                 f.label_current_to(then);
-                compile_stm(f, stm.b.as_ref().unwrap());
+                compile_stm(f, stm.b.as_ref().unwrap())?;
                 f.label_current_to(end);
             } else {
-                compile_exp(f, stm.a.as_ref().unwrap());
+                compile_exp(f, stm.a.as_ref().unwrap())?;
                 let end = f.emitjump(OpcodeType::OP_JFALSE);
-                compile_stm(f, stm.b.as_ref().unwrap());
+                compile_stm(f, stm.b.as_ref().unwrap())?;
                 f.label_current_to(end);
             }
         },
@@ -1135,9 +1225,9 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
             f.new_scope(VMJumpScope::DoLoop);
     
             let lop = f.current();
-            compile_stm(f, stm.a.as_ref().unwrap());
+            compile_stm(f, stm.a.as_ref().unwrap())?;
             let cont = f.current();
-            compile_exp(f, stm.b.as_ref().unwrap());
+            compile_exp(f, stm.b.as_ref().unwrap())?;
             f.emitjumpto(OpcodeType::OP_JTRUE, lop);
             
             f.fill_jumps(f.current(), cont);
@@ -1148,9 +1238,9 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
             f.new_scope(VMJumpScope::WhileLoop);
 
             let lop = f.current();
-            compile_exp(f, stm.a());
+            compile_exp(f, stm.a())?;
             let end = f.emitjump(OpcodeType::OP_JFALSE);
-            compile_stm(f, stm.b());
+            compile_stm(f, stm.b())?;
             f.emitjumpto(OpcodeType::OP_JUMP, lop);
             f.label_current_to(end);
 
@@ -1162,11 +1252,11 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
             f.new_scope(VMJumpScope::ForLoop);
 
             if stm.ast_type == AstType::STM_FOR_VAR {
-                compile_varinit(f, stm.a());
+                compile_varinit(f, stm.a())?;
             } else {       
                 let a = stm.a();
                 if ! a.is_null() {
-                    compile_exp(f, a);
+                    compile_exp(f, a)?;
                     f.emitop(OpcodeType::OP_POP);
                 }
             }
@@ -1174,18 +1264,18 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
             let lop = f.current();
             let b = stm.b();
             let end = if ! b.is_null() {
-                compile_exp(f, b);
+                compile_exp(f, b)?;
                 f.emitjump(OpcodeType::OP_JFALSE)
             } else {
                 0
             };
 
-            compile_stm(f, stm.d.as_ref().unwrap());
+            compile_stm(f, stm.d.as_ref().unwrap())?;
 
             let cont = f.current();
             let c = stm.c();
             if !c.is_null() {
-                compile_exp(f, c);
+                compile_exp(f, c)?;
                 f.emitop(OpcodeType::OP_POP);
             }
             f.emitjumpto(OpcodeType::OP_JUMP, lop);
@@ -1201,15 +1291,22 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
         AstType::STM_FOR_IN |  AstType::STM_FOR_IN_VAR => {
             f.new_scope(VMJumpScope::ForInLoop);
 
-            compile_exp(f, stm.b());
+            compile_exp(f, stm.b())?;
             f.emitop(OpcodeType::OP_ITERATOR);
             let lop = f.current();
             
             f.emitop(OpcodeType::OP_NEXTITER);
             let end = f.emitjump(OpcodeType::OP_JFALSE);
-            compile_assignforin(f, stm);
+            compile_assignforin(f, stm)?;
 
-            compile_stm(f, stm.c.as_ref().unwrap());
+            if f.script {
+                /* the iterator is under the completion value in the loop body */
+                f.emitop(OpcodeType::OP_ROT2);
+                compile_stm(f, stm.c.as_ref().unwrap())?;
+                f.emitop(OpcodeType::OP_ROT2);
+            } else {
+                compile_stm(f, stm.c.as_ref().unwrap())?;
+            }
             
             f.emitjumpto(OpcodeType::OP_JUMP, lop);
             f.label_current_to(end);
@@ -1220,7 +1317,7 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
         
         AstType::STM_SWITCH => {
             f.new_scope(VMJumpScope::SwitchScope);
-            compile_switch(f, stm);
+            compile_switch(f, stm)?;
             f.fill_jumps(f.current(), f.current());
             f.delete_scope();
         },
@@ -1229,7 +1326,7 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
             let a = stm.a.as_ref().unwrap();
             f.new_scope(VMJumpScope::LabelSection(a.str().to_string()));           
             
-            compile_stm(f, stm.b.as_ref().unwrap());
+            compile_stm(f, stm.b.as_ref().unwrap())?;
             
             f.fill_jumps(f.current(), f.current());
             f.delete_scope();
@@ -1246,10 +1343,10 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
                 break_scope = f.target_break_scope();
             }
             if break_scope == 0 {
-                panic!("Can't find break target!");
+                return Err(CompileError::new(stm, "Can't find break target!"));
             }
             
-            compile_exit(f, break_scope - 1, AstType::STM_BREAK);
+            compile_exit(f, stm, break_scope - 1)?;
             let from = f.emitjump(OpcodeType::OP_JUMP);
             let jump = VMJumpType::BreakJump(from);
             f.add_jump(break_scope - 1, jump);
@@ -1266,10 +1363,10 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
                 continue_scope = f.target_continue_scope();
             }
             if continue_scope == 0 {
-                panic!("Can't find continue target!");                
+                return Err(CompileError::new(stm, "Can't find continue target!"));
             }
 
-            compile_exit(f, continue_scope - 1, AstType::STM_CONTINUE);
+            compile_exit(f, stm, continue_scope - 1)?;
             let from = f.emitjump(OpcodeType::OP_JUMP);
             let jump = VMJumpType::ContinueJump(from);
             f.add_jump(continue_scope - 1, jump);
@@ -1277,34 +1374,34 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
         
         AstType::STM_RETURN => {
             if f.script {
-                panic!("Find return in script code!");
+                return Err(CompileError::new(stm, "Find return in script code!"));
             }
 
             let a = stm.a.as_ref().unwrap();
             if a.is_null() {
                 f.emitop(OpcodeType::OP_UNDEF);
             } else {
-                compile_exp(f, a);
+                compile_exp(f, a)?;
             }
             
-            compile_exit(f, 0, AstType::STM_RETURN);
+            compile_exit(f, stm, 0)?;
             f.emitop(OpcodeType::OP_RETURN);
         },
 
         AstType::STM_THROW => {
-            compile_exp(f, stm.a.as_ref().unwrap());
+            compile_exp(f, stm.a.as_ref().unwrap())?;
             f.emitop(OpcodeType::OP_THROW);
         },
 
         AstType::STM_TRY => {
             if stm.has_b() && stm.has_c() {
                 if stm.has_d() {
-                    compile_trycatchfinally(f, stm.a(), stm.b(), stm.c(), stm.d());
+                    compile_trycatchfinally(f, stm.a(), stm.b(), stm.c(), stm.d())?;
                 } else {
-                    compile_trycatch(f, stm.a(), stm.b(), stm.c());
+                    compile_trycatch(f, stm.a(), stm.b(), stm.c())?;
                 }
             } else {
-                compile_finally(f, stm.a(), stm.b()); 
+                compile_finally(f, stm.a(), stm.b())?; 
             }
         },
 
@@ -1317,22 +1414,32 @@ fn compile_stm(f: &mut VMFunction, stm: &AstNode) {
         },
 
         _ => {
-            compile_exp(f, stm);
-            f.emitop(OpcodeType::OP_POP);
+            if f.script {
+                /* keep the value of the last expression for scripts, the old value is
+                 * dropped after the expression so an exception doesn't shrink the stack */
+                compile_exp(f, stm)?;
+                f.emitop(OpcodeType::OP_ROT2);
+                f.emitop(OpcodeType::OP_POP);
+            } else {
+                compile_exp(f, stm)?;
+                f.emitop(OpcodeType::OP_POP);
+            }
         }    
     }
+    return Ok(());
 }
 
-fn compile_stmlist(f: &mut VMFunction, lst: &AstNode) {
+fn compile_stmlist(f: &mut VMFunction, lst: &AstNode) -> Result<(), CompileError> {
     if lst.is_null() {
-        return;
+        return Ok(());
     }
     for stm in lst.iter() {
-        compile_stm(f, stm);
+        compile_stm(f, stm)?;
     }
+    return Ok(());
 }
 
-pub fn compile_func(name: &AstNode, params: &AstNode, body: &AstNode, script: bool) -> Result<VMFunction, String> {
+pub fn compile_func(name: &AstNode, params: &AstNode, body: &AstNode, script: bool) -> Result<VMFunction, CompileError> {
     let mut f = VMFunction::new(script);
 
     // parsing params
@@ -1348,7 +1455,7 @@ pub fn compile_func(name: &AstNode, params: &AstNode, body: &AstNode, script: bo
     if !body.is_null() {
 		f.parsing_vardec(body);
         f.numvars = f.str_tab.len() - f.numparams;
-		f.parsing_fundec(body);
+		f.parsing_fundec(body)?;
     }
 
     if !name.is_null() {
@@ -1361,10 +1468,10 @@ pub fn compile_func(name: &AstNode, params: &AstNode, body: &AstNode, script: bo
 
     if f.script {
         f.emitop(OpcodeType::OP_UNDEF);
-        compile_stmlist(&mut f, body);
+        compile_stmlist(&mut f, body)?;
         f.emitop(OpcodeType::OP_RETURN);
     } else {
-        compile_stmlist(&mut f, body);
+        compile_stmlist(&mut f, body)?;
         f.emitop(OpcodeType::OP_UNDEF);
         f.emitop(OpcodeType::OP_RETURN);
     }
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::runtime::*;
+
+/* conversions between Rust and JS values, hosts can expose typed functions without the VM stack */
+
+pub trait FromJs: Sized {
+	fn from_js(rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException>;
+}
+
+pub trait IntoJs {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException>;
+}
+
+impl FromJs for SharedValue {
+	fn from_js(_rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		return Ok(value.clone());
+	}
+}
+
+impl IntoJs for SharedValue {
+	fn into_js(self, _rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(self);
+	}
+}
+
+impl FromJs for () {
+	fn from_js(_rt: &mut JsRuntime, _value: &SharedValue) -> Result<Self, JsException> {
+		return Ok(());
+	}
+}
+
+impl IntoJs for () {
+	fn into_js(self, _rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(SharedValue::new_undefined());
+	}
+}
+
+impl FromJs for f64 {
+	fn from_js(_rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		return Ok(value.to_number());
+	}
+}
+
+impl IntoJs for f64 {
+	fn into_js(self, _rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(SharedValue::new_number(self));
+	}
+}
+
+// integers are truncated, NaN and numbers out of the range are errors
+macro_rules! integer_conversion {
+	($($t:ty),*) => {
+		$(
+		impl FromJs for $t {
+			fn from_js(_rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+				let n = value.to_number().trunc();
+				if n.is_nan() || n < <$t>::MIN as f64 || n > <$t>::MAX as f64 {
+					return Err(JsException::new_range_error(format!("Can't convert {} to {}", value.to_string(), stringify!($t))));
+				}
+				return Ok(n as $t);
+			}
+		}
+
+		impl IntoJs for $t {
+			fn into_js(self, _rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+				return Ok(SharedValue::new_number(self as f64));
+			}
+		}
+		)*
+	}
+}
+
+integer_conversion!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl FromJs for bool {
+	fn from_js(_rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		return Ok(value.to_boolean());
+	}
+}
+
+impl IntoJs for bool {
+	fn into_js(self, _rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(SharedValue::new_boolean(self));
+	}
+}
+
+// objects are converted by their toString
+impl FromJs for String {
+	fn from_js(rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		return rt.to_string(value.clone());
+	}
+}
+
+impl IntoJs for String {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(rt.new_string(self));
+	}
+}
+
+impl IntoJs for &str {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return Ok(rt.new_string(self.to_string()));
+	}
+}
+
+// undefined and null are None, None is converted to null
+impl<T: FromJs> FromJs for Option<T> {
+	fn from_js(rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		if !value.is_something() {
+			return Ok(None);
+		}
+		return Ok(Some(T::from_js(rt, value)?));
+	}
+}
+
+impl<T: IntoJs> IntoJs for Option<T> {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		match self {
+			Some(v) => v.into_js(rt),
+			None => Ok(SharedValue::new_null()),
+		}
+	}
+}
+
+impl<T: FromJs> FromJs for Vec<T> {
+	fn from_js(rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		if !(value.is_object() && value.get_object().borrow().is_array()) {
+			return Err(JsException::new_type_error(format!("Can't convert {} to an array", value.to_string())));
+		}
+		let values = value.get_object().borrow().get_array().clone();
+		let mut result = Vec::with_capacity(values.len());
+		for v in values.iter() {
+			result.push(T::from_js(rt, v)?);
+		}
+		return Ok(result);
+	}
+}
+
+impl<T: IntoJs> IntoJs for Vec<T> {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		let mut values = Vec::with_capacity(self.len());
+		for v in self {
+			values.push(v.into_js(rt)?);
+		}
+		return Ok(rt.new_array(values));
+	}
+}
+
+// own enumerable properties of objects, getters are called
+impl<T: FromJs> FromJs for HashMap<String, T> {
+	fn from_js(rt: &mut JsRuntime, value: &SharedValue) -> Result<Self, JsException> {
+		if !value.is_object() || value.is_string() {
+			return Err(JsException::new_type_error(format!("Can't convert {} to an object", value.to_string())));
+		}
+		let target = value.get_object();
+		let keys: Vec<String> = target.borrow().properties.iter().filter(|(_, prop)| prop.enumerable()).map(|(k, _)| k.clone()).collect();
+		let mut result = HashMap::new();
+		for k in keys {
+			rt.getproperty(target.clone(), &k)?;
+			let v = rt.stack.pop().unwrap();
+			result.insert(k, T::from_js(rt, &v)?);
+		}
+		return Ok(result);
+	}
+}
+
+impl<T: IntoJs> IntoJs for HashMap<String, T> {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		let obj = SharedValue::new_vanilla(rt.prototypes.object_prototype.clone());
+		for (k, v) in self {
+			let v = v.into_js(rt)?;
+			rt.defproperty(obj.get_object(), &k, v, JS_DEFAULT_ATTR, None, None)?;
+		}
+		return Ok(obj);
+	}
+}
+
+// errors of typed functions are thrown to scripts
+impl<T: IntoJs> IntoJs for Result<T, JsException> {
+	fn into_js(self, rt: &mut JsRuntime) -> Result<SharedValue, JsException> {
+		return self?.into_js(rt);
+	}
+}
+
+/* Rust functions with FromJs arguments and an IntoJs result, Args is the tuple of argument types */
+pub trait JsTypedFunction<Args> {
+	fn argc(&self) -> usize;
+	fn call_js(&self, rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException>;
+}
+
+macro_rules! typed_function {
+	($argc:expr $(, $t:ident $i:tt)*) => {
+		impl<F, R $(, $t)*> JsTypedFunction<($($t,)*)> for F
+			where F: Fn($($t),*) -> R, R: IntoJs $(, $t: FromJs)* {
+			fn argc(&self) -> usize {
+				return $argc;
+			}
+			#[allow(unused_variables)]
+			fn call_js(&self, rt: &mut JsRuntime, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+				return (self)($($t::from_js(rt, &args[$i])?),*).into_js(rt);
+			}
+		}
+	}
+}
+
+typed_function!(0);
+typed_function!(1, A0 0);
+typed_function!(2, A0 0, A1 1);
+typed_function!(3, A0 0, A1 1, A2 2);
+typed_function!(4, A0 0, A1 1, A2 2, A3 3);
+typed_function!(5, A0 0, A1 1, A2 2, A3 3, A4 4);
+typed_function!(6, A0 0, A1 1, A2 2, A3 3, A4 4, A5 5);
+
+impl JsRuntime {
+	// register a typed Rust function as a global, missing arguments are undefined
+	pub fn register_typed_function<Args, F>(&mut self, name: &str, f: F) where F: JsTypedFunction<Args> + 'static {
+		let argc = f.argc();
+		self.register_function(name, move |rt, args| f.call_js(rt, args), argc);
+	}
+}
@@ -0,0 +1,364 @@
+use crate::runtime::*;
+
+/* time values of ES5 15.9.1, milliseconds since 1970-01-01 UTC */
+
+pub const MS_PER_DAY: f64 = 86400000.0;
+const MS_PER_HOUR: f64 = 3600000.0;
+const MS_PER_MINUTE: f64 = 60000.0;
+const MS_PER_SECOND: f64 = 1000.0;
+
+const WEEK_DAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+const MONTH_DAYS: [f64; 13] = [0.0, 31.0, 59.0, 90.0, 120.0, 151.0, 181.0, 212.0, 243.0, 273.0, 304.0, 334.0, 365.0];
+
+fn day(t: f64) -> f64 {
+	return (t / MS_PER_DAY).floor();
+}
+
+fn day_from_year(y: f64) -> f64 {
+	return 365.0 * (y - 1970.0) + ((y - 1969.0) / 4.0).floor() - ((y - 1901.0) / 100.0).floor() + ((y - 1601.0) / 400.0).floor();
+}
+
+fn is_leap_year(y: f64) -> bool {
+	return (y % 4.0 == 0.0 && y % 100.0 != 0.0) || y % 400.0 == 0.0;
+}
+
+// the first day of month m, counting from 0
+fn month_start(m: usize, leap: bool) -> f64 {
+	if leap && m >= 2 {
+		return MONTH_DAYS[m] + 1.0;
+	}
+	return MONTH_DAYS[m];
+}
+
+// the number of days in month m, counting from 0
+fn month_days(year: f64, m: usize) -> f64 {
+	let leap = is_leap_year(year);
+	return month_start(m + 1, leap) - month_start(m, leap);
+}
+
+pub fn year_from_time(t: f64) -> f64 {
+	let mut y = (t / (MS_PER_DAY * 365.2425)).floor() + 1970.0;
+	while day_from_year(y) * MS_PER_DAY > t {
+		y = y - 1.0;
+	}
+	while day_from_year(y + 1.0) * MS_PER_DAY <= t {
+		y = y + 1.0;
+	}
+	return y;
+}
+
+// month and date (from 1) of the time
+fn month_date(t: f64) -> (f64, f64) {
+	let y = year_from_time(t);
+	let leap = is_leap_year(y);
+	let d = day(t) - day_from_year(y);
+	let mut m = 0;
+	while m < 11 && d >= month_start(m + 1, leap) {
+		m = m + 1;
+	}
+	return (m as f64, d - month_start(m, leap) + 1.0);
+}
+
+pub fn month_from_time(t: f64) -> f64 {
+	return month_date(t).0;
+}
+
+pub fn date_from_time(t: f64) -> f64 {
+	return month_date(t).1;
+}
+
+pub fn week_day(t: f64) -> f64 {
+	return (day(t) + 4.0).rem_euclid(7.0) + 0.0;
+}
+
+pub fn hour_from_time(t: f64) -> f64 {
+	return (t / MS_PER_HOUR).floor().rem_euclid(24.0) + 0.0;
+}
+
+pub fn min_from_time(t: f64) -> f64 {
+	return (t / MS_PER_MINUTE).floor().rem_euclid(60.0) + 0.0;
+}
+
+pub fn sec_from_time(t: f64) -> f64 {
+	return (t / MS_PER_SECOND).floor().rem_euclid(60.0) + 0.0;
+}
+
+pub fn ms_from_time(t: f64) -> f64 {
+	return t.rem_euclid(MS_PER_SECOND) + 0.0;
+}
+
+pub fn make_time(hour: f64, min: f64, sec: f64, ms: f64) -> f64 {
+	if !hour.is_finite() || !min.is_finite() || !sec.is_finite() || !ms.is_finite() {
+		return f64::NAN;
+	}
+	return hour.trunc() * MS_PER_HOUR + min.trunc() * MS_PER_MINUTE + sec.trunc() * MS_PER_SECOND + ms.trunc();
+}
+
+pub fn make_day(year: f64, month: f64, date: f64) -> f64 {
+	if !year.is_finite() || !month.is_finite() || !date.is_finite() {
+		return f64::NAN;
+	}
+	let m = month.trunc();
+	let y = year.trunc() + (m / 12.0).floor();
+	let mn = m.rem_euclid(12.0) as usize;
+	if y.abs() > 400000.0 {
+		return f64::NAN;
+	}
+	return day_from_year(y) + month_start(mn, is_leap_year(y)) + date.trunc() - 1.0;
+}
+
+pub fn make_date(day: f64, time: f64) -> f64 {
+	if !day.is_finite() || !time.is_finite() {
+		return f64::NAN;
+	}
+	return day * MS_PER_DAY + time;
+}
+
+pub fn time_clip(t: f64) -> f64 {
+	if !t.is_finite() || t.abs() > 8.64e15 {
+		return f64::NAN;
+	}
+	return t.trunc() + 0.0;
+}
+
+/* formatting, the time is already shifted to local time when needed */
+
+// YYYY-MM-DDTHH:mm:ss.sssZ, extended years are used out of 0..9999
+pub fn iso_string(t: f64) -> String {
+	let y = year_from_time(t);
+	let year = if (0.0..=9999.0).contains(&y) {
+		format!("{:04}", y)
+	} else if y < 0.0 {
+		format!("-{:06}", -y)
+	} else {
+		format!("+{:06}", y)
+	};
+	let (m, d) = month_date(t);
+	return format!("{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, m + 1.0, d,
+		hour_from_time(t), min_from_time(t), sec_from_time(t), ms_from_time(t));
+}
+
+fn year_string(y: f64) -> String {
+	if y < 0.0 {
+		return format!("-{:06}", -y);
+	}
+	return format!("{:04}", y);
+}
+
+// Tue Oct 18 2026
+pub fn date_string(t: f64) -> String {
+	let (m, d) = month_date(t);
+	return format!("{} {} {:02} {}", WEEK_DAYS[week_day(t) as usize], MONTHS[m as usize], d, year_string(year_from_time(t)));
+}
+
+// 10:00:00 GMT+0800, the offset is in milliseconds
+pub fn time_string(t: f64, offset: f64) -> String {
+	let minutes = (offset / MS_PER_MINUTE).round();
+	let sign = if minutes < 0.0 { '-' } else { '+' };
+	let minutes = minutes.abs();
+	return format!("{:02}:{:02}:{:02} GMT{}{:02}{:02}", hour_from_time(t), min_from_time(t), sec_from_time(t),
+		sign, (minutes / 60.0).floor(), minutes % 60.0);
+}
+
+// Tue, 18 Oct 2026 10:00:00 GMT
+pub fn utc_string(t: f64) -> String {
+	let (m, d) = month_date(t);
+	return format!("{}, {:02} {} {} {:02}:{:02}:{:02} GMT", WEEK_DAYS[week_day(t) as usize], d, MONTHS[m as usize],
+		year_string(year_from_time(t)), hour_from_time(t), min_from_time(t), sec_from_time(t));
+}
+
+/* parsing */
+
+struct DateScanner<'a> {
+	s:		&'a [u8],
+	pos:	usize,
+}
+
+impl<'a> DateScanner<'a> {
+	fn accept(&mut self, c: u8) -> bool {
+		if self.pos < self.s.len() && self.s[self.pos] == c {
+			self.pos = self.pos + 1;
+			return true;
+		}
+		return false;
+	}
+
+	// exactly n digits
+	fn digits(&mut self, n: usize) -> Option<f64> {
+		if self.pos + n > self.s.len() {
+			return None;
+		}
+		let mut v = 0.0;
+		for i in 0..n {
+			let c = self.s[self.pos + i];
+			if !c.is_ascii_digit() {
+				return None;
+			}
+			v = v * 10.0 + (c - b'0') as f64;
+		}
+		self.pos = self.pos + n;
+		return Some(v);
+	}
+
+	fn end(&self) -> bool {
+		return self.pos == self.s.len();
+	}
+}
+
+// Date Time String Format of ES5 15.9.1.15, the time is UTC without an offset
+fn parse_iso(s: &str) -> Option<f64> {
+	let mut sc = DateScanner { s: s.as_bytes(), pos: 0 };
+
+	let year = if sc.accept(b'+') {
+		sc.digits(6)?
+	} else if sc.accept(b'-') {
+		-sc.digits(6)?
+	} else {
+		sc.digits(4)?
+	};
+	let mut month = 1.0;
+	let mut date = 1.0;
+	if sc.accept(b'-') {
+		month = sc.digits(2)?;
+		if sc.accept(b'-') {
+			date = sc.digits(2)?;
+		}
+	}
+
+	let (mut hour, mut min, mut sec, mut ms) = (0.0, 0.0, 0.0, 0.0);
+	let mut offset = 0.0;
+	if sc.accept(b'T') {
+		hour = sc.digits(2)?;
+		if !sc.accept(b':') {
+			return None;
+		}
+		min = sc.digits(2)?;
+		if sc.accept(b':') {
+			sec = sc.digits(2)?;
+			if sc.accept(b'.') {
+				// more than 3 digits of fraction are ignored
+				let start = sc.pos;
+				while sc.pos < sc.s.len() && sc.s[sc.pos].is_ascii_digit() {
+					sc.pos = sc.pos + 1;
+				}
+				let fraction = &s[start..sc.pos];
+				if fraction.is_empty() {
+					return None;
+				}
+				let padded = format!("{:0<3}", &fraction[..fraction.len().min(3)]);
+				ms = padded.parse::<f64>().ok()?;
+			}
+		}
+		if sc.accept(b'Z') {
+			offset = 0.0;
+		} else if sc.pos < sc.s.len() && (sc.s[sc.pos] == b'+' || sc.s[sc.pos] == b'-') {
+			let sign = if sc.s[sc.pos] == b'-' { -1.0 } else { 1.0 };
+			sc.pos = sc.pos + 1;
+			let h = sc.digits(2)?;
+			if !sc.accept(b':') {
+				return None;
+			}
+			let m = sc.digits(2)?;
+			if h > 23.0 || m > 59.0 {
+				return None;
+			}
+			offset = sign * (h * MS_PER_HOUR + m * MS_PER_MINUTE);
+		}
+	}
+	if !sc.end() {
+		return None;
+	}
+
+	// out of range fields are errors, they don't roll over into the next month or day
+	if !(1.0..=12.0).contains(&month) || hour > 24.0 || min > 59.0 || sec > 59.0 {
+		return None;
+	}
+	if date < 1.0 || date > month_days(year, month as usize - 1) {
+		return None;
+	}
+	if hour == 24.0 && (min != 0.0 || sec != 0.0 || ms != 0.0) {
+		return None;
+	}
+	let t = make_date(make_day(year, month - 1.0, date), make_time(hour, min, sec, ms));
+	return Some(t - offset);
+}
+
+// the formats of toString and toUTCString, the offset is None without a time zone
+fn parse_text(s: &str) -> Option<(f64, Option<f64>)> {
+	let mut year = None;
+	let mut month = None;
+	let mut date = None;
+	let mut time = (0.0, 0.0, 0.0);
+	let mut offset = None;
+
+	for word in s.split([' ', ',']).filter(|w| !w.is_empty()) {
+		if let Some(m) = MONTHS.iter().position(|n| word.eq_ignore_ascii_case(n)) {
+			month = Some(m as f64);
+		} else if WEEK_DAYS.iter().any(|n| word.eq_ignore_ascii_case(n)) {
+			continue;
+		} else if word.contains(':') {
+			let parts: Vec<&str> = word.split(':').collect();
+			if parts.len() < 2 || parts.len() > 3 {
+				return None;
+			}
+			let h = parts[0].parse::<f64>().ok()?;
+			let m = parts[1].parse::<f64>().ok()?;
+			let sec = if parts.len() == 3 { parts[2].parse::<f64>().ok()? } else { 0.0 };
+			time = (h, m, sec);
+		} else if word == "GMT" || word == "UTC" || word == "Z" {
+			offset = Some(0.0);
+		} else if word.starts_with("GMT") || word.starts_with("UTC") {
+			let zone = &word[3..];
+			let sign = match zone.as_bytes().first() {
+				Some(b'+') => 1.0,
+				Some(b'-') => -1.0,
+				_ => return None,
+			};
+			let digits = &zone[1..];
+			if digits.len() != 4 || !digits.bytes().all(|c| c.is_ascii_digit()) {
+				return None;
+			}
+			let h = digits[..2].parse::<f64>().ok()?;
+			let m = digits[2..].parse::<f64>().ok()?;
+			offset = Some(sign * (h * MS_PER_HOUR + m * MS_PER_MINUTE));
+		} else if let Ok(n) = word.parse::<f64>() {
+			if word.len() <= 2 && date.is_none() {
+				date = Some(n);
+			} else if year.is_none() {
+				year = Some(n);
+			} else {
+				return None;
+			}
+		} else {
+			return None;
+		}
+	}
+
+	let t = make_date(make_day(year?, month?, date?), make_time(time.0, time.1, time.2, 0.0));
+	return Some((t, offset));
+}
+
+// the time value and whether the string has a time zone, None when the string is unknown
+pub fn parse_date(s: &str) -> Option<(f64, bool)> {
+	let s = s.trim();
+	if let Some(t) = parse_iso(s) {
+		return Some((t, true));
+	}
+	let (t, offset) = parse_text(s)?;
+	match offset {
+		Some(offset) => return Some((t - offset, true)),
+		None => return Some((t, false)),
+	}
+}
+
+impl JsRuntime {
+	// LocalTime and UTC of ES5 15.9.1.9
+	pub fn local_time(&self, t: f64) -> f64 {
+		return t + self.clock.local_offset(t);
+	}
+
+	pub fn utc_time(&self, t: f64) -> f64 {
+		return t - self.clock.local_offset(t - self.clock.local_offset(t));
+	}
+}
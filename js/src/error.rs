@@ -0,0 +1,53 @@
+use std::fmt;
+
+use crate::runtime::*;
+
+/* errors reported to the embedding application */
+pub enum Error {
+	SyntaxError {
+		msg:	String,
+		line:	u32,
+		column:	u32,
+	},
+	CompileError {
+		msg:	String,
+		line:	u32,
+	},
+	RuntimeException(SharedValue),		// the value thrown out of the script
+	Aborted(String),					// stopped by the host, scripts can't catch it
+	InternalError(String),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::SyntaxError{msg, line, column} => {
+				return write!(f, "SyntaxError: {} (line {}, column {})", msg, line, column);
+			},
+			Error::CompileError{msg, line} => {
+				return write!(f, "CompileError: {} (line {})", msg, line);
+			},
+			Error::RuntimeException(value) => {
+				if value.is_exception() {
+					let e = value.get_object().borrow().get_exception();
+					return write!(f, "{}", e.stack());
+				}
+				return write!(f, "Exception: {}", value.to_string());
+			},
+			Error::Aborted(msg) => {
+				return write!(f, "Aborted: {}", msg);
+			},
+			Error::InternalError(msg) => {
+				return write!(f, "InternalError: {}", msg);
+			},
+		}
+	}
+}
+
+impl fmt::Debug for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		return fmt::Display::fmt(self, f);
+	}
+}
+
+impl std::error::Error for Error {}
@@ -1,8 +1,12 @@
 use std::rc::Rc;
+use std::any::Any;
 use std::cmp;
 
+use crate::common::*;
 use crate::bytecode::*;
 use crate::runtime::*;
+use crate::build_function_within;
+use crate::error::Error;
 
 /* implementation for JsEnvironment, partly JsRuntime and jscall */
 
@@ -65,6 +69,32 @@ impl JsEnvironment {
 	fn drop_variable(&self, name: &str) {
 		self.variables.borrow_mut().drop_property(name);
 	}
+
+	/* inspecting for debuggers */
+	pub fn outer(&self) -> Option<SharedScope> {
+		return self.outer.clone();
+	}
+
+	// variables declared in this scope, sorted by name
+	pub fn variables(&self) -> Vec<(String, SharedValue)> {
+		let mut vars: Vec<(String, SharedValue)> = Vec::new();
+		for (name, prop) in self.variables.borrow().properties.iter() {
+			vars.push((name.clone(), prop.value.clone()));
+		}
+		vars.sort_by(|a, b| a.0.cmp(&b.0));
+		return vars;
+	}
+
+	// find a variable along the scope chain
+	pub fn lookup(&self, name: &str) -> Option<SharedValue> {
+		if self.query_variable(name) {
+			return Some(self.get_variable(name).value);
+		}
+		if let Some(outer) = &self.outer {
+			return outer.borrow().lookup(name);
+		}
+		return None;
+	}
 }
 
 impl JsRuntime {
@@ -130,7 +160,7 @@ impl JsRuntime {
 
 	/* properties operation */
     // make a new  or replace proptery o for object, following reler of object's attr
-    fn defproperty(&mut self, target_: SharedObject, name: &str, value: SharedValue,
+    pub fn defproperty(&mut self, target_: SharedObject, name: &str, value: SharedValue,
 		attr:JsPropertyAttr, getter: Option<SharedObject>, setter: Option<SharedObject>) -> Result<(), JsException> {
 		let mut target = target_.borrow_mut();
 
@@ -167,14 +197,55 @@ impl JsRuntime {
 			target.set_property(name, prop);
 			return Ok(());
 		} else {
-			return Err(JsException::new(format!("TODO: {}", line!())));
+			return Err(JsException::new_type_error(format!("Can't add property '{}', object is not extensible", name)));
 		}
 	}
 
 	// change value of the proptery for object
-	fn setproperty(&mut self, target_: SharedObject, name: &str, value: SharedValue) -> Result<(), JsException> {
+	pub fn setproperty(&mut self, target_: SharedObject, name: &str, value: SharedValue) -> Result<(), JsException> {
+		/* elements and length of arrays are kept in the vector */
+		if target_.borrow().is_array() {
+			// the value may be the array itself, it's copied before the array is borrowed
+			let value = value.duplicate();
+			let mut target = target_.borrow_mut();
+			let (writable, _, configurable) = target.elements_attr;
+			if name == "length" {
+				let n = value.to_number();
+				if !(n >= 0.0 && n < 4294967296.0 && n.fract() == 0.0) {
+					return Err(JsException::new_range_error("Invalid array length".to_string()));
+				}
+				if n as usize > JS_ARRAY_MAX_LENGTH {
+					return Err(JsException::new_range_error("Array length is too large".to_string()));
+				}
+				let len = target.get_array().len();
+				if !writable || (!configurable && (n as usize) < len) || (!target.extensible && (n as usize) > len) {
+					return Err(JsException::new_type_error("Can't change length of a frozen or sealed array".to_string()));
+				}
+				target.get_mut_array().resize_with(n as usize, SharedValue::new_undefined);
+				return Ok(());
+			}
+			if let Some(idx) = str_to_index(name) {
+				if !writable {
+					return Err(JsException::new_type_error(format!("Can't write read-only property '{}'", name)));
+				}
+				let extensible = target.extensible;
+				let v = target.get_mut_array();
+				if idx >= v.len() {
+					if !extensible {
+						return Err(JsException::new_type_error(format!("Can't add property '{}', object is not extensible", name)));
+					}
+					if idx >= JS_ARRAY_MAX_LENGTH {
+						return Err(JsException::new_range_error("Array length is too large".to_string()));
+					}
+					v.resize_with(idx + 1, SharedValue::new_undefined);
+				}
+				v[idx] = value;
+				return Ok(());
+			}
+		}
+
 		let prop_r = target_.borrow().query_property(name);
-		if let Some((mut prop, _own)) = prop_r {
+		if let Some((mut prop, own)) = prop_r {
 			if let Some(setter) = prop.setter {
 				self.push_object(setter.clone());
 				self.push_object(target_.clone());
@@ -183,46 +254,84 @@ impl JsRuntime {
 				self.pop(1);
 				return Ok(());
 			}
-			if prop.writeable() {
+			if prop.getter.is_some() || !prop.writeable() {
+				return Err(JsException::new_type_error(format!("Can't write read-only property '{}'", name)));
+			}
+			if own {
 				prop.value.replace( value );
 				return Ok(());
-			} else {								
-				println!("Cant write property for specia object!");
-				return Err(JsException::new(format!("TODO: {}", line!())));
 			}
+			/* writable properties inherited from prototypes are shadowed by a new own one */
 		} 
 
 		/* Property not found on this object, so create one with default attr*/
-		self.defproperty(target_, name, value, JS_DEFAULT_ATTR, None, None)?;
+		self.defproperty(target_, name, value.duplicate(), JS_DEFAULT_ATTR, None, None)?;
 		return Ok(());	
 	}	
 
+	// the object to read properties from, numbers and booleans use their prototypes
+	fn property_target(&self, value: &SharedValue) -> Option<SharedObject> {
+		if value.is_object() {
+			return Some(value.get_object());
+		}
+		if value.is_number() {
+			return Some(self.prototypes.number_prototype.clone());
+		}
+		if value.is_boolean() {
+			return Some(self.prototypes.boolean_prototype.clone());
+		}
+		return None;
+	}
+
 	// get value from the proptery of object
-	fn getproperty(&mut self, target_: SharedObject, name: &str) -> Result<bool, JsException> {		
+	pub fn getproperty(&mut self, target_: SharedObject, name: &str) -> Result<bool, JsException> {		
 		let target = target_.borrow();
 		let target_ = target_.clone();
 
 		// get value from index
 		match target.value {
-			JsClass::string(ref s) => {				
+			JsClass::string(ref s) => {
+				/* strings are indexed by UTF-16 code units */
+				if name == "length" {
+					self.push_number( s.encode_utf16().count() as f64 );
+					return Ok(true);
+				}
 				if let Ok(idx) = name.parse::<usize>() {
-					if idx < s.len() {
-						self.push_string( s[idx..idx+1].to_string() ); 
+					if let Some(c) = s.encode_utf16().nth(idx) {
+						self.push_string( String::from_utf16_lossy(&[c]) );
 						return Ok(true);
 					}
 				}
 			},
 			JsClass::array(ref v) => {
-				if let Ok(idx) = name.parse::<usize>() {
+				if name == "length" {
+					self.push_number( v.len() as f64 );
+					return Ok(true);
+				}
+				if let Some(idx) = str_to_index(name) {
 					if idx < v.len() {
 						self.push( v[idx].clone() );
 						return Ok(true);
 					}
 				}
 			},
+			JsClass::function(_) | JsClass::builtin(_) | JsClass::bound(_) => {
+				if name == "length" && !target.properties.contains_key(name) {
+					self.push_number( target.func_length() as f64 );
+					return Ok(true);
+				}
+				if name == "name" && !target.properties.contains_key(name) {
+					self.push_string( target.func_name() );
+					return Ok(true);
+				}
+			},
 			_ => {}
 		}		
-		let prop_r = target.query_property(name);
+		let mut prop_r = target.query_property(name);
+		if prop_r.is_none() && target.callable() && target.__proto__.is_none() {
+			/* builtins are created before Function.prototype, it's their prototype too */
+			prop_r = self.prototypes.function_prototype.borrow().query_property(name);
+		}
 		if let Some((prop, _own)) = prop_r {
 			if let Some(getter) = prop.getter {
 				self.push_object(getter.clone());
@@ -236,27 +345,41 @@ impl JsRuntime {
 		self.push_undefined();
 		return Ok(false);
 	}		
-	fn delproperty(&mut self, target_: SharedObject, name: &str) -> bool {		
+	pub fn delproperty(&mut self, target_: SharedObject, name: &str) -> bool {		
 		let mut target = target_.borrow_mut();
 
 		match target.value {
-			JsClass::object => {},
+			JsClass::object | JsClass::host(_) => {},
+			JsClass::array(_) => {
+				/* elements are kept in the vector, deleted ones become undefined */
+				if name == "length" {
+					return false;
+				}
+				if let Some(idx) = str_to_index(name) {
+					if idx >= target.get_array().len() {
+						return true;
+					}
+					if !target.elements_attr.2 {
+						return false;
+					}
+					target.get_mut_array()[idx] = SharedValue::new_undefined();
+					return true;
+				}
+			},
 			_ => {
 				println!("Cant delete property for specia object!");
 				return false;
 			}
 		}
 
-		let prop_r = target.query_property(name);
-		if let Some((prop, own)) = prop_r {
-			if own {
-				if prop.configable() {
-					target.drop_property(name);
-					return true;
-				}
+		// only own configurable properties can be deleted, missing ones are deleted already
+		if let Some(prop) = target.properties.get(name) {
+			if !prop.configable() {
+				return false;
 			}
+			target.drop_property(name);
 		}
-		return false;
+		return true;
 	}	
 
 	/* item + item */
@@ -265,7 +388,8 @@ impl JsRuntime {
 		let y = self.top(-1);
 		self.pop(2);
 
-		if x.is_number() {			
+		// strings and objects are concatenated, others are added as numbers
+		if !x.is_object() && !y.is_object() {
 			let x = x.to_number();
 			let y = y.to_number();
 			self.push_number(x+y);
@@ -366,95 +490,42 @@ impl JsRuntime {
 
 	fn strict_equal(&mut self) -> bool {
 		let x = self.top(-2);
-		let y = self.top(-1);		
-		
-		// string with others
-		if x.is_string() {
-			let x_str = x.to_string();
-			if y.is_string() {
-				let y_str = y.to_string();
-				if x_str == y_str {
-					return true;
-				}
-			} 
-			return false;
-		}
-
-		// null with defineded
-		if x.is_undefined() {
-			if y.is_undefined() {
-				return true;
-			}			
-			return false;
-		}
-
-		if x.is_null() {			
-			if y.is_null() {
-				return true;
-			}
-			return false;
-		}
-
-		// boolean with boolean
-		if x.is_boolean()  {
-			if y.is_boolean() {
-				return x.to_boolean() == y.to_boolean();
-			}
-			return false;
-		}
-
-		// number with others
-		if x.is_number() {
-			let x_num = x.to_number();
-			if y.is_number() {
-				let y_num = y.to_number();
-				if x_num == y_num {
-					return true;
-				}
-			}
-			return false;
-		}
-
-		// object with object
-		let x_obj = x.get_object();
-		if y.is_object() {
-			let y_obj = y.get_object();
-			return Rc::ptr_eq(&x_obj, &y_obj);
-		}
-		return false;
+		let y = self.top(-1);
+		return x.strict_equals(&y);
 	}
 
-	fn compare_item(&mut self) -> Option<i32> {
+	fn compare_item(&mut self) -> Result<Option<i32>, JsException> {
 		let x = self.top(-2);
 		let y = self.top(-1);
 		self.pop(2);
 
-		if x.is_number() {
-			let x = x.to_number();
-			let y = y.to_number();
-			if x == f64::NAN || y == f64::NAN {
-				return None;
-			}
-			if x > y {
-				return Some(1);
-			} else if x == y {
-				return Some(0);
-			} else  {
-				return Some(-1);
-			}
-		}
-		if x.is_string() {
+		// objects are compared by their primitive values, e.g. dates by their time values
+		let x = self.primitive_number(x)?;
+		let y = self.primitive_number(y)?;
+		if x.is_string() && y.is_string() {
 			let x = x.to_string();
 			let y = y.to_string();
 			if x > y {
-				return Some(1);
+				return Ok(Some(1));
 			} else if x == y {
-				return Some(0);
+				return Ok(Some(0));
 			} else  {
-				return Some(-1);
+				return Ok(Some(-1));
 			}
 		}
-		return None;
+
+		let x = x.to_number();
+		let y = y.to_number();
+		if x.is_nan() || y.is_nan() {
+			return Ok(None);
+		}
+		if x > y {
+			return Ok(Some(1));
+		} else if x == y {
+			return Ok(Some(0));
+		} else  {
+			return Ok(Some(-1));
+		}
 	}
 
 	fn in_operator(&mut self) -> Result<(), JsException> {
@@ -463,12 +534,21 @@ impl JsRuntime {
 		self.pop(2);
 		
 		if !y.is_object() {
-			println!("in: invalid operand");
-			self.push_boolean(false);
-			return Ok(());
+			return Err(JsException::new_type_error("Right operand of 'in' is not an object".to_string()));
 		}
 
 		let propstr = self.to_string(x)?;
+		if y.get_object().borrow().is_array() {
+			if let Some(idx) = str_to_index(&propstr) {
+				let b = idx < y.get_object().borrow().get_array().len();
+				self.push_boolean(b);
+				return Ok(());
+			}
+			if propstr == "length" {
+				self.push_boolean(true);
+				return Ok(());
+			}
+		}
 		if let Some((_prop, _own)) = y.get_object().borrow().query_property(&propstr) {
 			self.push_boolean(true);
 			return Ok(());
@@ -483,21 +563,19 @@ impl JsRuntime {
 		let y = self.top(-1);
 		self.pop(2);
 		
-		if !x.is_object() {
-			self.push_boolean(false);
-			return Ok(());
+		if !y.is_object() || !y.get_object().borrow().callable() {
+			return Err(JsException::new_type_error("Right operand of 'instanceof' is not callable".to_string()));
 		}
-		if !y.is_object() {
-			println!("instanceof: invalid operand");
+		if !x.is_object() {
 			self.push_boolean(false);
 			return Ok(());
 		}
 		let mut x = x.get_object();
-		let y = y.get_object();
-		if !y.borrow().callable() {
-			println!("instanceof: invalid operand");
-			self.push_boolean(false);
-			return Ok(());
+		let mut y = y.get_object();
+		/* bound functions use the prototype of their targets */
+		while y.borrow().is_bound() {
+			let target = y.borrow().get_bound().target.clone();
+			y = target;
 		}
 
 		self.getproperty(y, "prototype")?;
@@ -527,11 +605,84 @@ impl JsRuntime {
 		return Ok(());
 	}
 
+	fn is_eval(&self, value: &SharedValue) -> bool {
+		if value.is_object() {
+			return Rc::ptr_eq(&value.get_object(), &self.eval_function);
+		}
+		return false;
+	}
+
 	/* Exceptions */
-	pub fn new_exception(&mut self, e: JsException) {
-		let obj = JsObject::new_exception(self.prototypes.exception_prototype.clone(), e);
-		let value = SharedValue::new_object(obj);
-		self.push(value);
+	pub fn trace_exception(&self, e: &mut JsException) {
+		for frame in self.frames.iter().rev() {
+			let vmf = &frame.vmf;
+			let location = format!("{}:{}", vmf.filename, vmf.line(frame.pc));
+			let name = if vmf.script {
+				"<script>".to_string()
+			} else if let Some(ref name) = vmf.name {
+				name.clone()
+			} else {
+				"<anonymous>".to_string()
+			};
+
+			if e.trace.len() == 0 {
+				e.location = location.clone();
+			}
+			e.trace.push(format!("{} ({})", name, location));
+		}
+	}
+
+	// 'this' of the running native function, null when it's called as a constructor
+	pub fn this_value(&self) -> SharedValue {
+		return self.native_this.clone();
+	}
+
+	/* string and array values of this runtime, e.g. results of natives */
+	pub fn new_string(&self, s: String) -> SharedValue {
+		let obj = JsObject::new_with(self.prototypes.string_prototype.clone(), JsClass::string(s));
+		return SharedValue::new_object(obj);
+	}
+
+	pub fn new_array(&self, values: Vec<SharedValue>) -> SharedValue {
+		let obj = JsObject::new_with(self.prototypes.array_prototype.clone(), JsClass::array(values));
+		return SharedValue::new_object(obj);
+	}
+
+	/* the error object thrown to scripts for exceptions raised by the runtime */
+	pub fn new_exception(&mut self, e: JsException) -> SharedValue {
+		let stack = JsObject::new_with(self.prototypes.string_prototype.clone(), JsClass::string(e.stack()));
+		let mut prop = JsProperty::new();
+		prop.fill_attr((true, false, true));
+		prop.value = SharedValue::new_object(stack);
+
+		let prototype = match e.kind {
+			JsErrorType::Exception => self.prototypes.exception_prototype.clone(),
+			JsErrorType::Error => self.prototypes.error_prototype.clone(),
+			JsErrorType::TypeError => self.prototypes.type_error_prototype.clone(),
+			JsErrorType::RangeError => self.prototypes.range_error_prototype.clone(),
+			JsErrorType::ReferenceError => self.prototypes.reference_error_prototype.clone(),
+			JsErrorType::SyntaxError => self.prototypes.syntax_error_prototype.clone(),
+		};
+
+		/* errors keep message as a property, the legacy Exception has a message() method */
+		let message = if e.kind != JsErrorType::Exception {
+			let s = JsObject::new_with(self.prototypes.string_prototype.clone(), JsClass::string(e.msg.clone()));
+			let mut prop = JsProperty::new();
+			prop.fill_attr((true, false, true));
+			prop.value = SharedValue::new_object(s);
+			Some(prop)
+		} else {
+			None
+		};
+
+		let extensible = e.kind != JsErrorType::Exception;
+		let mut obj = JsObject::new_exception(prototype, e);
+		obj.extensible = extensible;
+		obj.set_property("stack", prop);
+		if let Some(message) = message {
+			obj.set_property("message", message);
+		}
+		return SharedValue::new_object(obj);
 	}
 
 	/* convert object to string */
@@ -557,9 +708,50 @@ impl JsRuntime {
 		return Ok(target.to_string());	
 	}
 
+	/* convert object to primitive, valueOf is tried before toString */
+	fn primitive_number(&mut self, target: SharedValue) -> Result<SharedValue, JsException> {
+		if !target.is_object() || target.is_string() {
+			return Ok(target);
+		}
+		self.getproperty(target.get_object(), "valueOf")?;
+		let object = self.top(-1);
+		self.pop(1);
+		if object.is_object() && object.get_object().borrow().callable() {
+			self.push(object);			// func
+			self.push(target.clone());	// this
+			jscall(self, 0)?;
+			let result = self.top(-1);
+			self.pop(1);
+			if !result.is_object() || result.is_string() {
+				return Ok(result);
+			}
+		}
+		let s = self.to_string(target)?;
+		return Ok(self.new_string(s));
+	}
+
 	/* create new object */
 	fn new_call(&mut self, argc: usize) -> Result<(), JsException> {
-		let obj = self.top(-1 - argc as isize).get_object();
+		let func = self.top(-1 - argc as isize);
+		if !func.is_object() || !func.get_object().borrow().callable() {
+			return Err(JsException::new_type_error("Can't construct with none function object".to_string()));
+		}
+		let mut obj = func.get_object();
+
+		/* bound functions construct with their targets, the bound this is ignored */
+		let mut argc = argc;
+		let bot = self.stack.len() - 1 - argc;
+		while obj.borrow().is_bound() {
+			let (target, args) = {
+				let obj = obj.borrow();
+				let bound = obj.get_bound();
+				(bound.target.clone(), bound.args.clone())
+			};
+			argc = argc + args.len();
+			self.stack[bot] = SharedValue::new_sobject(target.clone());
+			self.stack.splice(bot+1..bot+1, args);
+			obj = target;
+		}
 
 		/* built-in constructors create their own objects, give them a 'null' this */
 		if obj.borrow().is_builtin() {
@@ -567,7 +759,7 @@ impl JsRuntime {
 			if argc > 0 {
 				self.rot(argc+1);				
 			}
-			jscall_builtin(self, argc);
+			jscall_builtin(self, argc)?;
 			return Ok(());
 		}
 		
@@ -652,9 +844,7 @@ impl JsRuntime {
 		self.stack.push(jv);
 	}
 	pub fn push_string(&mut self, v:String) {
-		let jclass = JsClass::string(v);
-		let jobj = JsObject::new_with(self.prototypes.string_prototype.clone(), jclass);
-		let jv = SharedValue::new_object(jobj);
+		let jv = self.new_string(v);
 		self.stack.push(jv);
 	}
 	pub fn push_object(&mut self, target: SharedObject) {		
@@ -751,39 +941,239 @@ impl JsRuntime {
 		self.pop(1);
 	}
 
-	fn debugger(&mut self) {
-		// runtime virtual machine debugger
-		println!("=======>{}", self.stack.len());
+	pub fn set_debugger(&mut self, debugger: Box<dyn Debugger>) {
+		self.debugger = debugger;
+	}
+
+	pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+		self.clock = clock;
+	}
+
+	/* PRNG of Math.random, xorshift64* */
+	pub fn set_random_seed(&mut self, seed: u64) {
+		// mix the seed with splitmix64, the state can't be zero
+		let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+		z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+		z = z ^ (z >> 31);
+		if z == 0 {
+			z = 0x9E3779B97F4A7C15;
+		}
+		self.random_state = z;
+	}
+
+	// a number in [0, 1)
+	pub fn random(&mut self) -> f64 {
+		let mut x = self.random_state;
+		x ^= x >> 12;
+		x ^= x << 25;
+		x ^= x >> 27;
+		self.random_state = x;
+		let r = x.wrapping_mul(0x2545F4914F6CDD1D);
+		return (r >> 11) as f64 / (1u64 << 53) as f64;
+	}
+
+	pub fn set_limits(&mut self, limits: JsLimits) {
+		self.limits = limits;
+	}
+
+	/* register a native function as a global, closures can capture the state of the host */
+	pub fn register_function<F>(&mut self, name: &str, f: F, argc: usize)
+	where F: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static {
+		self.register_boxed_function(name, Box::new(f), argc);
+	}
+
+	pub fn register_boxed_function(&mut self, name: &str, f: Box<JsNativeFunction>, argc: usize) {
+		let fobj = JsObject::new_builtin(JsBuiltinFunction::new_boxed(f, argc));
+		self.genv.borrow_mut().init_var(name, SharedValue::new_object(fobj));
+	}
+
+	/* build the prototype of a host class, objects of the class are created by new_host_object */
+	pub fn new_host_class(&mut self, class: JsHostClass) -> JsHostType {
+		let class = Rc::new(class);
+		let mut prototype = JsObject::new_with(self.prototypes.object_prototype.clone(), JsClass::object);
+		prototype.extensible = false;
+		for (name, f) in class.methods.iter() {
+			let mut prop = JsProperty::new();
+			prop.fill_attr(JS_READONLY_ATTR);
+			prop.value = SharedValue::new_object(JsObject::new_builtin(host_method(&class, name, f)));
+			prototype.properties.insert(name.clone(), prop);
+		}
+		for (name, getter, setter) in class.accessors.iter() {
+			let mut prop = JsProperty::new();
+			prop.fill_attr(JS_READONLY_ATTR);
+			prop.getter = getter.as_ref().map(|f| SharedObject_new(JsObject::new_builtin(host_method(&class, name, f))));
+			prop.setter = setter.as_ref().map(|f| SharedObject_new(JsObject::new_builtin(host_method(&class, name, f))));
+			prototype.properties.insert(name.clone(), prop);
+		}
+		let host_type = JsHostType {
+			class: class,
+			prototype: SharedObject_new(prototype),
+		};
+		self.host_types.push(host_type.clone());
+		return host_type;
+	}
+
+	// the type must be registered in this runtime or in the template of this realm
+	pub fn new_host_object(&mut self, host_type: &JsHostType, payload: Rc<dyn Any>) -> Result<SharedValue, JsException> {
+		let prototype = match self.host_types.iter().find(|t| Rc::ptr_eq(&t.class, &host_type.class)) {
+			Some(t) => t.prototype.clone(),
+			None => return Err(JsException::new_type_error(format!("Host class '{}' isn't registered in this runtime", host_type.class.name))),
+		};
+		let obj = JsObject::new_host(prototype, host_type.class.clone(), payload);
+		return Ok(SharedValue::new_object(obj));
+	}
+
+	// count the opcode and check the limits, exceeding aborts the script
+	fn check_limits(&mut self) -> Result<(), JsException> {
+		self.opcodes = self.opcodes + 1;
+		if self.limits.max_opcodes > 0 && self.opcodes > self.limits.max_opcodes {
+			return Err(JsException::new_abort("opcode limit exceeded".to_string()));
+		}
+		if self.limits.max_stack > 0 && self.stack.len() > self.limits.max_stack {
+			return Err(JsException::new_abort("stack limit exceeded".to_string()));
+		}
+		return Ok(());
+	}
+	/* enter a nesting level, e.g. a call, each successful enter_nested is paired with leave_nested */
+	pub fn enter_nested(&mut self) -> Result<(), JsException> {
+		// the Rust stack grows down, its use is measured from the outermost level
+		let addr = stack_address();
+		if self.depth == 0 {
+			self.native_base = addr;
+		}
+		let too_deep = self.limits.max_depth > 0 && self.depth >= self.limits.max_depth;
+		let too_large = self.limits.max_native_stack > 0 && self.native_base.saturating_sub(addr) > self.limits.max_native_stack;
+		if too_deep || too_large {
+			return Err(JsException::new_range_error("Maximum call stack size exceeded".to_string()));
+		}
+		self.depth = self.depth + 1;
+		return Ok(());
+	}
+	pub fn leave_nested(&mut self) {
+		self.depth = self.depth - 1;
+	}
+
+	/* breakpoints, the function is identified by its address */
+	pub fn set_breakpoint(&mut self, vmf: &VMFunction, line: u32) {
+		self.breakpoints.insert((vmf as *const VMFunction as usize, line));
+	}
+	pub fn clear_breakpoint(&mut self, vmf: &VMFunction, line: u32) {
+		self.breakpoints.remove(&(vmf as *const VMFunction as usize, line));
+	}
+	pub fn clear_breakpoints(&mut self) {
+		self.breakpoints.clear();
+	}
+
+	// should the debugger be called before the opcode at pc
+	fn should_break(&self, func: &VMFunction, pc: usize) -> bool {
+		match self.debug_mode {
+			DebugAction::Step => {
+				return true;
+			},
+			DebugAction::StepLine => {
+				return func.line_start(pc).is_some();
+			},
+			_ => {}
+		}
+		if self.breakpoints.len() == 0 {
+			return false;
+		}
+		if let Some(line) = func.line_start(pc) {
+			return self.breakpoints.contains(&(func as *const VMFunction as usize, line));
+		}
+		return false;
+	}
+
+	// call the debugger hook before the opcode at pc
+	fn debug(&mut self, func: &VMFunction, pc: usize) -> Result<(), JsException> {
+		let action = self.debugger.on_debug(func, pc, &self.stack, &self.cenv);
+		if action == DebugAction::Abort {
+			self.debug_mode = DebugAction::Continue;
+			return Err(JsException::new_abort("Aborted by debugger".to_string()));
+		}
+		self.debug_mode = action;
+		return Ok(());
 	}
 	
 }
 
 
 
+// methods of host classes check 'this', natives of the class always get their own payload
+fn host_method(class: &Rc<JsHostClass>, name: &str, f: &JsBuiltinFunction) -> JsBuiltinFunction {
+	let class = class.clone();
+	let name = name.to_string();
+	let native = f.f.clone();
+	return JsBuiltinFunction::new(move |rt, args| {
+		let this = rt.this_value();
+		let matched = this.is_object() && match this.get_object().borrow().value {
+			JsClass::host(ref h) => Rc::ptr_eq(&h.class, &class),
+			_ => false,
+		};
+		if !matched {
+			return Err(JsException::new_type_error(format!("{}.{} called on incompatible object", class.name, name)));
+		}
+		return native(rt, args);
+	}, f.argc);
+}
+
+// unwind to the innermost catch block, the address of the catch block is returned
+#[inline(never)]
+fn catch_exception(rt: &mut JsRuntime, mut e: JsException, catch_scopes: &mut Vec<(usize, usize, SharedScope)>) -> Result<usize, JsException> {
+	if e.trace.len() == 0 {
+		rt.trace_exception(&mut e);
+	}
+	if e.abort {
+		return Err(e);
+	}
+	if let Some((new_pc, new_top, env)) = catch_scopes.pop() {
+		let dropped = rt.stack.len() - new_top;
+		rt.pop(dropped);
+		rt.cenv = env;
+
+		if let Some(value) = e.value {
+			rt.push(value);
+		} else {
+			let value = rt.new_exception(e);
+			rt.push(value);
+		}
+		return Ok(new_pc);
+	}
+	return Err(e);
+}
+
 fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsException> {
 	assert!(rt.stack.len() > 0);
 	let mut pc:usize = pc;
 	let bot:usize = rt.stack.len() - 1;
 
-	let mut catch_scopes: Vec<(usize, usize)> = Vec::new();
+	let mut catch_scopes: Vec<(usize, usize, SharedScope)> = Vec::new();
 
 	macro_rules! handle_exception {
-		// `()` indicates that the macro takes no argument.
 		($e:ident) => {
-			if let Some((new_pc, new_top)) = catch_scopes.pop() {
-				let dropped = rt.stack.len() - new_top;
-				rt.pop(dropped);
-	
-				rt.new_exception($e);
-				pc = new_pc;
-				continue;
-			} else {
-				break;
+			match catch_exception(rt, $e, &mut catch_scopes) {
+				Ok(new_pc) => {
+					pc = new_pc;
+					continue;
+				},
+				Err(e) => {
+					return Err(e);
+				}
 			}
 		}
 	}
 
 	loop {
+		rt.frames.last_mut().unwrap().pc = pc;
+		if let Err(e) = rt.check_limits() {
+			handle_exception!(e);
+		}
+		if rt.should_break(func, pc) {
+			if let Err(e) = rt.debug(func, pc) {
+				handle_exception!(e);
+			}
+		}
 		let opcode = func.opcode(&mut pc);
 		match opcode {
 			OpcodeType::OP_POP => {
@@ -840,6 +1230,17 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 				let obj = SharedValue::new_vanilla(rt.prototypes.object_prototype.clone());
 				rt.push(obj);
 			},
+			OpcodeType::OP_NEWREGEXP => {
+				let flags = rt.top(-1).to_string();
+				let pattern = rt.top(-2).to_string();
+				rt.pop(2);
+				match rt.new_regexp(&pattern, &flags) {
+					Ok(re) => rt.push(re),
+					Err(e) => {
+						handle_exception!(e);
+					}
+				}
+			},
 			OpcodeType::OP_NEWARRAY => {
 				let a = JsClass::array(Vec::new());
 				let obj = JsObject::new_with(rt.prototypes.array_prototype.clone(), a);
@@ -868,8 +1269,7 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 						if br == true {
 							continue;
 						} else {
-							println!("'{}' is not defined", s);
-							JsException::new(format!("TODO: {}", line!()))
+							JsException::new_reference_error(format!("'{}' is not defined", s))
 						}
 					},
 					Err(e) => {
@@ -961,7 +1361,13 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 			},
 
 			OpcodeType::OP_GETPROP => {
-				let target = rt.top(-2).get_object();
+				let target = match rt.property_target(&rt.top(-2)) {
+					Some(target) => target,
+					None => {
+						let e = JsException::new_type_error("Access none objects's property!".to_string());
+						handle_exception!(e);
+					}
+				};
 				let name = match rt.to_string( rt.top(-1)) {
 					Ok(s) => s,
 					Err(e) => {
@@ -974,12 +1380,13 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 				rt.rot3pop2();
 			},
 			OpcodeType::OP_GETPROP_S => {
-				let target = rt.top(-1);
-				if !target.is_object() {
-					let e = JsException::new("Access none objects's property!".to_string());					
-					handle_exception!(e);
-				}
-				let target = target.get_object();
+				let target = match rt.property_target(&rt.top(-1)) {
+					Some(target) => target,
+					None => {
+						let e = JsException::new_type_error("Access none objects's property!".to_string());
+						handle_exception!(e);
+					}
+				};
 				let name = func.string(&mut pc);
 				if let Err(e) = rt.getproperty(target, &name) {
 					handle_exception!(e);
@@ -987,8 +1394,18 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 				rt.rot2pop1();
 			},
 			OpcodeType::OP_SETPROP => {
-				let target = rt.top(-3).get_object();
-				let name = rt.to_string( rt.top(-2))?;
+				let target = rt.top(-3);
+				if !target.is_object() {
+					let e = JsException::new_type_error("Access none objects's property!".to_string());
+					handle_exception!(e);
+				}
+				let target = target.get_object();
+				let name = match rt.to_string( rt.top(-2)) {
+					Ok(s) => s,
+					Err(e) => {
+						handle_exception!(e);
+					}
+				};
 				let value = rt.top(-1);
 				if let Err(e) = rt.setproperty(target, &name, value) {
 					handle_exception!(e);
@@ -996,7 +1413,12 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 				rt.rot3pop2();
 			},
 			OpcodeType::OP_SETPROP_S => {
-				let target = rt.top(-2).get_object();
+				let target = rt.top(-2);
+				if !target.is_object() {
+					let e = JsException::new_type_error("Access none objects's property!".to_string());
+					handle_exception!(e);
+				}
+				let target = target.get_object();
 				let value = rt.top(-1);
 				let name = func.string(&mut pc);
 				if let Err(e) = rt.setproperty(target, &name, value) {
@@ -1005,7 +1427,12 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 				rt.rot2pop1();
 			},
 			OpcodeType::OP_DELPROP => {
-				let target = rt.top(-2).get_object();
+				let target = rt.top(-2);
+				if !target.is_object() {
+					let e = JsException::new_type_error("Can't delete none object's proptery".to_string());
+					handle_exception!(e);
+				}
+				let target = target.get_object();
 				let name = match rt.to_string( rt.top(-1)) {
 					Ok(s) => s,
 					Err(e) => {
@@ -1025,7 +1452,7 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 					rt.pop(1);
 					rt.push_boolean(b);
 				} else {					
-					let e = JsException::new("Can't delete none object's proptery".to_string());
+					let e = JsException::new_type_error("Can't delete none object's proptery".to_string());
 					handle_exception!(e);
 				}
 			},
@@ -1061,6 +1488,21 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 			},
 			
 			/* Function calls */
+			OpcodeType::OP_EVAL => {
+				let n = func.int(&mut pc) as usize;
+				let fbot = rt.stack.len() - 1 - n;
+				let result = if rt.is_eval(&rt.stack[fbot-1]) {
+					/* direct eval runs with the caller's scope and this */
+					rt.stack[fbot] = rt.stack[bot].clone();
+					let env = rt.cenv.clone();
+					jseval(rt, n, env)
+				} else {
+					jscall(rt, n)
+				};
+				if let Err(e) = result {
+					handle_exception!(e);
+				}
+			},
 			OpcodeType::OP_CALL => {
 				let n = func.int(&mut pc) as usize;
 				if let Err(e) = jscall(rt, n) {					
@@ -1208,35 +1650,39 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 
 			/* Relational operators */
 			OpcodeType::OP_LT => {
-				let r = rt.compare_item();
-				if let Some(b) = r {
-					rt.push_boolean( b < 0 );
-				} else {
-					rt.push_boolean(false);
+				match rt.compare_item() {
+					Ok(Some(b)) => rt.push_boolean( b < 0 ),
+					Ok(None) => rt.push_boolean(false),
+					Err(e) => {
+						handle_exception!(e);
+					}
 				}
 			},
 			OpcodeType::OP_GT => {
-				let r = rt.compare_item();
-				if let Some(b) = r {
-					rt.push_boolean( b > 0);
-				} else {
-					rt.push_boolean(false);
+				match rt.compare_item() {
+					Ok(Some(b)) => rt.push_boolean( b > 0),
+					Ok(None) => rt.push_boolean(false),
+					Err(e) => {
+						handle_exception!(e);
+					}
 				}
 			},
 			OpcodeType::OP_LE => {
-				let r = rt.compare_item();
-				if let Some(b) = r {
-					rt.push_boolean( b <= 0 );
-				} else {
-					rt.push_boolean(false);
+				match rt.compare_item() {
+					Ok(Some(b)) => rt.push_boolean( b <= 0 ),
+					Ok(None) => rt.push_boolean(false),
+					Err(e) => {
+						handle_exception!(e);
+					}
 				}
 			},
 			OpcodeType::OP_GE => {
-				let r = rt.compare_item();
-				if let Some(b) = r {
-					rt.push_boolean( b >= 0);
-				} else {
-					rt.push_boolean(false);
+				match rt.compare_item() {
+					Ok(Some(b)) => rt.push_boolean( b >= 0),
+					Ok(None) => rt.push_boolean(false),
+					Err(e) => {
+						handle_exception!(e);
+					}
 				}
 			},
 
@@ -1319,7 +1765,7 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 			/* Try and Catch */	
 			OpcodeType::OP_TRY => {
 				let catch_block = func.address(&mut pc);
-				catch_scopes.push((pc, rt.stack.len()));
+				catch_scopes.push((pc, rt.stack.len(), rt.cenv.clone()));
 				pc = catch_block;
 			},
 			OpcodeType::OP_ENDTRY => {
@@ -1341,12 +1787,14 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 			OpcodeType::OP_THROW => {
 				let evalue = rt.top(-1);
 				rt.pop(1);
-				if evalue.is_exception() {
-					let e = evalue.get_object().borrow().get_exception();
-					handle_exception!(e);
+				let e = if evalue.is_exception() {
+					let mut e = evalue.get_object().borrow().get_exception();
+					e.value = Some(evalue);
+					e
 				} else {
-					panic!("Throw a none exception object!");
-				}
+					JsException::new_value(evalue)
+				};
+				handle_exception!(e);
 			},
 			
 			/* Branching & Flow control */			
@@ -1385,12 +1833,12 @@ fn jsrun(rt: &mut JsRuntime, func: &VMFunction, pc: usize) -> Result<(), JsExcep
 			},
 
 			OpcodeType::OP_DEBUG => {
-				rt.debugger();
-				panic!("Exiting with debug");
+				if let Err(e) = rt.debug(func, pc - 1) {
+					handle_exception!(e);
+				}
 			},
 
 			/* do nothing */
-			OpcodeType::OP_EVAL => {},
 			OpcodeType::OP_NOP => {},
 			OpcodeType::OP_LAST => {},
 		}
@@ -1404,19 +1852,25 @@ fn jscall_script(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 	let bot = rt.stack.len() - 1 - argc;
 
 	let fobj = rt.stack[bot-1].get_object();
-	let rfobj = fobj.borrow();
-	let vmf = &rfobj.get_func().vmf;
+	let vmf = fobj.borrow().get_func().vmf.clone();
 
-	/* init var in current env*/
+	/* init var in current env, declared variables are kept */
 	for i in 0..vmf.numvars {
-		let jv = SharedValue::new_undefined();
 		let var = &vmf.str_tab[i];
+		if rt.cenv.borrow().query_variable(var) {
+			continue;
+		}
+		let jv = SharedValue::new_undefined();
 		rt.cenv.borrow_mut().init_var(var, jv);
 	}
 
 	/* scripts take no arguments */
 	rt.pop(argc);
-	jsrun(rt, vmf, 0)?;
+
+	rt.frames.push(JsFrame{vmf: vmf.clone(), pc: 0});
+	let result = jsrun(rt, &vmf, 0);
+	rt.frames.pop();
+	result?;
 
 	/* clear stack */
 	let jv = rt.stack.pop().unwrap();
@@ -1426,15 +1880,68 @@ fn jscall_script(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 	return Ok(())
 }
 
+// compile the string argument and run it as a script in the env
+fn jseval(rt: &mut JsRuntime, argc: usize, env: SharedScope) -> Result<(), JsException> {
+	let bot = rt.stack.len() - 1 - argc;
+
+	let code = if argc > 0 {
+		rt.stack[bot+1].clone()
+	} else {
+		SharedValue::new_undefined()
+	};
+	if !code.is_string() {
+		rt.pop(argc + 2);
+		rt.push(code);
+		return Ok(());
+	}
+
+	// the source is parsed within the runtime's limit of the Rust stack, eval runs nested in a call
+	let stack_limit = match rt.limits.max_native_stack {
+		0 => 0,
+		n => rt.native_base.saturating_sub(n),
+	};
+	// the SyntaxError names itself, the message only has what went wrong and where
+	let vmf = match build_function_within("[eval]", &code.to_string(), stack_limit) {
+		Ok(vmf) => vmf,
+		Err(Error::SyntaxError{msg, line, column}) => {
+			return Err(JsException::new_syntax_error(format!("{} (line {}, column {})", msg, line, column)));
+		},
+		Err(Error::CompileError{msg, line}) => {
+			return Err(JsException::new_syntax_error(format!("{} (line {})", msg, line)));
+		},
+		Err(err) => {
+			return Err(JsException::new_syntax_error(err.to_string()));
+		}
+	};
+	let fobj = JsObject::new_function(vmf, env.clone());
+	rt.stack[bot-1] = SharedValue::new_object(fobj);
+
+	let old_env = rt.cenv.clone();
+	rt.cenv = env;
+	let result = jscall_script(rt, argc);
+	rt.cenv = old_env;
+	return result;
+}
+
+// eval of another realm called as a plain function, the code runs in the global scope of that realm
+pub fn jseval_global(rt: &mut JsRuntime, code: SharedValue, genv: SharedScope) -> Result<SharedValue, JsException> {
+	let global = genv.borrow().target();
+	rt.push(SharedValue::new_undefined());
+	rt.push(SharedValue::new_sobject(global));
+	rt.push(code);
+	jseval(rt, 1, genv)?;
+	return Ok(rt.stack.pop().unwrap());
+}
+
 fn jscall_function(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {	
 	let bot = rt.stack.len() - 1 - argc;
 
 	let fobj = rt.stack[bot-1].get_object();
-	let rfobj = fobj.borrow();
-	let vmf = &rfobj.get_func().vmf;
+	let vmf = fobj.borrow().get_func().vmf.clone();
+	let scope = fobj.borrow().get_func().scope.clone();
 
 	/* create new scope */
-	let new_env = JsEnvironment::new_from(rfobj.get_func().scope.clone());
+	let new_env = JsEnvironment::new_from(scope);
 	let old_env = rt.cenv.clone();
 	rt.cenv = new_env;
 
@@ -1476,45 +1983,82 @@ fn jscall_function(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 	}
 
 	
-	jsrun(rt, vmf, 0)?;
+	rt.frames.push(JsFrame{vmf: vmf.clone(), pc: 0});
+	let result = jsrun(rt, &vmf, 0);
+	rt.frames.pop();
+
+	/* restore old env */
+	rt.cenv = old_env;
+	result?;
 
 	/* clear stack */
 	let jv = rt.stack.pop().unwrap();
 	rt.pop(2);
 	rt.push(jv);
 
-	/* restore old env */
-	rt.cenv = old_env;
-
 	return Ok(());
 }
 
-fn jscall_builtin(rt: &mut JsRuntime, argc: usize) {
+fn jscall_builtin(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 	let bot = rt.stack.len() - 1 - argc;
 	let fobj = rt.stack[bot-1].get_object();
 	let builtin = fobj.borrow().get_builtin();
-	
-	if argc > builtin.argc {
-		for _i in builtin.argc .. argc {
-			rt.pop(1);
-		}	
-	} else if argc < builtin.argc {
-		for _i in argc .. builtin.argc {
-			rt.push_undefined();
-		}
+
+	/* the arguments are moved out of the stack, missing ones are undefined */
+	let mut args = rt.stack.split_off(bot + 1);
+	if builtin.argc != JS_VARARGS && args.len() < builtin.argc {
+		args.resize_with(builtin.argc, SharedValue::new_undefined);
 	}
+	let this = rt.stack[bot].clone();
 
-	(builtin.f)(rt);
+	let old_this = std::mem::replace(&mut rt.native_this, this);
+	let result = (builtin.f)(rt, &args);
+	rt.native_this = old_this;
 
-	let jv = rt.stack.pop().unwrap();
-	rt.pop(builtin.argc + 2);
-	rt.push(jv);
+	/* the function and this are left for catch handlers on errors, as script functions do */
+	let value = result?;
+	rt.pop(2);
+	rt.push(value);
+	return Ok(());
 }
 
 pub fn jscall(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
+	rt.enter_nested()?;
+	let result = jscall_nested(rt, argc);
+	rt.leave_nested();
+	return result;
+}
+
+fn jscall_nested(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 	assert!(rt.stack.len() >= argc + 2);
 	let bot = rt.stack.len() - 1 - argc;
 
+	/* a bound function calls its target with the bound this and leading arguments, chains are unwrapped here */
+	let mut argc = argc;
+	while rt.stack[bot-1].is_object() && rt.stack[bot-1].get_object().borrow().is_bound() {
+		let (target, this, args) = {
+			let fobj = rt.stack[bot-1].get_object();
+			let fobj = fobj.borrow();
+			let bound = fobj.get_bound();
+			(bound.target.clone(), bound.this.clone(), bound.args.clone())
+		};
+		argc = argc + args.len();
+		rt.stack[bot-1] = SharedValue::new_sobject(target);
+		rt.stack[bot] = this;
+		rt.stack.splice(bot+1..bot+1, args);
+	}
+
+	if !rt.stack[bot-1].is_object() {
+		return Err(JsException::new_type_error("Can't call none function object".to_string()));
+	}
+	if rt.is_eval(&rt.stack[bot-1]) {
+		/* indirect eval runs in the global scope */
+		let global = rt.genv.borrow().target();
+		rt.stack[bot] = SharedValue::new_sobject(global);
+		let env = rt.genv.clone();
+		return jseval(rt, argc, env);
+	}
+
 	let fobj = rt.stack[bot-1].get_object();
 	if fobj.borrow().is_function() == true {
 
@@ -1525,9 +2069,9 @@ pub fn jscall(rt: &mut JsRuntime, argc: usize) -> Result<(), JsException> {
 		};
 
 	} else if fobj.borrow().is_builtin() == true {
-		jscall_builtin(rt, argc);
+		jscall_builtin(rt, argc)?;
 	} else {
-        panic!("Can't call none function object");
+		return Err(JsException::new_type_error("Can't call none function object".to_string()));
 	}
 	
 	return Ok(());
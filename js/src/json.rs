@@ -0,0 +1,554 @@
+use std::rc::Rc;
+
+use crate::runtime::*;
+use crate::execute::*;
+
+/* JSON parser and serializer, shared by the JSON object and the host API */
+
+// nesting of arrays and objects accepted by the parser, deeper text is a SyntaxError. Levels are
+// nesting levels of the runtime too, so the text is rejected earlier if the Rust stack runs short.
+const JSON_MAX_DEPTH: usize = 512;
+
+struct JsonParser<'a> {
+	chars:	Vec<char>,
+	pos:	usize,
+	depth:	usize,				// arrays and objects being parsed
+	rt:		&'a mut JsRuntime,
+}
+
+impl<'a> JsonParser<'a> {
+	fn error(&self, msg: &str) -> JsException {
+		return JsException::new_syntax_error(format!("JSON.parse: {} at position {}", msg, self.pos));
+	}
+
+	fn peek(&self) -> Option<char> {
+		return self.chars.get(self.pos).cloned();
+	}
+
+	fn skip_space(&mut self) {
+		while let Some(c) = self.peek() {
+			if c == ' ' || c == '\t' || c == '\n' || c == '\r' {
+				self.pos = self.pos + 1;
+			} else {
+				break;
+			}
+		}
+	}
+
+	fn expect(&mut self, c: char) -> Result<(), JsException> {
+		if self.peek() != Some(c) {
+			return Err(self.error(&format!("expected '{}'", c)));
+		}
+		self.pos = self.pos + 1;
+		return Ok(());
+	}
+
+	fn expect_word(&mut self, word: &str) -> Result<(), JsException> {
+		for c in word.chars() {
+			if self.peek() != Some(c) {
+				return Err(self.error("unexpected token"));
+			}
+			self.pos = self.pos + 1;
+		}
+		return Ok(());
+	}
+
+	fn parse_value(&mut self) -> Result<SharedValue, JsException> {
+		self.skip_space();
+		match self.peek() {
+			Some(c) if c == '{' || c == '[' => {
+				if self.depth >= JSON_MAX_DEPTH || self.rt.enter_nested().is_err() {
+					return Err(self.error("too deeply nested"));
+				}
+				self.depth = self.depth + 1;
+				let result = if c == '{' { self.parse_object() } else { self.parse_array() };
+				self.depth = self.depth - 1;
+				self.rt.leave_nested();
+				return result;
+			},
+			Some('"') => {
+				let s = self.parse_string()?;
+				return Ok(self.rt.new_string(s));
+			},
+			Some('t') => {
+				self.expect_word("true")?;
+				return Ok(SharedValue::new_boolean(true));
+			},
+			Some('f') => {
+				self.expect_word("false")?;
+				return Ok(SharedValue::new_boolean(false));
+			},
+			Some('n') => {
+				self.expect_word("null")?;
+				return Ok(SharedValue::new_null());
+			},
+			Some(c) if c == '-' || c.is_ascii_digit() => {
+				return self.parse_number();
+			},
+			Some(_) => {
+				return Err(self.error("unexpected token"));
+			},
+			None => {
+				return Err(self.error("unexpected end of input"));
+			}
+		}
+	}
+
+	fn parse_object(&mut self) -> Result<SharedValue, JsException> {
+		self.expect('{')?;
+		let obj = SharedValue::new_vanilla(self.rt.prototypes.object_prototype.clone());
+
+		self.skip_space();
+		if self.peek() == Some('}') {
+			self.pos = self.pos + 1;
+			return Ok(obj);
+		}
+		loop {
+			self.skip_space();
+			if self.peek() != Some('"') {
+				return Err(self.error("expected property name"));
+			}
+			let name = self.parse_string()?;
+			self.skip_space();
+			self.expect(':')?;
+			let value = self.parse_value()?;
+			self.rt.defproperty(obj.get_object(), &name, value, JS_DEFAULT_ATTR, None, None)?;
+
+			self.skip_space();
+			match self.peek() {
+				Some(',') => {
+					self.pos = self.pos + 1;
+				},
+				Some('}') => {
+					self.pos = self.pos + 1;
+					return Ok(obj);
+				},
+				_ => {
+					return Err(self.error("expected ',' or '}'"));
+				}
+			}
+		}
+	}
+
+	fn parse_array(&mut self) -> Result<SharedValue, JsException> {
+		self.expect('[')?;
+		let mut values: Vec<SharedValue> = Vec::new();
+
+		self.skip_space();
+		if self.peek() == Some(']') {
+			self.pos = self.pos + 1;
+			return Ok(self.rt.new_array(values));
+		}
+		loop {
+			values.push( self.parse_value()? );
+			self.skip_space();
+			match self.peek() {
+				Some(',') => {
+					self.pos = self.pos + 1;
+				},
+				Some(']') => {
+					self.pos = self.pos + 1;
+					return Ok(self.rt.new_array(values));
+				},
+				_ => {
+					return Err(self.error("expected ',' or ']'"));
+				}
+			}
+		}
+	}
+
+	fn parse_hex4(&mut self) -> Result<u16, JsException> {
+		let mut v: u16 = 0;
+		for _i in 0..4 {
+			let d = match self.peek().and_then(|c| c.to_digit(16)) {
+				Some(d) => d,
+				None => {
+					return Err(self.error("bad unicode escape"));
+				}
+			};
+			v = v * 16 + d as u16;
+			self.pos = self.pos + 1;
+		}
+		return Ok(v);
+	}
+
+	fn parse_string(&mut self) -> Result<String, JsException> {
+		self.expect('"')?;
+		let mut units: Vec<u16> = Vec::new();
+		loop {
+			let c = match self.peek() {
+				Some(c) => c,
+				None => {
+					return Err(self.error("unterminated string"));
+				}
+			};
+			self.pos = self.pos + 1;
+			match c {
+				'"' => {
+					return Ok(String::from_utf16_lossy(&units));
+				},
+				'\\' => {
+					let e = match self.peek() {
+						Some(e) => e,
+						None => {
+							return Err(self.error("unterminated string"));
+						}
+					};
+					self.pos = self.pos + 1;
+					let r = match e {
+						'"' => '"',
+						'\\' => '\\',
+						'/' => '/',
+						'b' => '\u{8}',
+						'f' => '\u{c}',
+						'n' => '\n',
+						'r' => '\r',
+						't' => '\t',
+						'u' => {
+							let v = self.parse_hex4()?;
+							units.push(v);
+							continue;
+						},
+						_ => {
+							return Err(self.error("bad escape"));
+						}
+					};
+					units.push(r as u16);
+				},
+				c if (c as u32) < 0x20 => {
+					return Err(self.error("control character in string"));
+				},
+				c => {
+					let mut buf = [0u16; 2];
+					units.extend_from_slice( c.encode_utf16(&mut buf) );
+				}
+			}
+		}
+	}
+
+	fn parse_number(&mut self) -> Result<SharedValue, JsException> {
+		let start = self.pos;
+		if self.peek() == Some('-') {
+			self.pos = self.pos + 1;
+		}
+		// no leading zeros
+		if self.peek() == Some('0') {
+			self.pos = self.pos + 1;
+		} else if self.skip_digits() == 0 {
+			return Err(self.error("bad number"));
+		}
+		if self.peek() == Some('.') {
+			self.pos = self.pos + 1;
+			if self.skip_digits() == 0 {
+				return Err(self.error("bad number"));
+			}
+		}
+		if self.peek() == Some('e') || self.peek() == Some('E') {
+			self.pos = self.pos + 1;
+			if self.peek() == Some('+') || self.peek() == Some('-') {
+				self.pos = self.pos + 1;
+			}
+			if self.skip_digits() == 0 {
+				return Err(self.error("bad number"));
+			}
+		}
+		let s: String = self.chars[start..self.pos].iter().collect();
+		match s.parse::<f64>() {
+			Ok(v) => {
+				return Ok(SharedValue::new_number(v));
+			},
+			Err(_) => {
+				return Err(self.error("bad number"));
+			}
+		}
+	}
+
+	fn skip_digits(&mut self) -> usize {
+		let mut n = 0;
+		while let Some(c) = self.peek() {
+			if !c.is_ascii_digit() {
+				break;
+			}
+			self.pos = self.pos + 1;
+			n = n + 1;
+		}
+		return n;
+	}
+}
+
+fn is_callable(v: &SharedValue) -> bool {
+	return v.is_object() && v.get_object().borrow().callable();
+}
+
+fn is_array(v: &SharedValue) -> bool {
+	return v.is_object() && v.get_object().borrow().is_array();
+}
+
+// quote and escape a string
+fn json_quote(s: &str) -> String {
+	let mut r = String::with_capacity(s.len() + 2);
+	r.push('"');
+	for c in s.chars() {
+		match c {
+			'"' => r.push_str("\\\""),
+			'\\' => r.push_str("\\\\"),
+			'\u{8}' => r.push_str("\\b"),
+			'\u{c}' => r.push_str("\\f"),
+			'\n' => r.push_str("\\n"),
+			'\r' => r.push_str("\\r"),
+			'\t' => r.push_str("\\t"),
+			c if (c as u32) < 0x20 => r.push_str(&format!("\\u{:04x}", c as u32)),
+			c => r.push(c),
+		}
+	}
+	r.push('"');
+	return r;
+}
+
+struct JsonWriter {
+	replacer:	Option<SharedValue>,		// replacer function
+	keys:		Option<Vec<String>>,		// replacer list of property names
+	gap:		String,
+	indent:		String,
+	stack:		Vec<SharedObject>,			// objects being serialized, for cycle detection
+}
+
+impl JsRuntime {
+	fn json_get(&mut self, target: SharedObject, name: &str) -> Result<SharedValue, JsException> {
+		self.getproperty(target, name)?;
+		return Ok(self.stack.pop().unwrap());
+	}
+
+	fn json_call(&mut self, func: SharedValue, thiz: SharedValue, args: &[SharedValue]) -> Result<SharedValue, JsException> {
+		let top = self.stack.len();
+		self.push(func);
+		self.push(thiz);
+		for v in args {
+			self.push(v.clone());
+		}
+		if let Err(e) = jscall(self, args.len()) {
+			self.stack.truncate(top);
+			return Err(e);
+		}
+		return Ok(self.stack.pop().unwrap());
+	}
+
+	// own enumerable property names of an object
+	fn json_keys(&self, target: &SharedObject) -> Vec<String> {
+		let object = target.borrow();
+		let mut keys = Vec::new();
+		for (name, prop) in object.properties.iter() {
+			if prop.enumerable() {
+				keys.push(name.clone());
+			}
+		}
+		return keys;
+	}
+
+	// parse the text, the reviver can transform the values
+	pub fn json_parse(&mut self, text: &str, reviver: Option<SharedValue>) -> Result<SharedValue, JsException> {
+		let mut parser = JsonParser {
+			chars: text.chars().collect(),
+			pos: 0,
+			depth: 0,
+			rt: self,
+		};
+		let value = parser.parse_value()?;
+		parser.skip_space();
+		if parser.pos < parser.chars.len() {
+			return Err(parser.error("unexpected token"));
+		}
+
+		if let Some(reviver) = reviver {
+			if is_callable(&reviver) {
+				let root = SharedValue::new_vanilla(self.prototypes.object_prototype.clone());
+				self.defproperty(root.get_object(), "", value, JS_DEFAULT_ATTR, None, None)?;
+				return self.json_revive(&reviver, root.get_object(), "");
+			}
+		}
+		return Ok(value);
+	}
+
+	fn json_revive(&mut self, reviver: &SharedValue, holder: SharedObject, name: &str) -> Result<SharedValue, JsException> {
+		let value = self.json_get(holder.clone(), name)?;
+		if value.is_object() && !value.is_string() {
+			self.enter_nested()?;
+			let result = self.json_revive_members(reviver, &value);
+			self.leave_nested();
+			result?;
+		}
+		let key = self.new_string(name.to_string());
+		return self.json_call(reviver.clone(), SharedValue::new_sobject(holder), &[key, value]);
+	}
+
+	fn json_revive_members(&mut self, reviver: &SharedValue, value: &SharedValue) -> Result<(), JsException> {
+		let target = value.get_object();
+		let keys = if is_array(value) {
+			let len = target.borrow().get_array().len();
+			(0..len).map(|i| i.to_string()).collect()
+		} else {
+			self.json_keys(&target)
+		};
+		for k in keys {
+			let v = self.json_revive(reviver, target.clone(), &k)?;
+			// removed like the delete operator does, array elements become undefined
+			if v.is_undefined() {
+				self.delproperty(target.clone(), &k);
+			} else {
+				self.json_set(target.clone(), &k, v)?;
+			}
+		}
+		return Ok(());
+	}
+
+	fn json_set(&mut self, target: SharedObject, name: &str, value: SharedValue) -> Result<(), JsException> {
+		if let Ok(idx) = name.parse::<usize>() {
+			let mut object = target.borrow_mut();
+			if object.is_array() {
+				let v = object.get_mut_array();
+				if idx < v.len() {
+					v[idx] = value;
+				}
+				return Ok(());
+			}
+		}
+		return self.defproperty(target, name, value, JS_DEFAULT_ATTR, None, None);
+	}
+
+	// serialize the value, None for values can't be serialized, e.g. undefined and functions
+	pub fn json_stringify(&mut self, value: SharedValue, replacer: Option<SharedValue>, space: Option<SharedValue>) -> Result<Option<String>, JsException> {
+		let mut writer = JsonWriter {
+			replacer: None,
+			keys: None,
+			gap: String::new(),
+			indent: String::new(),
+			stack: Vec::new(),
+		};
+
+		if let Some(replacer) = replacer {
+			if is_callable(&replacer) {
+				writer.replacer = Some(replacer);
+			} else if is_array(&replacer) {
+				let mut keys: Vec<String> = Vec::new();
+				let items = replacer.get_object().borrow().get_array().clone();
+				for v in items {
+					if v.is_string() || v.is_number() {
+						let k = v.to_string();
+						if !keys.contains(&k) {
+							keys.push(k);
+						}
+					}
+				}
+				writer.keys = Some(keys);
+			}
+		}
+
+		if let Some(space) = space {
+			if space.is_number() {
+				let n = space.to_number();
+				let n = if n.is_nan() { 0.0 } else { n.max(0.0).min(10.0) };
+				writer.gap = " ".repeat(n as usize);
+			} else if space.is_string() {
+				writer.gap = space.to_string().chars().take(10).collect();
+			}
+		}
+
+		let holder = SharedValue::new_vanilla(self.prototypes.object_prototype.clone());
+		self.defproperty(holder.get_object(), "", value, JS_DEFAULT_ATTR, None, None)?;
+		return self.json_str(&mut writer, holder.get_object(), "");
+	}
+
+	fn json_str(&mut self, w: &mut JsonWriter, holder: SharedObject, key: &str) -> Result<Option<String>, JsException> {
+		let mut value = self.json_get(holder.clone(), key)?;
+
+		if value.is_object() && !value.is_string() {
+			let to_json = self.json_get(value.get_object(), "toJSON")?;
+			if is_callable(&to_json) {
+				let k = self.new_string(key.to_string());
+				value = self.json_call(to_json, value.clone(), &[k])?;
+			}
+		}
+		if let Some(replacer) = w.replacer.clone() {
+			let k = self.new_string(key.to_string());
+			value = self.json_call(replacer, SharedValue::new_sobject(holder), &[k, value])?;
+		}
+
+		// new Number, String and Boolean give primitives, there are no wrapper objects to unwrap
+		if value.is_null() {
+			return Ok(Some("null".to_string()));
+		}
+		if value.is_boolean() {
+			return Ok(Some(value.to_string()));
+		}
+		if value.is_string() {
+			return Ok(Some(json_quote(&value.to_string())));
+		}
+		if value.is_number() {
+			if value.to_number().is_finite() {
+				return Ok(Some(value.to_string()));
+			}
+			return Ok(Some("null".to_string()));
+		}
+		if value.is_object() && !is_callable(&value) {
+			let target = value.get_object();
+			if w.stack.iter().any(|o| Rc::ptr_eq(o, &target)) {
+				return Err(JsException::new_type_error("Converting circular structure to JSON".to_string()));
+			}
+			self.enter_nested()?;
+			w.stack.push(target.clone());
+			let stepback = w.indent.clone();
+			w.indent = format!("{}{}", w.indent, w.gap);
+
+			let result = if is_array(&value) {
+				self.json_array(w, target)
+			} else {
+				self.json_object(w, target)
+			};
+
+			self.leave_nested();
+			w.indent = stepback;
+			w.stack.pop();
+			return Ok(Some(result?));
+		}
+		return Ok(None);
+	}
+
+	fn json_join(&self, w: &JsonWriter, items: Vec<String>, open: &str, close: &str) -> String {
+		if items.len() == 0 {
+			return format!("{}{}", open, close);
+		}
+		if w.gap.len() == 0 {
+			return format!("{}{}{}", open, items.join(","), close);
+		}
+		let stepback = &w.indent[..w.indent.len() - w.gap.len()];
+		let sep = format!(",\n{}", w.indent);
+		return format!("{}\n{}{}\n{}{}", open, w.indent, items.join(&sep), stepback, close);
+	}
+
+	fn json_object(&mut self, w: &mut JsonWriter, target: SharedObject) -> Result<String, JsException> {
+		let keys = match w.keys {
+			Some(ref keys) => keys.clone(),
+			None => self.json_keys(&target),
+		};
+		let mut items: Vec<String> = Vec::new();
+		for k in keys {
+			if let Some(s) = self.json_str(w, target.clone(), &k)? {
+				let colon = if w.gap.len() == 0 { ":" } else { ": " };
+				items.push(format!("{}{}{}", json_quote(&k), colon, s));
+			}
+		}
+		return Ok(self.json_join(w, items, "{", "}"));
+	}
+
+	fn json_array(&mut self, w: &mut JsonWriter, target: SharedObject) -> Result<String, JsException> {
+		let len = target.borrow().get_array().len();
+		let mut items: Vec<String> = Vec::new();
+		for i in 0..len {
+			match self.json_str(w, target.clone(), &i.to_string())? {
+				Some(s) => items.push(s),
+				None => items.push("null".to_string()),
+			}
+		}
+		return Ok(self.json_join(w, items, "[", "]"));
+	}
+}
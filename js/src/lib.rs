@@ -9,29 +9,61 @@ mod optimizer;
 mod value;
 mod execute;
 mod builtin;
+mod json;
+mod regexp;
+mod date;
+mod realm;
 
 pub mod runtime;
+pub mod error;
+pub mod convert;
 
+pub use crate::error::Error;
+pub use crate::convert::{FromJs, IntoJs};
+pub use crate::bytecode::VMFunction;
+
+use std::collections::HashSet;
 use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::common::stack_address;
 use crate::ast::*;
 use crate::bytecode::*;
 use crate::compile::*;
+use crate::optimizer::*;
 
 use crate::runtime::*;
 use crate::execute::*;
 use crate::builtin::*;
+use crate::realm::*;
+
+pub fn build_function_from_code(script: &str) -> Result<SharedFunction, Error> {
+    return build_function_from_file("[string]", script);
+}
+
+pub fn build_function_from_file(filename: &str, script: &str) -> Result<SharedFunction, Error> {
+    return build_function_within(filename, script, stack_address().saturating_sub(AST_MAX_STACK));
+}
 
-pub fn build_function_from_code(script: &str) -> Result<SharedFunction, String> {
-    let ast = build_ast_from_script(script).unwrap();
+// the parser doesn't use the Rust stack below stack_limit, eval passes the limit of its runtime
+pub(crate) fn build_function_within(filename: &str, script: &str, stack_limit: usize) -> Result<SharedFunction, Error> {
+    let ast = build_ast_from_script(script, stack_limit)?;
 
     let null = AstNode::null();
-    let func = compile_func(&null, &null, &ast, true)?;
+    let mut func = match compile_func(&null, &null, &ast, true) {
+        Ok(func) => func,
+        Err(e) => return Err(Error::CompileError {
+            msg:    e.msg,
+            line:   e.line,
+        }),
+    };
+    func.set_filename(filename);
+    optimize_func(&mut func);
     return Ok(SharedFunction_new(func));
 }
 
 pub fn dump_function(f: &VMFunction) {
     println!("-------------------------------");
-    println!("script: {}", f.script);
+    println!("script: {} ({})", f.script, f.filename);
     println!("functions: {}", f.func_tab.len());
     println!("---num----");
     for n in &f.num_tab {
@@ -53,6 +85,11 @@ pub fn dump_function(f: &VMFunction) {
         addr = addr + 1;
     }
 
+    println!("---lines----");
+    for (pc, line) in &f.lines {
+        println!("{}\t\tline: {}", pc, line);
+    }
+
     println!("---functions---");
     for i in &f.func_tab {
         dump_function( i );
@@ -64,9 +101,18 @@ pub fn new_runtime() -> JsRuntime {
 	let prototypes = JsPrototype {
 		object_prototype:		SharedObject_new(JsObject::new()),
 		string_prototype:		SharedObject_new(JsObject::new()),
+		number_prototype:		SharedObject_new(JsObject::new()),
+		boolean_prototype:		SharedObject_new(JsObject::new()),
+		regexp_prototype:		SharedObject_new(JsObject::new()),
+		date_prototype:			SharedObject_new(JsObject::new()),
 		array_prototype:		SharedObject_new(JsObject::new()),
 		function_prototype:		SharedObject_new(JsObject::new()),
 		exception_prototype:	SharedObject_new(JsObject::new()),
+		error_prototype:			SharedObject_new(JsObject::new()),
+		type_error_prototype:		SharedObject_new(JsObject::new()),
+		range_error_prototype:		SharedObject_new(JsObject::new()),
+		reference_error_prototype:	SharedObject_new(JsObject::new()),
+		syntax_error_prototype:		SharedObject_new(JsObject::new()),
 	};
 
 	let genv = JsEnvironment::new();
@@ -77,8 +123,28 @@ pub fn new_runtime() -> JsRuntime {
 		genv:		genv,
 		cenv:		cenv,
 		stack:		Vec::new(),
+		frames:		Vec::new(),
+		eval_function:	SharedObject_new(JsObject::new()),
+
+		debugger:	Box::new(DefaultDebugger{}),
+		debug_mode:	DebugAction::Continue,
+		breakpoints: HashSet::new(),
+		limits:		JsLimits::default(),
+		opcodes:	0,
+		depth:		0,
+		native_base: 0,
+
+		random_state: 0,
+		clock:		Box::new(SystemClock{}),
+		native_this: SharedValue::new_undefined(),
+		host_types: Vec::new(),
+		joining:	Vec::new(),
 	};
 
+	// Math.random is seeded with the clock, hosts can reset it by set_random_seed
+	let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+	runtime.set_random_seed(seed);
+
 	// init prototypes
 	prototypes_init(&mut runtime);
 	builtin_init(&mut runtime);
@@ -86,25 +152,53 @@ pub fn new_runtime() -> JsRuntime {
 	return runtime;
 }
 
-pub fn run_script(rt: &mut JsRuntime, vmf: SharedFunction) -> Result<SharedValue, String> {
-	assert!( vmf.script == true);
+// a sandboxed runtime with copies of the globals of the template, e.g. a runtime after loading
+// common libraries. Compiled functions can run in any realm, the clock and debugger aren't copied
+// The builtins are copied as well, a realm is cheaper than a new runtime only by what the template ran
+pub fn new_realm(template: &JsRuntime) -> JsRuntime {
+	return clone_realm(template);
+}
+
+// convert the exception out of the runtime to error
+fn exception_value(rt: &mut JsRuntime, e: JsException) -> Error {
+	if e.abort {
+		return Error::Aborted(e.msg);
+	}
+	let value = match e.value {
+		Some(ref v) => v.clone(),
+		None => rt.new_exception(e),
+	};
+	return Error::RuntimeException(value);
+}
+
+// convert the exception out of the runtime to error, the runtime is reset
+fn exception_error(rt: &mut JsRuntime, e: JsException) -> Error {
+	let err = exception_value(rt, e);
+	rt.stack.clear();
+	rt.cenv = rt.genv.clone();
+	return err;
+}
+
+pub fn run_script(rt: &mut JsRuntime, vmf: SharedFunction) -> Result<SharedValue, Error> {
+	if vmf.script == false {
+		return Err(Error::InternalError("run_script needs a script function".to_string()));
+	}
 	let fobj = SharedObject_new(JsObject::new_function(vmf, rt.genv.clone()));
 	let thiz = rt.genv.borrow().target(); 
+	rt.opcodes = 0;
 
 	rt.push_object(fobj);	// function object
 	rt.push_object(thiz);	// this
 
 	let result = jscall(rt, 0);
-	if result.is_err() {
-		let err_msg = format!("Exceptions: {:?}", result.err().unwrap());
-		println!("{}", err_msg);
-		rt.stack.clear();
-		return Err(err_msg);
+	if let Err(e) = result {
+		return Err(exception_error(rt, e));
 	}
 
 	if rt.stack.len() != 1 {
 		let err_msg = format!("stack len should be 1 but get {}", rt.stack.len());
-		panic!(err_msg);
+		rt.stack.clear();
+		return Err(Error::InternalError(err_msg));
 	}
 
 	let value = rt.stack[0].clone();
@@ -112,3 +206,73 @@ pub fn run_script(rt: &mut JsRuntime, vmf: SharedFunction) -> Result<SharedValue
 	return Ok(value);
 }
 
+pub fn parse_json(rt: &mut JsRuntime, text: &str) -> Result<SharedValue, Error> {
+	match rt.json_parse(text, None) {
+		Ok(v) => {
+			return Ok(v);
+		},
+		Err(e) => {
+			return Err(exception_error(rt, e));
+		}
+	}
+}
+
+// None for values can't be represented in JSON, e.g. undefined or functions
+pub fn to_json(rt: &mut JsRuntime, value: SharedValue) -> Result<Option<String>, Error> {
+	match rt.json_stringify(value, None, None) {
+		Ok(s) => {
+			return Ok(s);
+		},
+		Err(e) => {
+			return Err(exception_error(rt, e));
+		}
+	}
+}
+
+impl JsRuntime {
+	/* call a function from the host, e.g. event handlers saved by scripts */
+	pub fn call(&mut self, func: &SharedValue, this: &SharedValue, args: &[SharedValue]) -> Result<SharedValue, Error> {
+		// calls from natives are nested in the running script, which keeps the stack and limits
+		let top = self.stack.len();
+		let env = self.cenv.clone();
+		if top == 0 {
+			self.opcodes = 0;
+		}
+
+		self.push(func.clone());
+		self.push(this.clone());
+		for v in args {
+			self.push(v.clone());
+		}
+		if let Err(e) = jscall(self, args.len()) {
+			let err = exception_value(self, e);
+			self.stack.truncate(top);
+			self.cenv = env;
+			return Err(err);
+		}
+		return Ok(self.stack.pop().unwrap());
+	}
+
+	// properties of the global object, getters and setters are called
+	pub fn get_global(&mut self, name: &str) -> Result<SharedValue, Error> {
+		let global = self.genv.borrow().target();
+		let top = self.stack.len();
+		if let Err(e) = self.getproperty(global, name) {
+			let err = exception_value(self, e);
+			self.stack.truncate(top);
+			return Err(err);
+		}
+		return Ok(self.stack.pop().unwrap());
+	}
+
+	pub fn set_global(&mut self, name: &str, value: SharedValue) -> Result<(), Error> {
+		let global = self.genv.borrow().target();
+		let top = self.stack.len();
+		if let Err(e) = self.setproperty(global, name, value) {
+			let err = exception_value(self, e);
+			self.stack.truncate(top);
+			return Err(err);
+		}
+		return Ok(());
+	}
+}
@@ -0,0 +1,401 @@
+use std::convert::TryFrom;
+use std::rc::Rc;
+
+use crate::bytecode::*;
+
+/* bytecode optimization pass for VMFunction */
+
+#[derive(Clone)]
+struct Instruction {
+    op:     OpcodeType,
+    arg:    u16,        // the operand for single operand opcodes
+    target: usize,      // the target instruction index for jump opcodes
+    line:   u32,        // the source line
+    live:   bool,
+}
+
+fn operand_size(op: OpcodeType) -> usize {
+    match op {
+        OpcodeType::OP_INTEGER | OpcodeType::OP_NUMBER | OpcodeType::OP_STRING | OpcodeType::OP_CLOSURE |
+        OpcodeType::OP_HASVAR | OpcodeType::OP_GETVAR | OpcodeType::OP_SETVAR | OpcodeType::OP_DELVAR |
+        OpcodeType::OP_GETPROP_S | OpcodeType::OP_SETPROP_S | OpcodeType::OP_DELPROP_S |
+        OpcodeType::OP_EVAL | OpcodeType::OP_CALL | OpcodeType::OP_NEW | OpcodeType::OP_CATCH => 1,
+
+        OpcodeType::OP_TRY | OpcodeType::OP_JUMP | OpcodeType::OP_JTRUE | OpcodeType::OP_JFALSE | OpcodeType::OP_JCASE => 2,
+
+        _ => 0,
+    }
+}
+
+fn is_jump(op: OpcodeType) -> bool {
+    match op {
+        OpcodeType::OP_TRY | OpcodeType::OP_JUMP | OpcodeType::OP_JTRUE | OpcodeType::OP_JFALSE | OpcodeType::OP_JCASE => true,
+        _ => false,
+    }
+}
+
+// split code into instructions, jump addresses are translated to instruction index
+fn decode(f: &VMFunction) -> Option<Vec<Instruction>> {
+    let mut lst: Vec<Instruction> = Vec::new();
+    let mut index_of: Vec<Option<usize>> = vec![None; f.code.len() + 1];
+
+    let mut pc: usize = 0;
+    let mut line: u32 = 0;
+    let mut next_line: usize = 0;
+    while pc < f.code.len() {
+        let op = OpcodeType::try_from(f.code[pc]).ok()?;
+        let size = operand_size(op);
+        if pc + size >= f.code.len() {
+            return None;
+        }
+        while next_line < f.lines.len() && f.lines[next_line].0 <= pc {
+            line = f.lines[next_line].1;
+            next_line = next_line + 1;
+        }
+
+        let mut ins = Instruction {
+            op:     op,
+            arg:    0,
+            target: 0,
+            line:   line,
+            live:   true,
+        };
+        if is_jump(op) {
+            ins.target = f.code[pc+1] as usize + (f.code[pc+2] as usize) * 65536;
+        } else if size == 1 {
+            ins.arg = f.code[pc+1];
+        }
+
+        index_of[pc] = Some(lst.len());
+        lst.push(ins);
+        pc = pc + 1 + size;
+    }
+    index_of[f.code.len()] = Some(lst.len());
+
+    for ins in lst.iter_mut() {
+        if is_jump(ins.op) {
+            if ins.target > f.code.len() {
+                return None;
+            }
+            ins.target = index_of[ins.target]?;
+        }
+    }
+    return Some(lst);
+}
+
+// write back instructions, removed instructions take no space so the
+// address of a removed one is the address of the next live instruction.
+fn encode(f: &mut VMFunction, lst: &Vec<Instruction>) {
+    let mut addr: Vec<usize> = Vec::with_capacity(lst.len() + 1);
+    let mut current: usize = 0;
+    for ins in lst {
+        addr.push(current);
+        if ins.live {
+            current = current + 1 + operand_size(ins.op);
+        }
+    }
+    addr.push(current);
+
+    let mut code: Vec<u16> = Vec::with_capacity(current);
+    let mut lines: Vec<(usize, u32)> = Vec::new();
+    for ins in lst {
+        if !ins.live {
+            continue;
+        }
+        if ins.line != 0 && (lines.len() == 0 || lines[lines.len() - 1].1 != ins.line) {
+            lines.push((code.len(), ins.line));
+        }
+        code.push(ins.op as u16);
+        if is_jump(ins.op) {
+            let dst = addr[ins.target];
+            code.push((dst & 0xFFFF) as u16);
+            code.push(((dst >> 16) & 0xFFFF) as u16);
+        } else if operand_size(ins.op) == 1 {
+            code.push(ins.arg);
+        }
+    }
+    f.code = code;
+    f.lines = lines;
+}
+
+fn next_live(lst: &Vec<Instruction>, from: usize) -> usize {
+    let mut i = from;
+    while i < lst.len() && !lst[i].live {
+        i = i + 1;
+    }
+    return i;
+}
+
+// point every jump to a live instruction
+fn retarget(lst: &mut Vec<Instruction>) {
+    for i in 0..lst.len() {
+        if lst[i].live && is_jump(lst[i].op) {
+            let target = next_live(lst, lst[i].target);
+            lst[i].target = target;
+        }
+    }
+}
+
+// instructions which can be entered without falling through from the previous one
+fn find_leaders(lst: &Vec<Instruction>) -> Vec<bool> {
+    let mut leaders = vec![false; lst.len() + 1];
+    for i in 0..lst.len() {
+        if !lst[i].live {
+            continue;
+        }
+        if is_jump(lst[i].op) {
+            leaders[lst[i].target] = true;
+        }
+        if lst[i].op == OpcodeType::OP_TRY {
+            /* the catch block follows OP_TRY */
+            leaders[next_live(lst, i + 1)] = true;
+        }
+    }
+    return leaders;
+}
+
+fn thread_jumps(lst: &mut Vec<Instruction>) -> bool {
+    let mut changed = false;
+    for i in 0..lst.len() {
+        if !lst[i].live {
+            continue;
+        }
+        match lst[i].op {
+            OpcodeType::OP_JUMP | OpcodeType::OP_JTRUE | OpcodeType::OP_JFALSE | OpcodeType::OP_JCASE => {},
+            _ => continue,
+        }
+
+        let mut target = lst[i].target;
+        let mut hops = 0;
+        while target < lst.len() && lst[target].op == OpcodeType::OP_JUMP && hops < lst.len() {
+            if lst[target].target == target {
+                break;
+            }
+            target = lst[target].target;
+            hops = hops + 1;
+        }
+        if target != lst[i].target {
+            lst[i].target = target;
+            changed = true;
+        }
+
+        if lst[i].op == OpcodeType::OP_JUMP {
+            if target == next_live(lst, i + 1) {
+                /* jump to the next instruction */
+                lst[i].live = false;
+                changed = true;
+            } else if target < lst.len() && lst[target].op == OpcodeType::OP_RETURN {
+                /* jump to return */
+                lst[i].op = OpcodeType::OP_RETURN;
+                changed = true;
+            }
+        }
+    }
+    return changed;
+}
+
+fn remove_unreachable(lst: &mut Vec<Instruction>) -> bool {
+    let mut reached = vec![false; lst.len() + 1];
+    let mut todo: Vec<usize> = vec![next_live(lst, 0)];
+
+    while let Some(i) = todo.pop() {
+        if i >= lst.len() || reached[i] {
+            continue;
+        }
+        reached[i] = true;
+
+        let next = next_live(lst, i + 1);
+        match lst[i].op {
+            OpcodeType::OP_RETURN | OpcodeType::OP_THROW => {},
+            OpcodeType::OP_JUMP => {
+                todo.push(lst[i].target);
+            },
+            OpcodeType::OP_JTRUE | OpcodeType::OP_JFALSE | OpcodeType::OP_JCASE | OpcodeType::OP_TRY => {
+                todo.push(lst[i].target);
+                todo.push(next);
+            },
+            _ => {
+                todo.push(next);
+            }
+        }
+    }
+
+    let mut changed = false;
+    for i in 0..lst.len() {
+        if lst[i].live && !reached[i] {
+            lst[i].live = false;
+            changed = true;
+        }
+    }
+    return changed;
+}
+
+fn constant_of(f: &VMFunction, ins: &Instruction) -> Option<f64> {
+    match ins.op {
+        OpcodeType::OP_INTEGER => Some(ins.arg as f64),
+        OpcodeType::OP_NUMBER => f.num_tab.get(ins.arg as usize).copied(),
+        _ => None,
+    }
+}
+
+fn make_constant(f: &mut VMFunction, value: f64, line: u32) -> Instruction {
+    let mut ins = Instruction {
+        op:     OpcodeType::OP_INTEGER,
+        arg:    0,
+        target: 0,
+        line:   line,
+        live:   true,
+    };
+
+    if value.fract() == 0.0 && value >= 0.0 && value <= 65535.0 && value.is_sign_positive() {
+        ins.arg = value as u16;
+        return ins;
+    }
+
+    ins.op = OpcodeType::OP_NUMBER;
+    for i in 0..f.num_tab.len() {
+        if f.num_tab[i] == value && f.num_tab[i].is_sign_positive() == value.is_sign_positive() {
+            ins.arg = i as u16;
+            return ins;
+        }
+    }
+    ins.arg = f.num_tab.len() as u16;
+    f.num_tab.push(value);
+    return ins;
+}
+
+fn fold_binary(op: OpcodeType, x: f64, y: f64) -> Option<f64> {
+    match op {
+        OpcodeType::OP_ADD => Some(x + y),
+        OpcodeType::OP_SUB => Some(x - y),
+        OpcodeType::OP_MUL => Some(x * y),
+        OpcodeType::OP_DIV => Some(x / y),
+        OpcodeType::OP_MOD => Some(x % y),
+        _ => None,
+    }
+}
+
+fn fold_unary(op: OpcodeType, x: f64) -> Option<f64> {
+    match op {
+        OpcodeType::OP_POS => Some(x),
+        OpcodeType::OP_NEG => Some(-x),
+        _ => None,
+    }
+}
+
+fn is_pure_push(op: OpcodeType) -> bool {
+    match op {
+        OpcodeType::OP_DUP | OpcodeType::OP_INTEGER | OpcodeType::OP_NUMBER | OpcodeType::OP_STRING |
+        OpcodeType::OP_UNDEF | OpcodeType::OP_NULL | OpcodeType::OP_TRUE | OpcodeType::OP_FALSE => true,
+        _ => false,
+    }
+}
+
+fn peephole(f: &mut VMFunction, lst: &mut Vec<Instruction>) -> bool {
+    let mut changed = false;
+    let mut leaders = find_leaders(lst);
+
+    let mut i = next_live(lst, 0);
+    while i < lst.len() {
+        let j = next_live(lst, i + 1);
+        if j >= lst.len() || leaders[j] {
+            i = j;
+            continue;
+        }
+
+        /* <pure push> OP_POP */
+        if is_pure_push(lst[i].op) && lst[j].op == OpcodeType::OP_POP {
+            lst[i].live = false;
+            lst[j].live = false;
+            changed = true;
+            i = next_live(lst, j + 1);
+            continue;
+        }
+
+        /* OP_TRUE/OP_FALSE with conditional jump */
+        if lst[i].op == OpcodeType::OP_TRUE || lst[i].op == OpcodeType::OP_FALSE {
+            let value = lst[i].op == OpcodeType::OP_TRUE;
+            let taken = match lst[j].op {
+                OpcodeType::OP_JTRUE => Some(value),
+                OpcodeType::OP_JFALSE => Some(!value),
+                _ => None,
+            };
+            if let Some(taken) = taken {
+                if taken {
+                    lst[i].op = OpcodeType::OP_JUMP;
+                    lst[i].target = lst[j].target;
+                    leaders[lst[i].target] = true;
+                } else {
+                    lst[i].live = false;
+                }
+                lst[j].live = false;
+                changed = true;
+                i = next_live(lst, j + 1);
+                continue;
+            }
+        }
+
+        if let Some(x) = constant_of(f, &lst[i]) {
+            /* <const> <unary> */
+            if let Some(v) = fold_unary(lst[j].op, x) {
+                lst[i] = make_constant(f, v, lst[i].line);
+                lst[j].live = false;
+                changed = true;
+                continue;
+            }
+
+            /* <const> <const> <binary> */
+            if let Some(y) = constant_of(f, &lst[j]) {
+                let k = next_live(lst, j + 1);
+                if k < lst.len() && !leaders[k] {
+                    if let Some(v) = fold_binary(lst[k].op, x, y) {
+                        lst[i] = make_constant(f, v, lst[i].line);
+                        lst[j].live = false;
+                        lst[k].live = false;
+                        changed = true;
+                        continue;
+                    }
+                }
+            }
+
+            /* small numbers are loaded without the number table */
+            if lst[i].op == OpcodeType::OP_NUMBER {
+                let ins = make_constant(f, x, lst[i].line);
+                if ins.op == OpcodeType::OP_INTEGER {
+                    lst[i] = ins;
+                    changed = true;
+                }
+            }
+        }
+
+        i = j;
+    }
+    return changed;
+}
+
+pub fn optimize_func(f: &mut VMFunction) {
+    for sub in f.func_tab.iter_mut() {
+        if let Some(sub) = Rc::get_mut(sub) {
+            optimize_func(sub);
+        }
+    }
+
+    let mut lst = match decode(f) {
+        Some(lst) => lst,
+        None => return,
+    };
+
+    loop {
+        retarget(&mut lst);
+        let mut changed = thread_jumps(&mut lst);
+        changed = remove_unreachable(&mut lst) || changed;
+        retarget(&mut lst);
+        changed = peephole(f, &mut lst) || changed;
+        if !changed {
+            break;
+        }
+    }
+
+    retarget(&mut lst);
+    encode(f, &lst);
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::runtime::*;
+use crate::builtin::eval_builtin;
+
+/*
+ * realms are copies of a template runtime, objects and scopes are copied deeply so
+ * scripts of one realm can't change the builtins of another. Compiled functions,
+ * natives and host objects are immutable or owned by the host, they are shared.
+ * Every builtin function and prototype is copied too, so a realm costs more than half of
+ * a new runtime: the saving is the work the template did after new_runtime, not the builtins.
+ */
+
+struct RealmCloner {
+	objects:	HashMap<*const std::cell::RefCell<JsObject>, SharedObject>,
+	scopes:		HashMap<*const std::cell::RefCell<JsEnvironment>, SharedScope>,
+}
+
+impl RealmCloner {
+	fn value(&mut self, v: &SharedValue) -> SharedValue {
+		if v.is_object() {
+			return SharedValue::new_sobject(self.object(&v.get_object()));
+		}
+		// primitives get new cells, properties are updated in place
+		return v.duplicate();
+	}
+
+	fn values(&mut self, values: &[SharedValue]) -> Vec<SharedValue> {
+		return values.iter().map(|v| self.value(v)).collect();
+	}
+
+	fn object(&mut self, obj: &SharedObject) -> SharedObject {
+		let key = Rc::as_ptr(obj);
+		if let Some(copy) = self.objects.get(&key) {
+			return copy.clone();
+		}
+		// registered before the fields are copied, for cycles
+		let copy = SharedObject_new(JsObject::new());
+		self.objects.insert(key, copy.clone());
+
+		let source = obj.borrow();
+		let mut properties = source.properties.clone();
+		for (_, prop) in properties.entries.iter_mut().flatten() {
+			prop.value = self.value(&prop.value);
+			prop.getter = prop.getter.as_ref().map(|f| self.object(f));
+			prop.setter = prop.setter.as_ref().map(|f| self.object(f));
+		}
+		let value = match source.value {
+			JsClass::object => JsClass::object,
+			JsClass::host(ref h) => JsClass::host(h.clone()),
+			JsClass::exception(ref e) => {
+				let mut e = e.clone();
+				e.value = e.value.as_ref().map(|v| self.value(v));
+				JsClass::exception(e)
+			},
+			JsClass::iterator(ref it) => JsClass::iterator(it.clone()),
+			JsClass::string(ref s) => JsClass::string(s.clone()),
+			JsClass::array(ref v) => JsClass::array(self.values(v)),
+			JsClass::regexp(ref re) => JsClass::regexp(re.clone()),
+			JsClass::date(t) => JsClass::date(t),
+			JsClass::function(ref f) => JsClass::function(JsFunction {
+				vmf: f.vmf.clone(),
+				scope: self.scope(&f.scope),
+			}),
+			JsClass::builtin(ref f) => JsClass::builtin(f.clone()),
+			JsClass::bound(ref b) => JsClass::bound(JsBoundFunction {
+				target: self.object(&b.target),
+				this: self.value(&b.this),
+				args: self.values(&b.args),
+			}),
+		};
+
+		let new_object = JsObject {
+			__proto__: source.__proto__.as_ref().map(|p| self.object(p)),
+			extensible: source.extensible,
+			properties: properties,
+			elements_attr: source.elements_attr,
+			value: value,
+		};
+		*copy.borrow_mut() = new_object;
+		return copy;
+	}
+
+	fn scope(&mut self, scope: &SharedScope) -> SharedScope {
+		let key = Rc::as_ptr(scope);
+		if let Some(copy) = self.scopes.get(&key) {
+			return copy.clone();
+		}
+		let copy = JsEnvironment::new();
+		self.scopes.insert(key, copy.clone());
+
+		let (variables, outer) = {
+			let source = scope.borrow();
+			(source.variables.clone(), source.outer.clone())
+		};
+		let variables = self.object(&variables);
+		let outer = outer.map(|s| self.scope(&s));
+		{
+			let mut env = copy.borrow_mut();
+			env.variables = variables;
+			env.outer = outer;
+		}
+		return copy;
+	}
+}
+
+// a new realm with copies of the globals and prototypes of the template
+pub fn clone_realm(template: &JsRuntime) -> JsRuntime {
+	let mut cloner = RealmCloner {
+		objects:	HashMap::new(),
+		scopes:		HashMap::new(),
+	};
+
+	let p = &template.prototypes;
+	let prototypes = JsPrototype {
+		object_prototype:		cloner.object(&p.object_prototype),
+		string_prototype:		cloner.object(&p.string_prototype),
+		number_prototype:		cloner.object(&p.number_prototype),
+		boolean_prototype:		cloner.object(&p.boolean_prototype),
+		regexp_prototype:		cloner.object(&p.regexp_prototype),
+		date_prototype:			cloner.object(&p.date_prototype),
+		array_prototype:		cloner.object(&p.array_prototype),
+		function_prototype:		cloner.object(&p.function_prototype),
+		exception_prototype:	cloner.object(&p.exception_prototype),
+		error_prototype:			cloner.object(&p.error_prototype),
+		type_error_prototype:		cloner.object(&p.type_error_prototype),
+		range_error_prototype:		cloner.object(&p.range_error_prototype),
+		reference_error_prototype:	cloner.object(&p.reference_error_prototype),
+		syntax_error_prototype:		cloner.object(&p.syntax_error_prototype),
+	};
+	let genv = cloner.scope(&template.genv);
+	// the copy of eval runs code in the global scope of the realm, not of the template
+	let eval_function = cloner.object(&template.eval_function);
+	eval_function.borrow_mut().value = JsClass::builtin(eval_builtin(&genv));
+
+	let mut runtime = JsRuntime {
+		prototypes:	prototypes,
+		genv:		genv.clone(),
+		cenv:		genv,
+		stack:		Vec::new(),
+		frames:		Vec::new(),
+		eval_function:	eval_function,
+
+		debugger:	Box::new(DefaultDebugger{}),
+		debug_mode:	DebugAction::Continue,
+		breakpoints: HashSet::new(),
+		limits:		template.limits,
+		opcodes:	0,
+		depth:		0,
+		native_base: 0,
+
+		random_state: 0,
+		clock:		Box::new(SystemClock{}),
+		native_this: SharedValue::new_undefined(),
+		host_types:	Vec::new(),
+		joining:	Vec::new(),
+	};
+	// host classes of the template are usable in the realm, with the copies of their prototypes
+	for t in template.host_types.iter() {
+		runtime.host_types.push(JsHostType {
+			class:		t.class.clone(),
+			prototype:	cloner.object(&t.prototype),
+		});
+	}
+
+	// realms don't share the sequence of Math.random
+	let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+	runtime.set_random_seed(seed ^ (Rc::as_ptr(&runtime.genv) as u64));
+	return runtime;
+}
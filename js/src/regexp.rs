@@ -0,0 +1,885 @@
+use std::rc::Rc;
+
+use crate::runtime::*;
+
+/* a backtracking regular expression engine for RegExp objects, input is matched in UTF-16 units */
+
+// the range of the whole match and of each capturing group, None for groups not participating
+pub type Captures = Vec<Option<(usize, usize)>>;
+
+#[derive(Clone, Debug)]
+enum ClassItem {
+	Range(u16, u16),
+	Not(Vec<(u16, u16)>),		// negated class escapes, \D \W \S
+}
+
+#[derive(Clone, Debug)]
+enum ReNode {
+	Char(u16),
+	Any,
+	Class(Vec<ClassItem>, bool),		// items, negated
+	LineStart,
+	LineEnd,
+	WordBoundary(bool),					// negated
+	Group(Box<ReNode>, Option<usize>),	// index of the capture
+	Look(Box<ReNode>, bool),			// lookahead, negated
+	BackRef(usize),
+	Seq(Vec<ReNode>),
+	Alt(Vec<ReNode>),
+	Repeat(Box<ReNode>, usize, usize, bool, (usize, usize)),	// min, max, greedy, captures inside
+}
+
+#[derive(Clone)]
+pub struct RegExp {
+	pub source:			String,
+	pub global:			bool,
+	pub ignore_case:	bool,
+	pub multiline:		bool,
+	ngroups:	usize,
+	root:		Rc<ReNode>,
+}
+
+const DIGIT_RANGES: [(u16, u16); 1] = [(0x30, 0x39)];
+const WORD_RANGES: [(u16, u16); 4] = [(0x30, 0x39), (0x41, 0x5A), (0x5F, 0x5F), (0x61, 0x7A)];
+const SPACE_RANGES: [(u16, u16); 10] = [(0x09, 0x0D), (0x20, 0x20), (0xA0, 0xA0), (0x1680, 0x1680), (0x2000, 0x200A),
+	(0x2028, 0x2029), (0x202F, 0x202F), (0x205F, 0x205F), (0x3000, 0x3000), (0xFEFF, 0xFEFF)];
+
+fn in_ranges(ranges: &[(u16, u16)], c: u16) -> bool {
+	return ranges.iter().any(|&(a, b)| c >= a && c <= b);
+}
+
+fn is_word(c: u16) -> bool {
+	return in_ranges(&WORD_RANGES, c);
+}
+
+fn is_line_terminator(c: u16) -> bool {
+	return c == 0x0A || c == 0x0D || c == 0x2028 || c == 0x2029;
+}
+
+// Canonicalize of ES5 15.10.2.8, the upper case of single character mappings
+fn canonicalize(c: u16) -> u16 {
+	let ch = match char::from_u32(c as u32) {
+		Some(ch) => ch,
+		None => return c,
+	};
+	let mut upper = ch.to_uppercase();
+	if let (Some(u), None) = (upper.next(), upper.next()) {
+		let u = u as u32;
+		if u <= 0xFFFF && !(c >= 128 && u < 128) {
+			return u as u16;
+		}
+	}
+	return c;
+}
+
+fn lower_case(c: u16) -> u16 {
+	if let Some(ch) = char::from_u32(c as u32) {
+		let mut lower = ch.to_lowercase();
+		if let (Some(l), None) = (lower.next(), lower.next()) {
+			if (l as u32) <= 0xFFFF {
+				return l as u32 as u16;
+			}
+		}
+	}
+	return c;
+}
+
+// nesting of groups and lookaheads accepted by the parser, deeper patterns are a SyntaxError
+const REGEXP_MAX_DEPTH: usize = 100;
+
+/* pattern parser */
+struct ReParser {
+	src:		Vec<u16>,
+	pos:		usize,
+	depth:		usize,		// groups being parsed
+	ngroups:	usize,
+	backrefs:	usize,		// the largest back reference
+}
+
+impl ReParser {
+	fn error(&self, msg: &str) -> String {
+		return format!("Invalid regular expression: {}", msg);
+	}
+
+	fn peek(&self) -> Option<u16> {
+		return self.src.get(self.pos).cloned();
+	}
+
+	fn peek_is(&self, c: char) -> bool {
+		return self.peek() == Some(c as u16);
+	}
+
+	fn accept(&mut self, c: char) -> bool {
+		if self.peek_is(c) {
+			self.pos = self.pos + 1;
+			return true;
+		}
+		return false;
+	}
+
+	fn disjunction(&mut self) -> Result<ReNode, String> {
+		let mut alts = vec![self.alternative()?];
+		while self.accept('|') {
+			alts.push(self.alternative()?);
+		}
+		if alts.len() == 1 {
+			return Ok(alts.pop().unwrap());
+		}
+		return Ok(ReNode::Alt(alts));
+	}
+
+	fn alternative(&mut self) -> Result<ReNode, String> {
+		let mut seq = Vec::new();
+		while let Some(c) = self.peek() {
+			if c == '|' as u16 || c == ')' as u16 {
+				break;
+			}
+			seq.push(self.term()?);
+		}
+		return Ok(ReNode::Seq(seq));
+	}
+
+	fn term(&mut self) -> Result<ReNode, String> {
+		if self.accept('^') {
+			return Ok(ReNode::LineStart);
+		}
+		if self.accept('$') {
+			return Ok(ReNode::LineEnd);
+		}
+		if self.peek_is('\\') {
+			match self.src.get(self.pos + 1).map(|c| *c as u8 as char) {
+				Some('b') => {
+					self.pos = self.pos + 2;
+					return Ok(ReNode::WordBoundary(false));
+				},
+				Some('B') => {
+					self.pos = self.pos + 2;
+					return Ok(ReNode::WordBoundary(true));
+				},
+				_ => {},
+			}
+		}
+
+		let first_group = self.ngroups + 1;
+		let atom = self.atom()?;
+		let captures = (first_group, self.ngroups + 1);
+		return self.quantifier(atom, captures);
+	}
+
+	fn quantifier(&mut self, atom: ReNode, captures: (usize, usize)) -> Result<ReNode, String> {
+		let start = self.pos;
+		let (min, max) = if self.accept('*') {
+			(0, usize::MAX)
+		} else if self.accept('+') {
+			(1, usize::MAX)
+		} else if self.accept('?') {
+			(0, 1)
+		} else if self.accept('{') {
+			match self.braces() {
+				Some(r) => r,
+				None => {
+					// not a quantifier, '{' is a literal character
+					self.pos = start;
+					return Ok(atom);
+				}
+			}
+		} else {
+			return Ok(atom);
+		};
+		if min > max {
+			return Err(self.error("numbers out of order in {} quantifier"));
+		}
+		let greedy = !self.accept('?');
+		return Ok(ReNode::Repeat(Box::new(atom), min, max, greedy, captures));
+	}
+
+	// {n}, {n,} or {n,m} after the '{'
+	fn braces(&mut self) -> Option<(usize, usize)> {
+		let min = self.decimal()?;
+		if self.accept('}') {
+			return Some((min, min));
+		}
+		if !self.accept(',') {
+			return None;
+		}
+		if self.accept('}') {
+			return Some((min, usize::MAX));
+		}
+		let max = self.decimal()?;
+		if !self.accept('}') {
+			return None;
+		}
+		return Some((min, max));
+	}
+
+	fn decimal(&mut self) -> Option<usize> {
+		let start = self.pos;
+		let mut n: usize = 0;
+		while let Some(c) = self.peek() {
+			if c < '0' as u16 || c > '9' as u16 {
+				break;
+			}
+			n = n.saturating_mul(10).saturating_add((c - '0' as u16) as usize);
+			self.pos = self.pos + 1;
+		}
+		if self.pos == start {
+			return None;
+		}
+		return Some(n);
+	}
+
+	fn atom(&mut self) -> Result<ReNode, String> {
+		let c = match self.peek() {
+			Some(c) => c,
+			None => return Err(self.error("unexpected end")),
+		};
+		self.pos = self.pos + 1;
+		match c as u8 as char {
+			_ if c > 0x7F => {
+				return Ok(ReNode::Char(c));
+			},
+			'.' => {
+				return Ok(ReNode::Any);
+			},
+			'(' => {
+				if self.depth >= REGEXP_MAX_DEPTH {
+					return Err(self.error("too many nested groups"));
+				}
+				self.depth = self.depth + 1;
+				let node = self.group();
+				self.depth = self.depth - 1;
+				return node;
+			},
+			')' => {
+				return Err(self.error("unmatched ')'"));
+			},
+			'[' => {
+				return self.class();
+			},
+			'\\' => {
+				return self.atom_escape();
+			},
+			'*' | '+' | '?' => {
+				return Err(self.error("nothing to repeat"));
+			},
+			'{' => {
+				let start = self.pos;
+				let quantifier = self.braces().is_some();
+				self.pos = start;
+				if quantifier {
+					return Err(self.error("nothing to repeat"));
+				}
+				return Ok(ReNode::Char(c));
+			},
+			_ => {
+				return Ok(ReNode::Char(c));
+			}
+		}
+	}
+
+	// a group or lookahead after the '('
+	fn group(&mut self) -> Result<ReNode, String> {
+		let node = if self.accept('?') {
+			if self.accept(':') {
+				ReNode::Group(Box::new(self.disjunction()?), None)
+			} else if self.accept('=') {
+				ReNode::Look(Box::new(self.disjunction()?), false)
+			} else if self.accept('!') {
+				ReNode::Look(Box::new(self.disjunction()?), true)
+			} else {
+				return Err(self.error("invalid group"));
+			}
+		} else {
+			self.ngroups = self.ngroups + 1;
+			let index = self.ngroups;
+			ReNode::Group(Box::new(self.disjunction()?), Some(index))
+		};
+		if !self.accept(')') {
+			return Err(self.error("unterminated group"));
+		}
+		return Ok(node);
+	}
+
+	fn atom_escape(&mut self) -> Result<ReNode, String> {
+		let c = match self.peek() {
+			Some(c) => c,
+			None => return Err(self.error("\\ at end of pattern")),
+		};
+		if c >= '1' as u16 && c <= '9' as u16 {
+			let n = self.decimal().unwrap();
+			if n > self.backrefs {
+				self.backrefs = n;
+			}
+			return Ok(ReNode::BackRef(n));
+		}
+		if let Some(items) = self.class_escape() {
+			return Ok(ReNode::Class(items, false));
+		}
+		return Ok(ReNode::Char(self.char_escape()?));
+	}
+
+	// \d \D \w \W \s \S
+	fn class_escape(&mut self) -> Option<Vec<ClassItem>> {
+		let ranges: &[(u16, u16)] = match self.peek().map(|c| c as u8 as char) {
+			Some('d') | Some('D') => &DIGIT_RANGES,
+			Some('w') | Some('W') => &WORD_RANGES,
+			Some('s') | Some('S') => &SPACE_RANGES,
+			_ => return None,
+		};
+		let negated = self.peek().unwrap() < 'a' as u16;
+		self.pos = self.pos + 1;
+		if negated {
+			return Some(vec![ClassItem::Not(ranges.to_vec())]);
+		}
+		return Some(ranges.iter().map(|&(a, b)| ClassItem::Range(a, b)).collect());
+	}
+
+	// the character after '\', the escape is consumed
+	fn char_escape(&mut self) -> Result<u16, String> {
+		let c = self.peek().unwrap();
+		self.pos = self.pos + 1;
+		let r = match c as u8 as char {
+			_ if c > 0x7F => c,
+			't' => 0x09,
+			'n' => 0x0A,
+			'v' => 0x0B,
+			'f' => 0x0C,
+			'r' => 0x0D,
+			'0' => 0x00,
+			'c' => {
+				match self.peek() {
+					Some(l) if (l >= 'a' as u16 && l <= 'z' as u16) || (l >= 'A' as u16 && l <= 'Z' as u16) => {
+						self.pos = self.pos + 1;
+						l % 32
+					},
+					_ => {
+						// not a control escape, '\' itself
+						self.pos = self.pos - 1;
+						'\\' as u16
+					}
+				}
+			},
+			'x' => self.hex_escape(2)?,
+			'u' => self.hex_escape(4)?,
+			_ => c,
+		};
+		return Ok(r);
+	}
+
+	fn hex_escape(&mut self, n: usize) -> Result<u16, String> {
+		if self.pos + n > self.src.len() {
+			return Err(self.error("invalid escape"));
+		}
+		let digits = String::from_utf16_lossy(&self.src[self.pos..self.pos + n]);
+		match u16::from_str_radix(&digits, 16) {
+			Ok(v) if digits.chars().all(|c| c.is_ascii_hexdigit()) => {
+				self.pos = self.pos + n;
+				return Ok(v);
+			},
+			_ => return Err(self.error("invalid escape")),
+		}
+	}
+
+	// character class after the '['
+	fn class(&mut self) -> Result<ReNode, String> {
+		let negated = self.accept('^');
+		let mut items = Vec::new();
+		loop {
+			if self.accept(']') {
+				break;
+			}
+			let first = match self.class_atom()? {
+				Ok(c) => c,
+				Err(escape) => {
+					items.extend(escape);
+					continue;
+				}
+			};
+			// range a-b, '-' is literal at the end of class
+			if self.peek_is('-') && self.src.get(self.pos + 1) != Some(&(']' as u16)) && self.pos + 1 < self.src.len() {
+				self.pos = self.pos + 1;
+				match self.class_atom()? {
+					Ok(last) => {
+						if first > last {
+							return Err(self.error("range out of order in character class"));
+						}
+						items.push(ClassItem::Range(first, last));
+					},
+					Err(escape) => {
+						items.push(ClassItem::Range(first, first));
+						items.push(ClassItem::Range('-' as u16, '-' as u16));
+						items.extend(escape);
+					}
+				}
+				continue;
+			}
+			items.push(ClassItem::Range(first, first));
+		}
+		return Ok(ReNode::Class(items, negated));
+	}
+
+	// a character, or a class escape in Err
+	fn class_atom(&mut self) -> Result<Result<u16, Vec<ClassItem>>, String> {
+		let c = match self.peek() {
+			Some(c) => c,
+			None => return Err(self.error("unterminated character class")),
+		};
+		self.pos = self.pos + 1;
+		if c != '\\' as u16 {
+			return Ok(Ok(c));
+		}
+		if self.peek().is_none() {
+			return Err(self.error("\\ at end of pattern"));
+		}
+		if let Some(items) = self.class_escape() {
+			return Ok(Err(items));
+		}
+		if self.accept('b') {
+			return Ok(Ok(0x08));
+		}
+		return Ok(Ok(self.char_escape()?));
+	}
+}
+
+impl ClassItem {
+	fn contains(&self, c: u16) -> bool {
+		match self {
+			ClassItem::Range(a, b) => {
+				return c >= *a && c <= *b;
+			},
+			ClassItem::Not(ranges) => {
+				return !in_ranges(ranges, c);
+			}
+		}
+	}
+}
+
+/*
+ * backtracking matcher, the nodes still to match are a linked list and the choices to retry are
+ * kept on a stack, so long inputs don't grow the Rust stack. The steps of one exec are bounded
+ * against exponential backtracking, exceeding them is a RangeError for scripts.
+ */
+const MATCH_MIN_STEPS: usize = 1 << 20;
+const MATCH_STEPS_PER_UNIT: usize = 64;		// more steps for each code unit of the input
+
+enum Todo<'r> {
+	Node(&'r ReNode),
+	CloseGroup(usize, usize),				// index of the capture, start of the group
+	RepeatNext(&'r ReNode, usize, usize),	// the repeat, iterations before this one, start of this one
+	RepeatBody(&'r ReNode, usize),			// the repeat, iterations done, another iteration of a lazy repeat
+}
+
+struct Frame<'r> {
+	todo:	Todo<'r>,
+	next:	Cont<'r>,
+}
+
+type Cont<'r> = Option<Rc<Frame<'r>>>;
+
+fn cont_push<'r>(todo: Todo<'r>, next: Cont<'r>) -> Cont<'r> {
+	return Some(Rc::new(Frame {
+		todo:	todo,
+		next:	next,
+	}));
+}
+
+enum Choice<'r> {
+	Resume(usize, Captures, Cont<'r>),
+	Range(usize, usize, usize, Captures, Cont<'r>),	// start, n, last: a single character repeat retried at start + n, n moves toward last
+}
+
+struct Matcher<'a> {
+	input:			&'a [u16],
+	ignore_case:	bool,
+	multiline:		bool,
+	steps:			usize,			// steps left
+	aborted:		bool,			// out of steps, nothing matches any more
+}
+
+impl<'a> Matcher<'a> {
+	fn class_match(&self, items: &[ClassItem], negated: bool, c: u16) -> bool {
+		let mut r = items.iter().any(|item| item.contains(c));
+		if !r && self.ignore_case {
+			let u = canonicalize(c);
+			let l = lower_case(c);
+			r = items.iter().any(|item| item.contains(u) || item.contains(l));
+		}
+		return r != negated;
+	}
+
+	// matching a single character node at pos
+	fn single(&self, node: &ReNode, pos: usize) -> Option<bool> {
+		let c = self.input.get(pos).cloned();
+		match node {
+			ReNode::Char(x) => {
+				return Some(match c {
+					Some(c) => c == *x || (self.ignore_case && canonicalize(c) == canonicalize(*x)),
+					None => false,
+				});
+			},
+			ReNode::Any => {
+				return Some(match c {
+					Some(c) => !is_line_terminator(c),
+					None => false,
+				});
+			},
+			ReNode::Class(items, negated) => {
+				return Some(match c {
+					Some(c) => self.class_match(items, *negated, c),
+					None => false,
+				});
+			},
+			_ => return None,
+		}
+	}
+
+	// the end and the captures of the first way to match all of cont from pos
+	fn run<'r>(&mut self, cont: Cont<'r>, pos: usize, caps: Captures) -> Option<(usize, Captures)> {
+		let mut choices: Vec<Choice<'r>> = Vec::new();
+		let mut state = Some((pos, caps, cont));
+		loop {
+			let (mut pos, mut caps, mut cont) = match state.take() {
+				Some(s) => s,
+				None => match choices.pop() {
+					None => return None,
+					Some(Choice::Resume(pos, caps, cont)) => (pos, caps, cont),
+					Some(Choice::Range(start, n, last, caps, cont)) => {
+						if n != last {
+							let next = if n < last { n + 1 } else { n - 1 };
+							choices.push(Choice::Range(start, next, last, caps.clone(), cont.clone()));
+						}
+						(start + n, caps, cont)
+					}
+				},
+			};
+
+			loop {
+				if self.aborted {
+					return None;
+				}
+				if self.steps == 0 {
+					self.aborted = true;
+					return None;
+				}
+				self.steps = self.steps - 1;
+
+				let frame = match cont {
+					Some(frame) => frame,
+					None => return Some((pos, caps)),
+				};
+				cont = frame.next.clone();
+				if !self.step(&frame.todo, &mut pos, &mut caps, &mut cont, &mut choices) {
+					break;
+				}
+			}
+		}
+	}
+
+	// one pending item, false when it doesn't match and the last choice is retried
+	fn step<'r>(&mut self, todo: &Todo<'r>, pos: &mut usize, caps: &mut Captures, cont: &mut Cont<'r>, choices: &mut Vec<Choice<'r>>) -> bool {
+		let node = match *todo {
+			Todo::Node(node) => node,
+			Todo::CloseGroup(index, start) => {
+				caps[index] = Some((start, *pos));
+				return true;
+			},
+			Todo::RepeatNext(node, count, start) => {
+				// an empty iteration can't satisfy the repeat any more
+				if let ReNode::Repeat(_, min, _, _, _) = node {
+					if *pos == start && count >= *min {
+						return false;
+					}
+				}
+				self.repeat(node, count + 1, *pos, caps, cont, choices);
+				return true;
+			},
+			Todo::RepeatBody(node, count) => {
+				self.repeat_body(node, count, *pos, caps, cont);
+				return true;
+			}
+		};
+
+		if let Some(r) = self.single(node, *pos) {
+			if r {
+				*pos = *pos + 1;
+			}
+			return r;
+		}
+
+		match node {
+			ReNode::LineStart => {
+				return *pos == 0 || (self.multiline && is_line_terminator(self.input[*pos - 1]));
+			},
+			ReNode::LineEnd => {
+				return *pos == self.input.len() || (self.multiline && is_line_terminator(self.input[*pos]));
+			},
+			ReNode::WordBoundary(negated) => {
+				let a = *pos > 0 && is_word(self.input[*pos - 1]);
+				let b = *pos < self.input.len() && is_word(self.input[*pos]);
+				return (a != b) != *negated;
+			},
+			ReNode::Group(inner, None) => {
+				*cont = cont_push(Todo::Node(inner), cont.take());
+				return true;
+			},
+			ReNode::Group(inner, Some(index)) => {
+				let next = cont_push(Todo::CloseGroup(*index, *pos), cont.take());
+				*cont = cont_push(Todo::Node(inner), next);
+				return true;
+			},
+			ReNode::Look(inner, negated) => {
+				// captures of a lookahead are kept, but it is never backtracked into
+				let found = self.run(cont_push(Todo::Node(inner), None), *pos, caps.clone());
+				if self.aborted {
+					return false;
+				}
+				if *negated {
+					return found.is_none();
+				}
+				match found {
+					Some((_, inner_caps)) => {
+						*caps = inner_caps;
+						return true;
+					},
+					None => return false,
+				}
+			},
+			ReNode::BackRef(index) => {
+				let (start, end) = match caps.get(*index).cloned().flatten() {
+					Some(r) => r,
+					None => return true,
+				};
+				let len = end - start;
+				if *pos + len > self.input.len() {
+					return false;
+				}
+				for i in 0..len {
+					let a = self.input[start + i];
+					let b = self.input[*pos + i];
+					if a != b && !(self.ignore_case && canonicalize(a) == canonicalize(b)) {
+						return false;
+					}
+				}
+				*pos = *pos + len;
+				return true;
+			},
+			ReNode::Seq(nodes) => {
+				for n in nodes.iter().rev() {
+					*cont = cont_push(Todo::Node(n), cont.take());
+				}
+				return true;
+			},
+			ReNode::Alt(alts) => {
+				for alt in alts[1..].iter().rev() {
+					choices.push(Choice::Resume(*pos, caps.clone(), cont_push(Todo::Node(alt), cont.clone())));
+				}
+				*cont = cont_push(Todo::Node(&alts[0]), cont.take());
+				return true;
+			},
+			ReNode::Repeat(inner, min, max, greedy, _) => {
+				if self.single(inner, *pos).is_some() {
+					return self.simple_repeat(inner, *min, *max, *greedy, pos, caps, cont, choices);
+				}
+				self.repeat(node, 0, *pos, caps, cont, choices);
+				return true;
+			},
+			_ => {
+				return false;
+			}
+		}
+	}
+
+	// repeating a single character node, the counts not taken are one choice
+	#[allow(clippy::too_many_arguments)]
+	fn simple_repeat<'r>(&self, inner: &ReNode, min: usize, max: usize, greedy: bool, pos: &mut usize, caps: &Captures, cont: &Cont<'r>, choices: &mut Vec<Choice<'r>>) -> bool {
+		let mut count = 0;
+		while count < max && self.single(inner, *pos + count) == Some(true) {
+			count = count + 1;
+		}
+		if count < min {
+			return false;
+		}
+		if greedy {
+			if count > min {
+				choices.push(Choice::Range(*pos, count - 1, min, caps.clone(), cont.clone()));
+			}
+			*pos = *pos + count;
+		} else {
+			if count > min {
+				choices.push(Choice::Range(*pos, min + 1, count, caps.clone(), cont.clone()));
+			}
+			*pos = *pos + min;
+		}
+		return true;
+	}
+
+	// after count iterations of the repeat node at pos
+	fn repeat<'r>(&self, node: &'r ReNode, count: usize, pos: usize, caps: &mut Captures, cont: &mut Cont<'r>, choices: &mut Vec<Choice<'r>>) {
+		let (min, max, greedy) = match node {
+			ReNode::Repeat(_, min, max, greedy, _) => (*min, *max, *greedy),
+			_ => return,
+		};
+		if count >= max {
+			return;
+		}
+		if count >= min {
+			if !greedy {
+				choices.push(Choice::Resume(pos, caps.clone(), cont_push(Todo::RepeatBody(node, count), cont.clone())));
+				return;
+			}
+			choices.push(Choice::Resume(pos, caps.clone(), cont.clone()));
+		}
+		self.repeat_body(node, count, pos, caps, cont);
+	}
+
+	fn repeat_body<'r>(&self, node: &'r ReNode, count: usize, pos: usize, caps: &mut Captures, cont: &mut Cont<'r>) {
+		if let ReNode::Repeat(inner, _, _, _, captures) = node {
+			// captures inside the atom are cleared for every iteration
+			for cap in caps[captures.0..captures.1].iter_mut() {
+				*cap = None;
+			}
+			let next = cont_push(Todo::RepeatNext(node, count, pos), cont.take());
+			*cont = cont_push(Todo::Node(inner), next);
+		}
+	}
+}
+
+impl RegExp {
+	pub fn new(pattern: &str, flags: &str) -> Result<RegExp, String> {
+		let mut global = false;
+		let mut ignore_case = false;
+		let mut multiline = false;
+		for c in flags.chars() {
+			let flag = match c {
+				'g' => &mut global,
+				'i' => &mut ignore_case,
+				'm' => &mut multiline,
+				_ => return Err(format!("Invalid regular expression flags '{}'", flags)),
+			};
+			if *flag {
+				return Err(format!("Invalid regular expression flags '{}'", flags));
+			}
+			*flag = true;
+		}
+
+		let mut parser = ReParser {
+			src:		pattern.encode_utf16().collect(),
+			pos:		0,
+			depth:		0,
+			ngroups:	0,
+			backrefs:	0,
+		};
+		let root = parser.disjunction()?;
+		if parser.pos < parser.src.len() {
+			return Err(parser.error("unmatched ')'"));
+		}
+		if parser.backrefs > parser.ngroups {
+			return Err(parser.error("invalid back reference"));
+		}
+
+		return Ok(RegExp {
+			source:			escape_source(pattern),
+			global:			global,
+			ignore_case:	ignore_case,
+			multiline:		multiline,
+			ngroups:		parser.ngroups,
+			root:			Rc::new(root),
+		});
+	}
+
+	// count of capturing groups
+	pub fn ngroups(&self) -> usize {
+		return self.ngroups;
+	}
+
+	pub fn flags(&self) -> String {
+		let mut flags = String::new();
+		if self.global {
+			flags.push('g');
+		}
+		if self.ignore_case {
+			flags.push('i');
+		}
+		if self.multiline {
+			flags.push('m');
+		}
+		return flags;
+	}
+
+	// the first match at or after start, index 0 is the whole match followed by the groups
+	pub fn exec(&self, input: &[u16], start: usize) -> Result<Option<Captures>, JsException> {
+		let mut matcher = Matcher {
+			input:			input,
+			ignore_case:	self.ignore_case,
+			multiline:		self.multiline,
+			steps:			MATCH_MIN_STEPS.saturating_add(input.len().saturating_mul(MATCH_STEPS_PER_UNIT)),
+			aborted:		false,
+		};
+		for begin in start..=input.len() {
+			let caps: Captures = vec![None; self.ngroups + 1];
+			let found = matcher.run(cont_push(Todo::Node(&self.root), None), begin, caps);
+			if matcher.aborted {
+				return Err(JsException::new_range_error(format!("Regular expression /{}/ is too complex for the input", self.source)));
+			}
+			if let Some((end, mut caps)) = found {
+				caps[0] = Some((begin, end));
+				return Ok(Some(caps));
+			}
+		}
+		return Ok(None);
+	}
+}
+
+// the source text as written in a literal, '/' is escaped
+fn escape_source(pattern: &str) -> String {
+	if pattern.is_empty() {
+		return "(?:)".to_string();
+	}
+	let mut result = String::new();
+	let mut escaped = false;
+	let mut in_class = false;
+	for c in pattern.chars() {
+		if c == '/' && !escaped && !in_class {
+			result.push('\\');
+		}
+		if !escaped {
+			if c == '[' {
+				in_class = true;
+			} else if c == ']' {
+				in_class = false;
+			}
+		}
+		escaped = c == '\\' && !escaped;
+		result.push(c);
+	}
+	return result;
+}
+
+impl JsRuntime {
+	// a RegExp object with lastIndex and the flag properties
+	pub fn new_regexp(&mut self, pattern: &str, flags: &str) -> Result<SharedValue, JsException> {
+		let re = match RegExp::new(pattern, flags) {
+			Ok(re) => re,
+			Err(msg) => return Err(JsException::new_syntax_error(msg)),
+		};
+		let props = [
+			("source", SharedValue::new_sobject(SharedObject_new(JsObject::new_with(self.prototypes.string_prototype.clone(), JsClass::string(re.source.clone()))))),
+			("global", SharedValue::new_boolean(re.global)),
+			("ignoreCase", SharedValue::new_boolean(re.ignore_case)),
+			("multiline", SharedValue::new_boolean(re.multiline)),
+		];
+		let obj = SharedObject_new(JsObject::new_with(self.prototypes.regexp_prototype.clone(), JsClass::regexp(re)));
+		for (name, v) in props.iter() {
+			let mut prop = JsProperty::new();
+			prop.fill_attr(JS_READONLY_ATTR);
+			prop.value = v.clone();
+			obj.borrow_mut().properties.insert(name.to_string(), prop);
+		}
+		let mut prop = JsProperty::new();
+		prop.fill_attr((true, false, false));
+		prop.value = SharedValue::new_number(0.0);
+		obj.borrow_mut().properties.insert("lastIndex".to_string(), prop);
+
+		return Ok(SharedValue::new_sobject(obj));
+	}
+}
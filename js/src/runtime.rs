@@ -1,10 +1,13 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use std::ffi::c_void;
+use std::any::Any;
 
 use crate::bytecode::*;
+use crate::regexp::RegExp;
 
 // runtime stuff
 pub type SharedObject = Rc<RefCell<JsObject>>;
@@ -47,12 +50,25 @@ pub struct JsFunction {
 	pub scope:	SharedScope,
 }
 
+// functions created by Function.prototype.bind
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone)]
+pub struct JsBoundFunction {
+	pub target:	SharedObject,
+	pub this:	SharedValue,
+	pub args:	Vec<SharedValue>,
+}
+
+/* native functions take the arguments and return the result, 'this' is JsRuntime::this_value() */
+pub type JsNativeFunction = dyn Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException>;
+
+#[allow(non_camel_case_types)]
+#[derive(Clone)]
 pub struct JsBuiltinFunction {
-	pub f:		fn(&mut JsRuntime),
-	pub argc:	usize,
+	pub f:		Rc<JsNativeFunction>,
+	pub argc:	usize,			// arguments are padded with undefined up to argc
 }
+pub const JS_VARARGS: usize = usize::MAX;	// argc of builtins taking any number of arguments
+pub const JS_ARRAY_MAX_LENGTH: usize = 1 << 24;	// arrays are dense, longer ones are RangeErrors
 
 #[allow(non_camel_case_types)]
 #[derive(Clone)]
@@ -61,35 +77,81 @@ pub struct JsIterator {
 	pub index:	usize,
 }
 
+/* types of errors raised by the runtime, Exception is the legacy class of scripts */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JsErrorType {
+	Exception,
+	Error,
+	TypeError,
+	RangeError,
+	ReferenceError,
+	SyntaxError,
+}
+
 #[allow(non_camel_case_types)]
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub struct JsException {
-	pub msg:	String,
+	pub value:		Option<SharedValue>,	// the thrown value, none for errors raised by the runtime
+	pub kind:		JsErrorType,
+	pub abort:		bool,					// abort the script, bypass all catch blocks
+	pub msg:		String,
+	pub location:	String,			// "file:line" where the exception is raised
+	pub trace:		Vec<String>,	// call stack, the innermost frame first
 }
 
-#[allow(non_camel_case_types)]
+pub type JsHostAccessor = (String, Option<JsBuiltinFunction>, Option<JsBuiltinFunction>);	// name, getter, setter
+pub type JsFinalizer = dyn Fn(&dyn Any);
+
+/* class descriptor of host objects, methods and accessors are installed on the prototype */
+pub struct JsHostClass {
+	pub name:		String,
+	pub methods:	Vec<(String, JsBuiltinFunction)>,
+	pub accessors:	Vec<JsHostAccessor>,
+	pub finalizer:	Option<Box<JsFinalizer>>,		// called when the script object is dropped
+}
+
+// a host class registered in one runtime, realms of the runtime have copies of the prototype
 #[derive(Clone)]
-pub struct JsExpander {
-	pub ptr: *const c_void,
+pub struct JsHostType {
+	pub class:		Rc<JsHostClass>,
+	pub prototype:	SharedObject,
+}
+
+// the payload of host objects, natives get it back by SharedValue::get_host, copies of realms share it
+pub struct JsHostObject {
+	pub class:		Rc<JsHostClass>,
+	pub payload:	Rc<dyn Any>,
 }
 
 #[allow(non_camel_case_types)]
 pub enum JsClass {
 	object,
-	expand(JsExpander),
+	host(Rc<JsHostObject>),
 	exception(JsException),
 	iterator(JsIterator),
 	string(String),
 	array(Vec<SharedValue>),
+	regexp(RegExp),
+	date(f64),				// the time value, NaN for invalid dates
 	function(JsFunction),
 	builtin(JsBuiltinFunction),
+	bound(JsBoundFunction),
+}
+
+/* properties are kept in the insertion order, for-in and JSON depend on it */
+#[allow(non_camel_case_types)]
+#[derive(Clone)]
+pub struct JsPropertyMap {
+	pub index:		HashMap<String, usize>,
+	pub entries:	Vec<Option<(String, JsProperty)>>,		// None for deleted properties
 }
 
 #[allow(non_camel_case_types)]
 pub struct JsObject {
 	pub __proto__:	Option<SharedObject>,
 	pub extensible:	bool,
-	pub properties: HashMap<String, JsProperty>,
+	pub properties: JsPropertyMap,
+	pub elements_attr:	JsPropertyAttr,		// shared by all elements of arrays
 	pub value:	JsClass,
 }
 
@@ -121,11 +183,97 @@ pub struct JsPrototype {
 	/* prototype for different objects */
 	pub object_prototype:	SharedObject,
 	pub string_prototype:	SharedObject,
+	pub number_prototype:	SharedObject,
+	pub boolean_prototype:	SharedObject,
+	pub regexp_prototype:	SharedObject,
+	pub date_prototype:		SharedObject,
 	pub array_prototype:	SharedObject,
 	pub function_prototype: SharedObject,
 
-	/* prototype for exceptions */
+	/* prototype for exceptions, the error types inherit from it */
 	pub exception_prototype: SharedObject,
+	pub error_prototype:			SharedObject,
+	pub type_error_prototype:		SharedObject,
+	pub range_error_prototype:		SharedObject,
+	pub reference_error_prototype:	SharedObject,
+	pub syntax_error_prototype:		SharedObject,
+}
+
+/* debugger hook for host applications */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DebugAction {
+	Continue,		// run until next debugger statement or breakpoint
+	Step,			// break again before the next opcode
+	StepLine,		// break again when reaching a new source line
+	Abort,			// stop the script, can't be caught by scripts
+}
+
+pub trait Debugger {
+	fn on_debug(&mut self, vmf: &VMFunction, pc: usize, stack: &[SharedValue], env: &SharedScope) -> DebugAction;
+}
+
+pub struct DefaultDebugger {}
+
+impl Debugger for DefaultDebugger {
+	fn on_debug(&mut self, _vmf: &VMFunction, _pc: usize, _stack: &[SharedValue], _env: &SharedScope) -> DebugAction {
+		return DebugAction::Continue;
+	}
+}
+
+/* time source of Date, hosts can freeze the time for tests */
+pub trait Clock {
+	fn now(&self) -> f64;						// milliseconds since 1970-01-01 UTC
+	fn local_offset(&self, _t: f64) -> f64 {	// offset of the local time zone in milliseconds at the UTC time t
+		return 0.0;
+	}
+}
+
+// the system time, the local time zone is UTC
+pub struct SystemClock {}
+
+impl Clock for SystemClock {
+	fn now(&self) -> f64 {
+		match SystemTime::now().duration_since(UNIX_EPOCH) {
+			Ok(d) => return d.as_millis() as f64,
+			Err(e) => return -(e.duration().as_millis() as f64),
+		}
+	}
+}
+
+/*
+ * execution limits for untrusted scripts, 0 means unlimited. A call of a native function is one opcode,
+ * its own loops (e.g. sort or regexp matching) aren't counted against max_opcodes.
+ *
+ * Every call, script or native, and every level of values walked recursively by natives (e.g. join of
+ * nested arrays or JSON.stringify) is one nesting level. Exceeding max_depth or max_native_stack throws
+ * the same RangeError, which scripts can catch. By default only the Rust stack is bounded, so the depth
+ * reached differs between debug and release builds, the error doesn't. The default suits the 2 MB stack
+ * of spawned threads, hosts running scripts on larger stacks can raise it. Parsers (scripts, eval, JSON
+ * and regexps) limit the nesting of the source too, they report SyntaxError instead.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct JsLimits {
+	pub max_opcodes:	usize,		// opcodes executed by one run_script
+	pub max_depth:		usize,		// nesting levels, a bound which is the same in every build
+	pub max_stack:		usize,		// size of the value stack
+	pub max_native_stack:	usize,	// bytes of the Rust stack used by nesting levels
+}
+
+impl Default for JsLimits {
+	fn default() -> Self {
+		JsLimits {
+			max_opcodes:	0,
+			max_depth:		0,
+			max_stack:		0,
+			max_native_stack:	1 << 20,
+		}
+	}
+}
+
+#[allow(non_camel_case_types)]
+pub struct JsFrame {
+	pub vmf:	SharedFunction,
+	pub pc:		usize,				// the current executing opcode
 }
 
 #[allow(non_camel_case_types)]
@@ -136,6 +284,24 @@ pub struct JsRuntime {
 	pub cenv:			SharedScope,
 
 	pub stack:			Vec<SharedValue>,
+	pub frames:			Vec<JsFrame>,
+
+	pub eval_function:	SharedObject,	// calls to this object are dispatched to eval
+
+	pub debugger:		Box<dyn Debugger>,
+	pub debug_mode:		DebugAction,				// Continue, Step or StepLine
+	pub breakpoints:	HashSet<(usize, u32)>,		// (address of VMFunction, line)
+
+	pub limits:			JsLimits,
+	pub opcodes:		usize,			// opcodes executed in the current run
+	pub depth:			usize,			// nesting levels entered, see JsLimits
+	pub native_base:	usize,			// address of the Rust stack at the outermost level
+
+	pub random_state:	u64,			// state of the PRNG behind Math.random
+	pub clock:			Box<dyn Clock>,	// time source of Date
+	pub native_this:	SharedValue,	// 'this' of the running native function
+	pub host_types:		Vec<JsHostType>,	// host classes registered in this runtime
+	pub joining:		Vec<SharedObject>,	// arrays being joined by join or toString
 }
 
 
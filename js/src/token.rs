@@ -1,4 +1,5 @@
 use std::char;
+use std::cmp;
 use std::collections::LinkedList;
 use crate::common::*;
 
@@ -13,6 +14,7 @@ pub enum TokenType {
 	TK_IDENTIFIER = 0,
     TK_NUMBER,
 	TK_STRING,
+	TK_REGEXP,
 
 	/* keywords */
 	TK_BREAK,
@@ -192,6 +194,9 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
         if c == 'n' {
             return '\n';
         }
+        if c == 'r' {
+            return '\r';
+        }
         return c;
     }
 
@@ -229,7 +234,9 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
 	loop {
         let chr = chars.next();
 		let ct = check_ct(chr);
-        pos = pos + 1;
+        // cursor is a byte offset of the script
+        let chr_len = chr.map_or(1, |c| c.len_utf8());
+        pos = pos + chr_len;
 
         // state handler
         if ps == ps::PS_NULL {
@@ -282,7 +289,7 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                 ct::CT_NEWLN => {
                     let value = tkbuf.into_iter().collect();
                     let symbol = GeneralToken::new_with(GeneralTokenType::TK_SYMBOL_, value);
-                    return Ok((symbol, pos - 1));
+                    return Ok((symbol, pos - chr_len));
                 },
                 ct::CT_LETTER => {
                     tkbuf.push( chr.unwrap());
@@ -291,7 +298,7 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                 ct::CT_PUNCT => {
                     let value = tkbuf.into_iter().collect();
                     let symbol = GeneralToken::new_with(GeneralTokenType::TK_SYMBOL_, value);
-                    return Ok((symbol, pos-1));
+                    return Ok((symbol, pos - chr_len));
                 }
             }
         }
@@ -303,7 +310,13 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                     return Err("Parsing string get end of file!");
                 },
                 ct::CT_NEWLN | ct::CT_LETTER | ct::CT_SPACE => {
-                    tkbuf.push( chr.unwrap());
+                    let ch = chr.unwrap();
+                    if tkbuf.len() > 0 && tkbuf[tkbuf.len() - 1] == '\0' {
+                        let last = tkbuf.len() - 1;
+                        tkbuf[last] = check_escape( ch );
+                        continue;
+                    }
+                    tkbuf.push(ch);
                     continue;
                 },
                 ct::CT_PUNCT => {
@@ -360,7 +373,7 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                 ct::CT_NEWLN => {
                     let value = tkbuf.into_iter().collect();
                     let comment = GeneralToken::new_with(GeneralTokenType::TK_COMMENT_, value);
-                    return Ok((comment, pos-1));
+                    return Ok((comment, pos - chr_len));
                 },
                 ct::CT_EOF => {
                     let value = tkbuf.into_iter().collect();
@@ -385,19 +398,19 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                 ct::CT_NEWLN => {
                     let value = tkbuf.into_iter().collect();
                     let punct = GeneralToken::new_with(GeneralTokenType::TK_PUNCT_, value);
-                    return Ok((punct, pos-1));
+                    return Ok((punct, pos - chr_len));
                 },
                 ct::CT_LETTER => {
                     let value = tkbuf.into_iter().collect();
                     let punct = GeneralToken::new_with(GeneralTokenType::TK_PUNCT_, value);
-                    return Ok((punct, pos-1));
+                    return Ok((punct, pos - chr_len));
                 },
                 ct::CT_PUNCT => {
                     let ch = chr.unwrap();
                     if ch == ';' {
                         let value = tkbuf.into_iter().collect();
                         let punct = GeneralToken::new_with(GeneralTokenType::TK_PUNCT_, value);
-                        return Ok((punct, pos-1));
+                        return Ok((punct, pos - chr_len));
                     }
                     {
                         // check is valid multiple punctuators
@@ -423,7 +436,7 @@ fn next_general_token (script: &str, cursor: usize) -> Result<(GeneralToken, usi
                     }
                     let value = tkbuf.into_iter().collect();
                     let punct = GeneralToken::new_with(GeneralTokenType::TK_PUNCT_, value);
-                    return Ok((punct, pos-1));
+                    return Ok((punct, pos - chr_len));
                 }
             }
         }
@@ -631,7 +644,13 @@ fn get_next_token(script: &str,  cursor: usize, line: u32) -> Result<(Token, (us
             },
             GeneralTokenType::TK_PUNCT_  => {
                 let value = tk.tk_value.unwrap();
-                let tkt = get_token_type(&value).unwrap();
+                let tkt = match get_token_type(&value) {
+                    Some(tkt) => tkt,
+                    None => {
+                        let err_msg = format!("Parsing error @ {} : unexpected punctuator '{}'", line, value);
+                        return Err(err_msg);
+                    }
+                };
                 if tkt == TokenType::TK_NEWLN {
                     line = line + 1;
                 }
@@ -697,10 +716,14 @@ pub struct Tokenlizer<'a> {
     script : &'a str,
     cursor : usize,
     line : u32,
+    start : usize,      // where the last fetched token starts
+    last : TokenType,   // type of the last fetched token, for the regexp literal
     forward_: LinkedList<(Token, bool)>,
 
     // help state variable for AST
     pub notin: bool, 
+    pub depth: usize,           // nesting of the source being parsed
+    pub stack_limit: usize,     // the parser doesn't use the Rust stack below this address, 0 for no limit
 }
 
 impl<'a> Tokenlizer<'a> {
@@ -709,9 +732,13 @@ impl<'a> Tokenlizer<'a> {
             script: script,
             cursor: 0,
             line: 1,
+            start: 0,
+            last: TokenType::TK_NEWLN,
             forward_: LinkedList::new(),
 
             notin: false,
+            depth: 0,
+            stack_limit: 0,
         }
     }
 
@@ -755,6 +782,20 @@ impl<'a> Tokenlizer<'a> {
         return self.line;
     }
 
+    // column of the last fetched token, counting from 1
+    pub fn column(&self) -> u32 {
+        let bytes = self.script.as_bytes();
+        let mut pos = cmp::min(self.start, bytes.len());
+        while pos < bytes.len() && (bytes[pos] == b' ' || bytes[pos] == b'\t' || bytes[pos] == b'\r') {
+            pos = pos + 1;
+        }
+        let begin = match bytes[..pos].iter().rposition(|b| *b == b'\n') {
+            Some(n) => n + 1,
+            None => 0,
+        };
+        return (pos - begin + 1) as u32;
+    }
+
     fn split_identifier(&mut self, token: Token, new_line: bool) {
         assert!(token.tk_type == TokenType::TK_IDENTIFIER);
 
@@ -787,9 +828,67 @@ impl<'a> Tokenlizer<'a> {
         }
     }
 
+    // '/' starts a regexp literal when the last token can't end an expression
+    fn regexp_allowed(&self) -> bool {
+        match self.last {
+            TokenType::TK_IDENTIFIER | TokenType::TK_NUMBER | TokenType::TK_STRING | TokenType::TK_REGEXP |
+            TokenType::TK_PAREN_RIGHT | TokenType::TK_BRACKET_RIGHT | TokenType::TK_BRACE_RIGHT |
+            TokenType::TK_THIS | TokenType::TK_TRUE | TokenType::TK_FALSE | TokenType::TK_NULL | TokenType::TK_UNDEF |
+            TokenType::TK_INC | TokenType::TK_DEC => false,
+            _ => true,
+        }
+    }
+
+    // scan the regexp literal starting with '/' at begin, return the literal and the end cursor
+    fn scan_regexp(&self, begin: usize) -> Result<(String, usize), String> {
+        let code = &self.script[begin..];
+        let mut chars = code.char_indices().skip(1);
+        let mut in_class = false;
+        let mut end = None;
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '\\' => {
+                    match chars.next() {
+                        Some((_, '\n')) | None => break,
+                        _ => continue,
+                    }
+                },
+                '\n' => {
+                    break;
+                },
+                '[' => {
+                    in_class = true;
+                },
+                ']' => {
+                    in_class = false;
+                },
+                '/' if !in_class => {
+                    end = Some(i + 1);
+                    break;
+                },
+                _ => {},
+            }
+        }
+        let mut end = match end {
+            Some(n) => n,
+            None => {
+                return Err(format!("Parsing error @ {} : unterminated regexp literal", self.line));
+            }
+        };
+        // flags
+        for ch in code[end..].chars() {
+            if !(ch.is_alphanumeric() || ch == '_' || ch == '$') {
+                break;
+            }
+            end = end + ch.len_utf8();
+        }
+        return Ok((code[..end].to_string(), begin + end));
+    }
+
     fn fetch_next(&mut self) -> Result<(), String> {
         let mut new_line = false;
         loop {            
+            self.start = self.cursor;
             let result = get_next_token(self.script, self.cursor, self.line);
             if result.is_ok() {
                 let (token, (cursor, line)) = result.unwrap();
@@ -804,11 +903,30 @@ impl<'a> Tokenlizer<'a> {
                     continue;
                 }
 
+                if (token.tk_type == TokenType::TK_DIV || token.tk_type == TokenType::TK_DIV_ASS) && self.regexp_allowed() {
+                    // back to the '/' of the punctuator
+                    let bytes = self.script.as_bytes();
+                    let mut begin = cursor;
+                    while begin > 0 && (bytes[begin - 1] == b' ' || bytes[begin - 1] == b'\t' || bytes[begin - 1] == b'\r') {
+                        begin = begin - 1;
+                    }
+                    begin = begin - if token.tk_type == TokenType::TK_DIV { 1 } else { 2 };
+
+                    let (literal, end) = self.scan_regexp(begin)?;
+                    self.cursor = end;
+                    let tk = Token::new_with(TokenType::TK_REGEXP, literal, token.src_line);
+                    self.last = TokenType::TK_REGEXP;
+                    self.forward_.push_back((tk, new_line));
+                    return Ok(());
+                }
+
                 if token.tk_type != TokenType::TK_IDENTIFIER {
+                    self.last = token.tk_type.clone();
                     self.forward_.push_back((token, new_line));
                     return Ok(());
                 }
                 self.split_identifier(token, new_line);
+                self.last = self.forward_.back().unwrap().0.tk_type.clone();
 
                 return Ok(());
             }
@@ -2,11 +2,12 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::rc::Rc;
-use std::ffi::c_void;
+use std::any::Any;
 
 use crate::common::*;
 use crate::bytecode::*;
 use crate::runtime::*;
+use crate::regexp::RegExp;
 
 /* implementation for VMFunction/SharedValue/JsValue/JsObject */
 
@@ -14,6 +15,7 @@ impl VMFunction {
 	pub fn new_anonymous() -> Self {
 		let mut anonymous = VMFunction {
             name:   None,
+            filename:   String::new(),
             script: false,
             numparams: 0,
             numvars: 0,
@@ -21,6 +23,7 @@ impl VMFunction {
             num_tab:    Vec::new(),
             str_tab:    Vec::new(),           
             func_tab:   Vec::new(),
+            lines:      Vec::new(),
 
             jumps:      Vec::new(),
         };
@@ -87,6 +90,36 @@ impl VMFunction {
 		*pc = *pc + 2;
 		return addr;
 	}
+	// source line of the code at pc, 0 for unknown
+	pub fn line(&self, pc: usize) -> u32 {
+		let mut line = 0;
+		for (addr, l) in &self.lines {
+			if *addr > pc {
+				break;
+			}
+			line = *l;
+		}
+		return line;
+	}
+	// the line begins at pc, None when pc is inside a line
+	pub fn line_start(&self, pc: usize) -> Option<u32> {
+		if let Ok(i) = self.lines.binary_search_by_key(&pc, |&(addr, _)| addr) {
+			return Some(self.lines[i].1);
+		}
+		return None;
+	}
+	// search the nested functions by name, used for setting breakpoints
+	pub fn find_function(&self, name: &str) -> Option<SharedFunction> {
+		for f in &self.func_tab {
+			if f.name.as_deref() == Some(name) {
+				return Some(f.clone());
+			}
+			if let Some(r) = f.find_function(name) {
+				return Some(r);
+			}
+		}
+		return None;
+	}
 }
 
 impl Clone for JsValue {
@@ -120,6 +153,65 @@ impl SharedValue {
 			self.v.borrow_mut().copyfrom( &other.v.borrow());
 		}
 	}
+	pub fn strict_equals(&self, other: &SharedValue) -> bool {
+		let x = self;
+		let y = other;
+		
+		// string with others
+		if x.is_string() {
+			let x_str = x.to_string();
+			if y.is_string() {
+				let y_str = y.to_string();
+				if x_str == y_str {
+					return true;
+				}
+			} 
+			return false;
+		}
+
+		// null with defineded
+		if x.is_undefined() {
+			if y.is_undefined() {
+				return true;
+			}			
+			return false;
+		}
+
+		if x.is_null() {			
+			if y.is_null() {
+				return true;
+			}
+			return false;
+		}
+
+		// boolean with boolean
+		if x.is_boolean()  {
+			if y.is_boolean() {
+				return x.to_boolean() == y.to_boolean();
+			}
+			return false;
+		}
+
+		// number with others
+		if x.is_number() {
+			let x_num = x.to_number();
+			if y.is_number() {
+				let y_num = y.to_number();
+				if x_num == y_num {
+					return true;
+				}
+			}
+			return false;
+		}
+
+		// object with object
+		let x_obj = x.get_object();
+		if y.is_object() {
+			let y_obj = y.get_object();
+			return Rc::ptr_eq(&x_obj, &y_obj);
+		}
+		return false;
+	}
 	pub fn duplicate(&self) -> SharedValue {
 		let sv = SharedValue::new_null();
 		sv.v.borrow_mut().copyfrom( &self.v.borrow() );
@@ -228,11 +320,14 @@ impl SharedValue {
 		}
 		if self.is_number() {
 			let v = self.to_number();
-			if v != 0.0 {
+			if v != 0.0 && !v.is_nan() {
 				return true;
 			}
 			return false;
 		}
+		if self.is_string() {
+			return self.to_string() != "";
+		}
 		return true;
 	}
 	pub fn is_number(&self) -> bool {
@@ -249,9 +344,7 @@ impl SharedValue {
 		}
 		if self.is_string() {
 			let s = self.to_string();
-			if let Some(v) = str_to_number(&s) {
-				return v;
-			}			
+			return string_to_number(&s);
 		}
 		if self.is_boolean() {
 			if self.to_boolean() {
@@ -260,6 +353,12 @@ impl SharedValue {
 				return 0.0;
 			}
 		}
+		// the time value of dates, as valueOf
+		if let JsValue::JSObject(ref obj) = *v {
+			if obj.borrow().is_date() {
+				return obj.borrow().get_date();
+			}
+		}
 		return std::f64::NAN;
 	}
 	pub fn is_exception(&self) -> bool {
@@ -269,6 +368,16 @@ impl SharedValue {
 		}
 		return false;
 	}
+	// the payload of host objects, None for other values or payloads of other types
+	pub fn get_host<T: Any>(&self) -> Option<Rc<T>> {
+		let v = self.v.borrow();
+		if let JsValue::JSObject(obj) = &*v {
+			if let JsClass::host(ref h) = obj.borrow().value {
+				return h.payload.clone().downcast::<T>().ok();
+			}
+		}
+		return None;
+	}
 	pub fn type_string(&self) -> String {
 		let v = self.v.borrow();
 		match &*v {
@@ -313,11 +422,14 @@ impl SharedValue {
 				}
 			},
 			JsValue::JSNumber(num) => {
-				return num.to_string();
+				return number_to_string(*num);
 			},
 			JsValue::JSObject(obj) => {
 				if obj.borrow().is_string() {
 					return obj.borrow().get_string();
+				} else if obj.borrow().is_regexp() {
+					let re = obj.borrow().get_regexp();
+					return format!("/{}/{}", re.source, re.flags());
 				} else {
 					return format!("[object:_{}_]", obj.borrow().type_string());
 				}
@@ -371,20 +483,170 @@ impl JsProperty {
 }
 
 impl JsBuiltinFunction {
-	pub fn new(f: fn(&mut JsRuntime), argc: usize) -> Self {
+	pub fn new<F>(f: F, argc: usize) -> Self
+		where F: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static {
 		JsBuiltinFunction {
-			f:		f,
+			f:		Rc::new(f),
 			argc:	argc
 		}
 	}
+	// natives boxed by hosts, they can capture any state
+	pub fn new_boxed(f: Box<JsNativeFunction>, argc: usize) -> Self {
+		JsBuiltinFunction {
+			f:		Rc::from(f),
+			argc:	argc
+		}
+	}
+}
+
+impl JsHostClass {
+	pub fn new(name: &str) -> Self {
+		JsHostClass {
+			name:		name.to_string(),
+			methods:	Vec::new(),
+			accessors:	Vec::new(),
+			finalizer:	None,
+		}
+	}
+	pub fn add_method<F>(&mut self, name: &str, f: F, argc: usize)
+		where F: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static {
+		self.methods.push((name.to_string(), JsBuiltinFunction::new(f, argc)));
+	}
+	// a read-only property
+	pub fn add_getter<G>(&mut self, name: &str, getter: G)
+		where G: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static {
+		self.accessors.push((name.to_string(), Some(JsBuiltinFunction::new(getter, 0)), None));
+	}
+	// the setter takes the new value as the only argument
+	pub fn add_accessor<G, S>(&mut self, name: &str, getter: G, setter: S)
+		where G: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static,
+			S: Fn(&mut JsRuntime, &[SharedValue]) -> Result<SharedValue, JsException> + 'static {
+		self.accessors.push((name.to_string(), Some(JsBuiltinFunction::new(getter, 0)), Some(JsBuiltinFunction::new(setter, 1))));
+	}
+	pub fn set_finalizer<F>(&mut self, f: F) where F: Fn(&dyn Any) + 'static {
+		self.finalizer = Some(Box::new(f));
+	}
+}
+
+impl Drop for JsHostObject {
+	fn drop(&mut self) {
+		if let Some(ref finalizer) = self.class.finalizer {
+			finalizer(&*self.payload);
+		}
+	}
 }
 
 impl JsException {
 	pub fn new(msg: String) -> JsException {
 		JsException{
-			msg: msg
+			value: None,
+			kind: JsErrorType::Error,
+			abort: false,
+			msg: msg,
+			location: String::new(),
+			trace: Vec::new(),
+		}
+	}
+	pub fn new_error(kind: JsErrorType, msg: String) -> JsException {
+		let mut e = JsException::new(msg);
+		e.kind = kind;
+		return e;
+	}
+	pub fn new_type_error(msg: String) -> JsException {
+		return JsException::new_error(JsErrorType::TypeError, msg);
+	}
+	pub fn new_range_error(msg: String) -> JsException {
+		return JsException::new_error(JsErrorType::RangeError, msg);
+	}
+	pub fn new_reference_error(msg: String) -> JsException {
+		return JsException::new_error(JsErrorType::ReferenceError, msg);
+	}
+	pub fn new_syntax_error(msg: String) -> JsException {
+		return JsException::new_error(JsErrorType::SyntaxError, msg);
+	}
+	pub fn new_abort(msg: String) -> JsException {
+		let mut e = JsException::new(msg);
+		e.abort = true;
+		return e;
+	}
+	pub fn new_value(value: SharedValue) -> JsException {
+		let mut e = JsException::new(value.to_string());
+		e.value = Some(value);
+		return e;
+	}
+	// message with the call stack, one frame per line
+	pub fn stack(&self) -> String {
+		let mut result = format!("{}: {}", self.kind.name(), self.msg);
+		for frame in &self.trace {
+			result.push_str("\n    at ");
+			result.push_str(frame);
+		}
+		return result;
+	}
+}
+
+impl JsErrorType {
+	pub fn name(&self) -> &'static str {
+		match self {
+			JsErrorType::Exception => "Exception",
+			JsErrorType::Error => "Error",
+			JsErrorType::TypeError => "TypeError",
+			JsErrorType::RangeError => "RangeError",
+			JsErrorType::ReferenceError => "ReferenceError",
+			JsErrorType::SyntaxError => "SyntaxError",
+		}
+	}
+}
+
+impl JsPropertyMap {
+	pub fn new() -> Self {
+		JsPropertyMap {
+			index: HashMap::new(),
+			entries: Vec::new(),
 		}
 	}
+	pub fn len(&self) -> usize {
+		return self.index.len();
+	}
+	pub fn contains_key(&self, name: &str) -> bool {
+		return self.index.contains_key(name);
+	}
+	pub fn get(&self, name: &str) -> Option<&JsProperty> {
+		let i = *self.index.get(name)?;
+		return self.entries[i].as_ref().map(|e| &e.1);
+	}
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut JsProperty> {
+		let i = *self.index.get(name)?;
+		return self.entries[i].as_mut().map(|e| &mut e.1);
+	}
+	// replace the property in place, or append a new one
+	pub fn insert(&mut self, name: String, prop: JsProperty) {
+		if let Some(i) = self.index.get(&name) {
+			self.entries[*i] = Some((name, prop));
+			return;
+		}
+		self.index.insert(name.clone(), self.entries.len());
+		self.entries.push(Some((name, prop)));
+	}
+	pub fn remove(&mut self, name: &str) -> Option<JsProperty> {
+		let i = self.index.remove(name)?;
+		let r = self.entries[i].take().map(|e| e.1);
+
+		// compact the entries when half of them are deleted
+		if self.entries.len() > 8 && self.index.len() * 2 < self.entries.len() {
+			self.entries.retain(|e| e.is_some());
+			for (i, e) in self.entries.iter().enumerate() {
+				self.index.insert(e.as_ref().unwrap().0.clone(), i);
+			}
+		}
+		return r;
+	}
+	pub fn iter(&self) -> impl Iterator<Item = (&String, &JsProperty)> {
+		return self.entries.iter().filter_map(|e| e.as_ref().map(|e| (&e.0, &e.1)));
+	}
+	pub fn keys(&self) -> impl Iterator<Item = &String> {
+		return self.iter().map(|e| e.0);
+	}
 }
 
 impl JsIterator {
@@ -392,6 +654,11 @@ impl JsIterator {
 		let target = target_.borrow();
 	
 		let mut keys: Vec<String> = Vec::new();
+		if let JsClass::array(ref v) = target.value {
+			for i in 0..v.len() {
+				keys.push(i.to_string());
+			}
+		}
 		for x in (*target).properties.keys() {
 			if target.properties.get(x).unwrap().enumerable() {
 				keys.push(x.to_string());
@@ -417,7 +684,8 @@ impl JsObject {
         JsObject {
 			extensible:	true,
             __proto__: None,
-            properties: HashMap::new(),
+            properties: JsPropertyMap::new(),
+            elements_attr: JS_DEFAULT_ATTR,
             value: JsClass::object,
         }
 	}
@@ -425,25 +693,22 @@ impl JsObject {
         JsObject {
 			extensible:	true,
             __proto__: Some(prototype),
-            properties: HashMap::new(),
+            properties: JsPropertyMap::new(),
+            elements_attr: JS_DEFAULT_ATTR,
             value: value
         }
 	}
 	
-	pub fn new_expand(ptr:*const c_void) -> JsObject {
-		JsObject {
-			extensible:	false,
-			__proto__: None,
-			properties: HashMap::new(),
-			value: JsClass::expand(JsExpander{ptr: ptr}),
-		}
+	pub fn new_host(prototype: SharedObject, class: Rc<JsHostClass>, payload: Rc<dyn Any>) -> JsObject {
+		JsObject::new_with(prototype, JsClass::host(Rc::new(JsHostObject{class: class, payload: payload})))
 	}
 
 	pub fn new_exception(prototype: SharedObject, e: JsException) -> JsObject {		
 		JsObject {
 			extensible:	false,
 			__proto__: Some(prototype),
-			properties: HashMap::new(),
+			properties: JsPropertyMap::new(),
+			elements_attr: JS_DEFAULT_ATTR,
 			value: JsClass::exception(e),
 		}
 	}
@@ -453,7 +718,8 @@ impl JsObject {
 		JsObject {
 			extensible:	false,
 			__proto__: None,
-			properties: HashMap::new(),
+			properties: JsPropertyMap::new(),
+			elements_attr: JS_DEFAULT_ATTR,
 			value: JsClass::iterator(it),
 		}
 	}
@@ -466,20 +732,19 @@ impl JsObject {
 		JsObject {
 			extensible:	false,
 			__proto__: None,
-			properties: HashMap::new(),
+			properties: JsPropertyMap::new(),
+			elements_attr: JS_DEFAULT_ATTR,
 			value: fvalue,
 		}
 	}
 
-	pub fn new_builtin(f: fn(&mut JsRuntime), argc: usize) -> JsObject {
-		let bvalue = JsClass::builtin(JsBuiltinFunction {
-			f: f,
-			argc: argc,
-		});
+	pub fn new_builtin(f: JsBuiltinFunction) -> JsObject {
+		let bvalue = JsClass::builtin(f);
 		JsObject {
 			extensible:	false,
 			__proto__: None,
-			properties: HashMap::new(),
+			properties: JsPropertyMap::new(),
+			elements_attr: JS_DEFAULT_ATTR,
 			value: bvalue,
 		}
 	}
@@ -504,6 +769,9 @@ impl JsObject {
 			JsClass::function(_) => {
 				"function".to_string()
 			},
+			JsClass::bound(_) => {
+				"function".to_string()
+			},
 			_ => {
 				"object".to_string()
 			}
@@ -528,6 +796,18 @@ impl JsObject {
 		}
 		panic!("Object can't be a exception!")
 	}
+	pub fn is_host(&self) -> bool {
+		if let JsClass::host(_) = self.value {
+			return true;
+		}
+		return false;
+	}
+	pub fn get_host(&self) -> &JsHostObject {
+		if let JsClass::host(ref h) = self.value {
+			return h;
+		}
+		panic!("Object can't be a host object!")
+	}
 	pub fn is_iterator(&self) -> bool {
 		if let JsClass::iterator(_) = self.value {
 			return true;
@@ -564,6 +844,34 @@ impl JsObject {
 		}
 		panic!("Object can't be a func!")
 	}
+	pub fn is_bound(&self) -> bool {
+		if let JsClass::bound(_) = self.value {
+			return true;
+		}
+		return false;
+	}
+	pub fn get_bound(&self) -> &JsBoundFunction {
+		if let JsClass::bound(ref func) = self.value {
+			return func;
+		}
+		panic!("Object can't be a bound function!")
+	}
+	// the length and name properties of callable objects
+	pub fn func_length(&self) -> usize {
+		match self.value {
+			JsClass::function(ref f) => f.vmf.numparams,
+			JsClass::builtin(ref b) => if b.argc == JS_VARARGS { 0 } else { b.argc },
+			JsClass::bound(ref b) => b.target.borrow().func_length().saturating_sub(b.args.len()),
+			_ => 0,
+		}
+	}
+	pub fn func_name(&self) -> String {
+		match self.value {
+			JsClass::function(ref f) => f.vmf.name.clone().unwrap_or_default(),
+			JsClass::bound(ref b) => format!("bound {}", b.target.borrow().func_name()),
+			_ => "".to_string(),
+		}
+	}
 	pub fn is_array(&self) -> bool {
 		if let JsClass::array(_) = self.value {
 			return true;
@@ -582,6 +890,30 @@ impl JsObject {
 		}
 		panic!("Object can't be a array!")
 	}
+	pub fn is_regexp(&self) -> bool {
+		if let JsClass::regexp(_) = self.value {
+			return true;
+		}
+		return false;
+	}
+	pub fn get_regexp(&self) -> RegExp {
+		if let JsClass::regexp(ref re) = self.value {
+			return re.clone();
+		}
+		panic!("Object can't be a regexp!")
+	}
+	pub fn is_date(&self) -> bool {
+		if let JsClass::date(_) = self.value {
+			return true;
+		}
+		return false;
+	}
+	pub fn get_date(&self) -> f64 {
+		if let JsClass::date(t) = self.value {
+			return t;
+		}
+		panic!("Object can't be a date!")
+	}
 	pub fn is_string(&self) -> bool {
 		if let JsClass::string(ref _func) = self.value {
 			return true;
@@ -595,7 +927,7 @@ impl JsObject {
 		panic!("Object can't be a string!")
 	}
 	pub fn callable(&self) -> bool {
-		if self.is_function() || self.is_builtin() {
+		if self.is_function() || self.is_builtin() || self.is_bound() {
 			return true;
 		}
 		return false;
@@ -0,0 +1,61 @@
+use ezjs::runtime::*;
+use ezjs::IntoJs;
+
+mod common;
+use common::eval;
+
+#[test]
+fn call_saved_script_functions() {
+	let mut rt = ezjs::new_runtime();
+	eval(&mut rt, "var handlers = []; function on(f) { handlers.push(f); } on(function(a, b) { return this.base + a * b; });").unwrap();
+
+	let handlers = rt.get_global("handlers").unwrap();
+	let f = handlers.get_object().borrow().get_array()[0].clone();
+	let this = eval(&mut rt, "({base: 100})").unwrap();
+	let r = rt.call(&f, &this, &[SharedValue::new_number(3.0), SharedValue::new_number(4.0)]).unwrap();
+	assert_eq!(r.to_number(), 112.0);
+
+	// exceptions are returned with the thrown value, the runtime can be used again
+	let thrower = eval(&mut rt, "(function(m) { throw new RangeError(m); })").unwrap();
+	let msg = "bad".into_js(&mut rt).ok().unwrap();
+	match rt.call(&thrower, &SharedValue::new_undefined(), &[msg]) {
+		Err(ezjs::Error::RuntimeException(v)) => rt.set_global("err", v).unwrap(),
+		_ => panic!("the exception should be returned"),
+	}
+	assert_eq!(eval(&mut rt, "err instanceof RangeError && err.message").unwrap().to_string(), "bad");
+	assert!(rt.call(&SharedValue::new_number(1.0), &SharedValue::new_undefined(), &[]).is_err());
+	assert!(rt.call(&f, &this, &[]).unwrap().to_number().is_nan());
+}
+
+#[test]
+fn call_from_natives() {
+	let mut rt = ezjs::new_runtime();
+	rt.register_function("twice", |rt, args| {
+		let this = rt.this_value();
+		let first = rt.call(&args[0], &this, &[SharedValue::new_number(1.0)]).map_err(|e| JsException::new_type_error(e.to_string()))?;
+		let second = rt.call(&args[0], &this, &[first]).map_err(|e| JsException::new_type_error(e.to_string()))?;
+		return Ok(second);
+	}, 1);
+
+	assert_eq!(eval(&mut rt, "twice(function(x) { return x * 10; }) + 1").unwrap().to_number(), 101.0);
+	let r = eval(&mut rt, "var e; try { twice(function() { throw 1; }); } catch (x) { e = x; } e instanceof TypeError;").unwrap();
+	assert!(r.to_boolean());
+	assert_eq!(eval(&mut rt, "[1, 2].map(function(x) { return twice(function(y) { return y + x; }); }).join()").unwrap().to_string(), "3,5");
+}
+
+#[test]
+fn get_and_set_globals() {
+	let mut rt = ezjs::new_runtime();
+	rt.set_global("answer", SharedValue::new_number(42.0)).unwrap();
+	assert_eq!(eval(&mut rt, "answer + 1").unwrap().to_number(), 43.0);
+
+	eval(&mut rt, "var n = 0; Object.defineProperty(this, 'next', {get: function() { return ++n; }});").unwrap();
+	assert_eq!(rt.get_global("next").unwrap().to_number(), 1.0);
+	assert_eq!(rt.get_global("next").unwrap().to_number(), 2.0);
+	assert!(rt.get_global("missing").unwrap().is_undefined());
+
+	// read-only globals can't be changed by hosts either
+	eval(&mut rt, "Object.defineProperty(this, 'fixed', {value: 1});").unwrap();
+	assert!(rt.set_global("fixed", SharedValue::new_number(2.0)).is_err());
+	assert_eq!(rt.get_global("fixed").unwrap().to_number(), 1.0);
+}
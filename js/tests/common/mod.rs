@@ -0,0 +1,8 @@
+use ezjs::runtime::*;
+
+/* helpers shared by the integration tests, each test file includes them with mod common */
+
+pub fn eval(rt: &mut JsRuntime, code: &str) -> Result<SharedValue, ezjs::Error> {
+	let vmf = ezjs::build_function_from_code(code).unwrap();
+	return ezjs::run_script(rt, vmf);
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use ezjs::runtime::*;
+use ezjs::{FromJs, IntoJs};
+
+mod common;
+use common::eval;
+
+fn from_js<T: FromJs>(rt: &mut JsRuntime, code: &str) -> Result<T, JsException> {
+	let v = eval(rt, code).unwrap();
+	return T::from_js(rt, &v);
+}
+
+#[test]
+fn convert_values_from_js() {
+	let mut rt = ezjs::new_runtime();
+	assert_eq!(from_js::<f64>(&mut rt, "1.5").ok(), Some(1.5));
+	assert_eq!(from_js::<i32>(&mut rt, "-7.9").ok(), Some(-7));
+	assert_eq!(from_js::<bool>(&mut rt, "'x'").ok(), Some(true));
+	assert_eq!(from_js::<String>(&mut rt, "12.5").ok(), Some("12.5".to_string()));
+	assert_eq!(from_js::<Option<String>>(&mut rt, "null").ok(), Some(None));
+	assert_eq!(from_js::<Vec<u8>>(&mut rt, "[1, 2, 3]").ok(), Some(vec![1, 2, 3]));
+
+	let map = from_js::<HashMap<String, Vec<String>>>(&mut rt, "({a: ['x'], b: []})").ok().unwrap();
+	assert_eq!(map.len(), 2);
+	assert_eq!(map["a"], vec!["x".to_string()]);
+
+	// numbers out of range and values of wrong types are errors
+	assert!(from_js::<u8>(&mut rt, "256").is_err());
+	assert!(from_js::<i32>(&mut rt, "NaN").is_err());
+	assert!(from_js::<Vec<f64>>(&mut rt, "'1,2'").is_err());
+	assert!(from_js::<HashMap<String, f64>>(&mut rt, "1").is_err());
+}
+
+#[test]
+fn convert_values_into_js() {
+	let mut rt = ezjs::new_runtime();
+	rt.register_typed_function("items", || vec![Some("a"), None]);
+	rt.register_typed_function("counts", || {
+		let mut map = HashMap::new();
+		map.insert("n".to_string(), 42u64);
+		return map;
+	});
+	assert!(eval(&mut rt, "var v = items(); v instanceof Array && v.length == 2 && v[0] == 'a' && v[1] === null").unwrap().to_boolean());
+	assert!(eval(&mut rt, "var m = counts(); m.n === 42 && Object.keys(m).length == 1").unwrap().to_boolean());
+	assert_eq!(eval(&mut rt, "JSON.stringify(counts())").unwrap().to_string(), "{\"n\":42}");
+
+	let v = vec![1u8, 2].into_js(&mut rt).ok().unwrap();
+	assert_eq!(Vec::<f64>::from_js(&mut rt, &v).ok(), Some(vec![1.0, 2.0]));
+}
+
+#[test]
+fn typed_functions() {
+	let mut rt = ezjs::new_runtime();
+	rt.register_typed_function("add", |a: f64, b: f64| a + b);
+	rt.register_typed_function("repeat", |s: String, n: usize| s.repeat(n));
+	rt.register_typed_function("greet", |name: Option<String>| format!("hello {}", name.unwrap_or("world".to_string())));
+	rt.register_typed_function("sum", |v: Vec<i64>| v.iter().sum::<i64>());
+	rt.register_typed_function("parse", |s: String| -> Result<f64, JsException> {
+		return s.parse::<f64>().map_err(|_| JsException::new_syntax_error(format!("bad number {}", s)));
+	});
+
+	assert_eq!(eval(&mut rt, "add(1, 2)").unwrap().to_number(), 3.0);
+	assert_eq!(eval(&mut rt, "repeat('ab', 3)").unwrap().to_string(), "ababab");
+	assert_eq!(eval(&mut rt, "greet() + ', ' + greet('js')").unwrap().to_string(), "hello world, hello js");
+	assert_eq!(eval(&mut rt, "sum([1, 2, 3])").unwrap().to_number(), 6.0);
+	assert_eq!(eval(&mut rt, "add.length").unwrap().to_number(), 2.0);
+
+	// conversion errors and errors of the function are thrown to scripts
+	let r = eval(&mut rt, "var e; try { repeat('a', -1); } catch (x) { e = x; } e instanceof RangeError;").unwrap();
+	assert!(r.to_boolean());
+	let r = eval(&mut rt, "var e; try { parse('x'); } catch (x) { e = x; } e instanceof SyntaxError && e.message;").unwrap();
+	assert_eq!(r.to_string(), "bad number x");
+	assert_eq!(eval(&mut rt, "parse('2.5')").unwrap().to_number(), 2.5);
+}
@@ -0,0 +1,100 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use ezjs::runtime::*;
+use ezjs::VMFunction;
+
+mod common;
+use common::eval;
+
+const SCRIPT: &str = "function add(a, b) {
+	var c = a + b;
+	return c;
+}
+var x = add(1, 2);
+x = add(x, 3);
+x;";
+
+type Stops = Rc<RefCell<Vec<(String, u32)>>>;
+
+// records the lines it stops at and answers with the next action of the plan
+struct Recorder {
+	stops:	Stops,
+	plan:	Vec<DebugAction>,
+}
+
+impl Debugger for Recorder {
+	fn on_debug(&mut self, vmf: &VMFunction, pc: usize, _stack: &[SharedValue], _env: &SharedScope) -> DebugAction {
+		let name = vmf.name.clone().unwrap_or("<script>".to_string());
+		self.stops.borrow_mut().push((name, vmf.line(pc)));
+		if self.plan.is_empty() {
+			return DebugAction::Continue;
+		}
+		return self.plan.remove(0);
+	}
+}
+
+fn debug_runtime(plan: Vec<DebugAction>) -> (JsRuntime, Stops) {
+	let stops = Rc::new(RefCell::new(Vec::new()));
+	let mut rt = ezjs::new_runtime();
+	rt.set_debugger(Box::new(Recorder{stops: stops.clone(), plan}));
+	return (rt, stops);
+}
+
+fn stop(name: &str, line: u32) -> (String, u32) {
+	return (name.to_string(), line);
+}
+
+#[test]
+fn breakpoints_in_nested_functions() {
+	let (mut rt, stops) = debug_runtime(Vec::new());
+	let vmf = ezjs::build_function_from_code(SCRIPT).unwrap();
+	let add = vmf.find_function("add").unwrap();
+	rt.set_breakpoint(&add, 3);
+	rt.set_breakpoint(&vmf, 6);
+
+	assert_eq!(ezjs::run_script(&mut rt, vmf.clone()).unwrap().to_number(), 6.0);
+	assert_eq!(*stops.borrow(), vec![stop("add", 3), stop("<script>", 6), stop("add", 3)]);
+
+	stops.borrow_mut().clear();
+	rt.clear_breakpoint(&add, 3);
+	ezjs::run_script(&mut rt, vmf.clone()).unwrap();
+	assert_eq!(*stops.borrow(), vec![stop("<script>", 6)]);
+
+	stops.borrow_mut().clear();
+	rt.clear_breakpoints();
+	ezjs::run_script(&mut rt, vmf).unwrap();
+	assert!(stops.borrow().is_empty());
+}
+
+#[test]
+fn step_line_stops_at_each_line() {
+	let (mut rt, stops) = debug_runtime(vec![DebugAction::StepLine, DebugAction::StepLine, DebugAction::StepLine]);
+	let vmf = ezjs::build_function_from_code(SCRIPT).unwrap();
+	let add = vmf.find_function("add").unwrap();
+	rt.set_breakpoint(&add, 2);
+
+	assert_eq!(ezjs::run_script(&mut rt, vmf).unwrap().to_number(), 6.0);
+	// returning from add doesn't start a new line, stepping goes on to the next call
+	assert_eq!(*stops.borrow(), vec![stop("add", 2), stop("add", 3), stop("<script>", 6), stop("add", 2)]);
+}
+
+#[test]
+fn debugger_aborts_scripts() {
+	let (mut rt, stops) = debug_runtime(vec![DebugAction::Abort]);
+	let vmf = ezjs::build_function_from_code(SCRIPT).unwrap();
+	let add = vmf.find_function("add").unwrap();
+	ezjs::run_script(&mut rt, vmf.clone()).unwrap();
+	rt.set_breakpoint(&add, 2);
+
+	// scripts can't catch the abort
+	match eval(&mut rt, "try { add(1, 1); } catch (e) { 'caught'; }") {
+		Err(ezjs::Error::Aborted(_)) => {},
+		_ => panic!("the script should be aborted"),
+	}
+	assert_eq!(*stops.borrow(), vec![stop("add", 2)]);
+
+	// the runtime keeps working after the abort
+	rt.clear_breakpoints();
+	assert_eq!(ezjs::run_script(&mut rt, vmf).unwrap().to_number(), 6.0);
+}
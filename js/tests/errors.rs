@@ -0,0 +1,82 @@
+use ezjs::Error;
+
+// the line and column of the syntax error, columns count characters from 1
+fn syntax_error_at(script: &str) -> (u32, u32) {
+	match ezjs::build_function_from_file("test.js", script) {
+		Err(Error::SyntaxError{line, column, ..}) => return (line, column),
+		Err(e) => panic!("expected a syntax error, got {}", e),
+		Ok(_) => panic!("expected a syntax error in {:?}", script),
+	}
+}
+
+#[test]
+fn syntax_errors_have_line_and_column() {
+	assert_eq!(syntax_error_at("var a = 1;\nvar b = a +;\n"), (2, 12));
+	assert_eq!(syntax_error_at("function f() {\n  return 1;\n  var = 2;\n}"), (3, 7));
+	assert_eq!(syntax_error_at("var o = {a: 1,\n\tb 2};"), (2, 4));
+	assert_eq!(syntax_error_at("x = [1, 2\n  3];"), (2, 3));
+	assert_eq!(syntax_error_at("\n\n    )"), (3, 5));
+	// unterminated source is reported where it ends
+	assert_eq!(syntax_error_at("if (x) {\n  y = 1;\n"), (3, 1));
+	assert_eq!(syntax_error_at("var s = 'abc\nx"), (1, 9));
+
+	let e = ezjs::build_function_from_code("var b = 1 +;").err().unwrap();
+	assert!(e.to_string().starts_with("SyntaxError: ") && e.to_string().ends_with("(line 1, column 12)"));
+}
+
+fn compile_error_at(script: &str) -> (String, u32) {
+	match ezjs::build_function_from_file("test.js", script) {
+		Err(Error::CompileError{msg, line}) => return (msg, line),
+		Err(e) => panic!("expected a compile error, got {}", e),
+		Ok(_) => panic!("expected a compile error in {:?}", script),
+	}
+}
+
+#[test]
+fn compile_errors_have_line() {
+	let (msg, line) = compile_error_at("var x;\n\nx++ = 3;");
+	assert_eq!((msg.as_str(), line), ("invalid l-value in assignment", 3));
+	assert_eq!(compile_error_at("1 = 2;").1, 1);
+
+	// the line of the second default, not of the end of the switch
+	let (msg, line) = compile_error_at("switch (x) {\ncase 1: break;\ndefault: break;\ndefault:\n  x = 1;\n}");
+	assert_eq!((msg.as_str(), line), ("more than one default label in switch", 4));
+
+	let (msg, line) = compile_error_at("a:\nwhile (1) {\n  break b;\n}");
+	assert_eq!((msg.as_str(), line), ("Can't find break target!", 3));
+	assert_eq!(compile_error_at("var a;\n\nbreak;").1, 3);
+	assert_eq!(compile_error_at("for (;;) {\n  continue foo;\n}").1, 2);
+
+	let (msg, line) = compile_error_at("function f() {\n  return 1;\n}\nreturn f();");
+	assert_eq!((msg.as_str(), line), ("Find return in script code!", 4));
+
+	let e = ezjs::build_function_from_code("var a;\nbreak;").err().unwrap();
+	assert_eq!(e.to_string(), "CompileError: Can't find break target! (line 2)");
+}
+
+const TRACE_SCRIPT: &str = "function inner(x) {
+	if (x) throw new Error('boom ' + x);
+	return x.y;
+}
+function outer(x) {
+	var r = inner(x);
+	return r;
+}
+";
+
+#[test]
+fn exceptions_trace_the_calls() {
+	let mut rt = ezjs::new_runtime();
+	let vmf = ezjs::build_function_from_file("trace.js", TRACE_SCRIPT).unwrap();
+	ezjs::run_script(&mut rt, vmf).unwrap();
+
+	// thrown by the script two calls deep
+	let vmf = ezjs::build_function_from_file("main.js", "var a = 1;\nouter(a);").unwrap();
+	let e = ezjs::run_script(&mut rt, vmf).err().unwrap();
+	assert_eq!(e.to_string(), "Error: boom 1\n    at inner (trace.js:2)\n    at outer (trace.js:6)\n    at <script> (main.js:2)");
+
+	// thrown by the vm, scripts see the same trace
+	let vmf = ezjs::build_function_from_file("main.js", "var s;\ntry {\n  outer(null);\n} catch (e) {\n  s = e.stack;\n}\ns;").unwrap();
+	let s = ezjs::run_script(&mut rt, vmf).unwrap().to_string();
+	assert_eq!(s, "TypeError: Access none objects's property!\n    at inner (trace.js:3)\n    at outer (trace.js:6)\n    at <script> (main.js:3)");
+}
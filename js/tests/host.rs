@@ -0,0 +1,106 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use ezjs::runtime::*;
+
+mod common;
+use common::eval;
+
+struct Point {
+	x:	Cell<f64>,
+	y:	Cell<f64>,
+	tag:	RefCell<String>,
+}
+
+fn point_class(finalized: Rc<RefCell<Vec<f64>>>) -> JsHostClass {
+	let mut class = JsHostClass::new("Point");
+	class.add_method("length", |rt, _args| {
+		let p = rt.this_value().get_host::<Point>().unwrap();
+		return Ok(SharedValue::new_number((p.x.get() * p.x.get() + p.y.get() * p.y.get()).sqrt()));
+	}, 0);
+	class.add_method("moveBy", |rt, args| {
+		let p = rt.this_value().get_host::<Point>().unwrap();
+		p.x.set(p.x.get() + args[0].to_number());
+		p.y.set(p.y.get() + args[1].to_number());
+		return Ok(SharedValue::new_undefined());
+	}, 2);
+	class.add_accessor("x", |rt, _args| {
+		let p = rt.this_value().get_host::<Point>().unwrap();
+		return Ok(SharedValue::new_number(p.x.get()));
+	}, |rt, args| {
+		let p = rt.this_value().get_host::<Point>().unwrap();
+		p.x.set(args[0].to_number());
+		return Ok(SharedValue::new_undefined());
+	});
+	class.add_getter("tag", |rt, _args| {
+		let p = rt.this_value().get_host::<Point>().unwrap();
+		let tag = p.tag.borrow().clone();
+		return ezjs::IntoJs::into_js(tag, rt);
+	});
+	class.set_finalizer(move |payload| {
+		if let Some(p) = payload.downcast_ref::<Point>() {
+			finalized.borrow_mut().push(p.x.get());
+		}
+	});
+	return class;
+}
+
+fn new_point(rt: &mut JsRuntime, t: &JsHostType, x: f64, y: f64) -> SharedValue {
+	let p = Point {
+		x:		Cell::new(x),
+		y:		Cell::new(y),
+		tag:	RefCell::new("p".to_string()),
+	};
+	return rt.new_host_object(t, Rc::new(p)).ok().unwrap();
+}
+
+#[test]
+fn host_methods_and_accessors() {
+	let finalized = Rc::new(RefCell::new(Vec::new()));
+	let mut rt = ezjs::new_runtime();
+	let point_type = rt.new_host_class(point_class(finalized));
+	let p = new_point(&mut rt, &point_type, 3.0, 4.0);
+	rt.set_global("p", p.clone()).unwrap();
+
+	assert_eq!(eval(&mut rt, "p.length()").unwrap().to_number(), 5.0);
+	eval(&mut rt, "p.moveBy(1, 2); p.x = p.x * 10;").unwrap();
+	let point = p.get_host::<Point>().unwrap();
+	assert_eq!(point.x.get(), 40.0);
+	assert_eq!(point.y.get(), 6.0);
+	assert_eq!(eval(&mut rt, "p.tag").unwrap().to_string(), "p");
+
+	// getters without setter are read-only, the prototype can't be changed by scripts
+	assert!(eval(&mut rt, "p.tag = 'q';").is_err());
+	assert!(eval(&mut rt, "Object.getPrototypeOf(p).length = null;").is_err());
+	assert_eq!(eval(&mut rt, "Object.prototype.toString.call(p)").unwrap().to_string(), "[object Point]");
+	assert!(p.get_host::<String>().is_none());
+}
+
+#[test]
+fn host_methods_check_this() {
+	let finalized = Rc::new(RefCell::new(Vec::new()));
+	let mut rt = ezjs::new_runtime();
+	let point_type = rt.new_host_class(point_class(finalized));
+	let p = new_point(&mut rt, &point_type, 3.0, 4.0);
+	rt.set_global("p", p).unwrap();
+
+	let r = eval(&mut rt, "var e; try { p.length.call({}); } catch (x) { e = x; } e instanceof TypeError && e.message;").unwrap();
+	assert_eq!(r.to_string(), "Point.length called on incompatible object");
+	let r = eval(&mut rt, "var e; try { Object.getOwnPropertyDescriptor(Object.getPrototypeOf(p), 'x').get.call(1); } catch (x) { e = x; } e instanceof TypeError;").unwrap();
+	assert!(r.to_boolean());
+}
+
+#[test]
+fn host_finalizer_runs_when_dropped() {
+	let finalized = Rc::new(RefCell::new(Vec::new()));
+	let mut rt = ezjs::new_runtime();
+	let point_type = rt.new_host_class(point_class(finalized.clone()));
+	let p = new_point(&mut rt, &point_type, 1.0, 0.0);
+	rt.set_global("p", p).unwrap();
+	let q = new_point(&mut rt, &point_type, 2.0, 0.0);
+	drop(q);
+	assert_eq!(*finalized.borrow(), vec![2.0]);
+
+	eval(&mut rt, "p = null;").unwrap();
+	assert_eq!(*finalized.borrow(), vec![2.0, 1.0]);
+}
@@ -0,0 +1,102 @@
+use ezjs::runtime::*;
+
+mod common;
+use common::eval;
+
+fn is_aborted(r: Result<SharedValue, ezjs::Error>) -> bool {
+	match r {
+		Err(ezjs::Error::Aborted(_)) => return true,
+		_ => return false,
+	}
+}
+
+#[test]
+fn opcode_limit_aborts_scripts() {
+	let mut rt = ezjs::new_runtime();
+	rt.set_limits(JsLimits{max_opcodes: 10000, ..JsLimits::default()});
+
+	// scripts can't catch the abort
+	assert!(is_aborted(eval(&mut rt, "try { while (true) {} } catch (e) {} 'done';")));
+	// the count starts again for each script
+	for _ in 0..3 {
+		assert_eq!(eval(&mut rt, "var n = 0; for (var i = 0; i < 100; i++) { n += i; } n;").unwrap().to_number(), 4950.0);
+	}
+}
+
+#[test]
+fn depth_and_stack_limits() {
+	let mut rt = ezjs::new_runtime();
+	eval(&mut rt, "function depth(n) { if (n == 0) return 0; return 1 + depth(n - 1); }").unwrap();
+
+	// max_depth gives the same bound in every build, scripts can catch the error
+	rt.set_limits(JsLimits{max_depth: 20, ..JsLimits::default()});
+	assert_eq!(eval(&mut rt, "depth(10)").unwrap().to_number(), 10.0);
+	let r = eval(&mut rt, "var e; try { depth(30); } catch (x) { e = x; } e instanceof RangeError && e.message;").unwrap();
+	assert_eq!(r.to_string(), "Maximum call stack size exceeded");
+	// the script is a level too
+	assert_eq!(eval(&mut rt, "depth(18)").unwrap().to_number(), 18.0);
+	assert!(eval(&mut rt, "depth(19)").is_err());
+
+	rt.set_limits(JsLimits{max_depth: 0, max_stack: 40, ..JsLimits::default()});
+	assert_eq!(eval(&mut rt, "depth(2)").unwrap().to_number(), 2.0);
+	assert!(is_aborted(eval(&mut rt, "depth(30)")));
+	rt.set_limits(JsLimits::default());
+	assert_eq!(eval(&mut rt, "depth(30)").unwrap().to_number(), 30.0);
+}
+
+// the outcome is the same in debug and release builds, only the depth reached differs
+#[test]
+fn default_limits_stop_runaway_recursion() {
+	let mut rt = ezjs::new_runtime();
+	let r = eval(&mut rt, "function f() { return f(); } var e; try { f(); } catch (x) { e = x; } e instanceof RangeError && e.message;").unwrap();
+	assert_eq!(r.to_string(), "Maximum call stack size exceeded");
+	assert_eq!(eval(&mut rt, "[1, 2, 3].map(function(x) { return x * 2; }).join()").unwrap().to_string(), "2,4,6");
+
+	// natives calling scripts calling natives are nesting levels too
+	let r = eval(&mut rt, "function g() { return [1].map(g); } var e; try { g(); } catch (x) { e = x; } e instanceof RangeError;").unwrap();
+	assert!(r.to_boolean());
+	let r = eval(&mut rt, "var a = []; for (var i = 0; i < 10000; i++) { a = [a]; } try { String(a); } catch (x) { e = x; } e instanceof RangeError;");
+	assert!(r.unwrap().to_boolean());
+	assert_eq!(rt.depth, 0);
+}
+
+#[test]
+fn deep_json_is_an_error() {
+	let mut rt = ezjs::new_runtime();
+	let text = "[".repeat(100000);
+	match ezjs::parse_json(&mut rt, &text) {
+		Err(ezjs::Error::RuntimeException(v)) => rt.set_global("err", v).unwrap(),
+		_ => panic!("deep JSON text should be rejected"),
+	}
+	assert!(eval(&mut rt, "err instanceof SyntaxError").unwrap().to_boolean());
+	assert!(ezjs::parse_json(&mut rt, "[[[1]]]").is_ok());
+
+	// stringifying counts nesting levels like calls do
+	let deep = eval(&mut rt, "var a = []; for (var i = 0; i < 100; i++) { a = [a]; } a;").unwrap();
+	rt.set_limits(JsLimits{max_depth: 50, ..JsLimits::default()});
+	assert!(ezjs::to_json(&mut rt, deep.clone()).is_err());
+	rt.set_limits(JsLimits::default());
+	assert_eq!(ezjs::to_json(&mut rt, deep).unwrap().unwrap().len(), 202);
+	assert_eq!(rt.depth, 0);
+}
+
+// parsing deeply nested source is an error in every build, never a stack overflow
+#[test]
+fn deep_source_is_a_syntax_error() {
+	let sources = ["(".repeat(100000), "[".repeat(100000), "{".repeat(100000), "!".repeat(100000) + "x", "x+".repeat(100000) + "x", "if (x) ".repeat(100000)];
+	for src in sources.iter() {
+		match ezjs::build_function_from_code(src) {
+			Err(ezjs::Error::SyntaxError{msg, line, ..}) => assert_eq!((msg.as_str(), line), ("too deeply nested", 1)),
+			_ => panic!("deep source should be a syntax error"),
+		}
+	}
+	// statement lists aren't nested
+	assert!(ezjs::build_function_from_code(&"x = 1;\n".repeat(100000)).is_ok());
+
+	// eval of deep source from the deepest call, the parser keeps within the runtime's stack limit
+	let mut rt = ezjs::new_runtime();
+	eval(&mut rt, "var d = ''; for (var i = 0; i < 1000; i++) { d = d + '(function() { return '; }").unwrap();
+	let r = eval(&mut rt, "var r; function f() { try { return f(); } catch (e) { try { eval(d); } catch (x) { r = x; } } } f(); r instanceof SyntaxError && r.message;");
+	assert!(r.unwrap().to_string().starts_with("too deeply nested"));
+	assert_eq!(rt.depth, 0);
+}
@@ -0,0 +1,68 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use ezjs::runtime::*;
+
+mod common;
+use common::eval;
+
+#[test]
+fn native_closures_capture_host_state() {
+	let mut rt = ezjs::new_runtime();
+	let calls = Rc::new(Cell::new(0));
+	let log = Rc::new(RefCell::new(Vec::new()));
+
+	let counter = calls.clone();
+	rt.register_function("tick", move |_rt, _args| {
+		counter.set(counter.get() + 1);
+		return Ok(SharedValue::new_number(counter.get() as f64));
+	}, 0);
+	let lines = log.clone();
+	rt.register_function("log", move |_rt, args| {
+		lines.borrow_mut().push(args[0].to_string());
+		return Ok(SharedValue::new_undefined());
+	}, 1);
+
+	let r = eval(&mut rt, "for (var i = 0; i < 3; i++) { log('line ' + tick()); } tick();").unwrap();
+	assert_eq!(r.to_number(), 4.0);
+	assert_eq!(calls.get(), 4);
+	assert_eq!(*log.borrow(), vec!["line 1", "line 2", "line 3"]);
+}
+
+#[test]
+fn native_arguments_and_this() {
+	let mut rt = ezjs::new_runtime();
+	rt.register_function("argc", |_rt, args| {
+		// missing arguments are undefined, extra ones are kept
+		let defined = args.iter().filter(|v| !v.is_undefined()).count();
+		return Ok(SharedValue::new_number(defined as f64));
+	}, 2);
+	rt.register_boxed_function("self", Box::new(|rt, _args| {
+		return Ok(rt.this_value());
+	}), 0);
+
+	assert_eq!(eval(&mut rt, "argc()").unwrap().to_number(), 0.0);
+	assert_eq!(eval(&mut rt, "argc(1, 2, 3)").unwrap().to_number(), 3.0);
+	assert!(eval(&mut rt, "var o = {f: self}; o.f() === o").unwrap().to_boolean());
+}
+
+#[test]
+fn native_errors_are_thrown_to_scripts() {
+	let mut rt = ezjs::new_runtime();
+	rt.register_function("check", |_rt, args| {
+		if !args[0].is_number() {
+			return Err(JsException::new_type_error("check needs a number".to_string()));
+		}
+		return Ok(args[0].clone());
+	}, 1);
+
+	let r = eval(&mut rt, "var e; try { check('x'); } catch (x) { e = x; } e instanceof TypeError && e.message;").unwrap();
+	assert_eq!(r.to_string(), "check needs a number");
+
+	match eval(&mut rt, "check(1); check(null);") {
+		Err(ezjs::Error::RuntimeException(_)) => {},
+		_ => panic!("uncaught native error should be a runtime exception"),
+	}
+	// the runtime is usable after the uncaught error
+	assert_eq!(eval(&mut rt, "check(2)").unwrap().to_number(), 2.0);
+}
@@ -0,0 +1,112 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use ezjs::runtime::*;
+
+mod common;
+use common::eval;
+
+struct Counter {
+	n:	Cell<i32>,
+}
+
+fn counter_class(finalized: Rc<Cell<usize>>) -> JsHostClass {
+	let mut class = JsHostClass::new("Counter");
+	class.add_method("next", |rt, _args| {
+		let counter = rt.this_value().get_host::<Counter>().unwrap();
+		counter.n.set(counter.n.get() + 1);
+		return Ok(SharedValue::new_number(counter.n.get() as f64));
+	}, 0);
+	class.set_finalizer(move |_payload| {
+		finalized.set(finalized.get() + 1);
+	});
+	return class;
+}
+
+#[test]
+fn realm_globals_are_isolated() {
+	let mut template = ezjs::new_runtime();
+	eval(&mut template, "var config = {verbose: false}; var n = 0; function inc() { return ++n; }").unwrap();
+
+	let mut realm = ezjs::new_realm(&template);
+	eval(&mut realm, "config.verbose = true; config.extra = 1; inc(); inc(); var local = 1;").unwrap();
+	assert_eq!(eval(&mut realm, "n").unwrap().to_number(), 2.0);
+	assert!(eval(&mut realm, "config.verbose").unwrap().to_boolean());
+
+	assert_eq!(eval(&mut template, "n").unwrap().to_number(), 0.0);
+	assert_eq!(eval(&mut template, "inc()").unwrap().to_number(), 1.0);
+	assert!(!eval(&mut template, "config.verbose").unwrap().to_boolean());
+	assert!(eval(&mut template, "typeof config.extra == 'undefined' && typeof local == 'undefined'").unwrap().to_boolean());
+
+	let mut other = ezjs::new_realm(&template);
+	assert!(eval(&mut other, "typeof local == 'undefined' && n == 1").unwrap().to_boolean());
+}
+
+#[test]
+fn realm_shares_host_objects() {
+	let finalized = Rc::new(Cell::new(0));
+	{
+		let mut template = ezjs::new_runtime();
+		let counter_type = template.new_host_class(counter_class(finalized.clone()));
+		let counter = template.new_host_object(&counter_type, Rc::new(Counter{n: Cell::new(0)})).ok().unwrap();
+		template.set_global("counter", counter).unwrap();
+
+		let mut realm = ezjs::new_realm(&template);
+		assert_eq!(eval(&mut realm, "counter.next()").unwrap().to_number(), 1.0);
+		assert_eq!(eval(&mut template, "counter.next()").unwrap().to_number(), 2.0);
+		drop(realm);
+		assert_eq!(finalized.get(), 0);
+	}
+	// the payload is finalized once, not once per realm
+	assert_eq!(finalized.get(), 1);
+}
+
+#[test]
+fn realm_host_types_use_realm_prototypes() {
+	let finalized = Rc::new(Cell::new(0));
+	let mut template = ezjs::new_runtime();
+	let counter_type = template.new_host_class(counter_class(finalized.clone()));
+	let first = template.new_host_object(&counter_type, Rc::new(Counter{n: Cell::new(0)})).ok().unwrap();
+	template.set_global("first", first).unwrap();
+
+	let mut realm = ezjs::new_realm(&template);
+	let second = realm.new_host_object(&counter_type, Rc::new(Counter{n: Cell::new(10)})).ok().unwrap();
+	realm.set_global("second", second).unwrap();
+	assert!(eval(&mut realm, "Object.getPrototypeOf(first) === Object.getPrototypeOf(second)").unwrap().to_boolean());
+	assert_eq!(eval(&mut realm, "second.next()").unwrap().to_number(), 11.0);
+
+	// prototypes of the template aren't reachable from the realm
+	let proto = eval(&mut template, "Object.getPrototypeOf(first)").unwrap();
+	realm.set_global("template_proto", proto).unwrap();
+	assert!(!eval(&mut realm, "Object.getPrototypeOf(second) === template_proto").unwrap().to_boolean());
+
+	// types of unrelated runtimes are rejected
+	let mut other = ezjs::new_runtime();
+	assert!(other.new_host_object(&counter_type, Rc::new(Counter{n: Cell::new(0)})).is_err());
+}
+
+#[test]
+fn realm_eval_runs_in_its_own_realm() {
+	let mut template = ezjs::new_runtime();
+	eval(&mut template, "var name = 'template';").unwrap();
+	let mut realm = ezjs::new_realm(&template);
+	eval(&mut realm, "name = 'realm';").unwrap();
+	assert_eq!(eval(&mut realm, "var e = eval; e('name');").unwrap().to_string(), "realm");
+
+	// eval taken from another realm is indirect eval in that realm's global scope
+	let other_eval = realm.get_global("eval").unwrap();
+	template.set_global("other_eval", other_eval).unwrap();
+	assert_eq!(eval(&mut template, "other_eval('name')").unwrap().to_string(), "realm");
+	assert_eq!(eval(&mut template, "other_eval('var added = 1; name + 1;')").unwrap().to_string(), "realm1");
+	assert!(eval(&mut template, "typeof added == 'undefined'").unwrap().to_boolean());
+	assert_eq!(eval(&mut realm, "added").unwrap().to_number(), 1.0);
+	assert_eq!(eval(&mut template, "other_eval(2)").unwrap().to_number(), 2.0);
+	let r = eval(&mut template, "var err; try { other_eval('1 +'); } catch (x) { err = x; } err instanceof SyntaxError;");
+	assert!(r.unwrap().to_boolean());
+
+	// the template's eval in the realm runs in the template
+	let template_eval = template.get_global("eval").unwrap();
+	realm.set_global("template_eval", template_eval).unwrap();
+	assert_eq!(eval(&mut realm, "template_eval('name')").unwrap().to_string(), "template");
+	assert_eq!(eval(&mut realm, "eval('name')").unwrap().to_string(), "realm");
+}
@@ -0,0 +1,143 @@
+function test_array_basic()
+{
+    var a = [1, 2, 3];
+    assert(a.length == 3, "length of literal");
+    a.push(4, 5);
+    assert(a.length == 5 && a[4] == 5, "push");
+    assert(a.pop() == 5 && a.length == 4, "pop");
+    assert(a.shift() == 1 && a[0] == 2, "shift");
+    assert(a.unshift(0, 1) == 5 && a[0] == 0 && a[1] == 1, "unshift");
+
+    a = [];
+    a[3] = "x";
+    assert(a.length == 4 && a[0] === undefined, "assign extends length");
+    a.length = 1;
+    assert(a.length == 1 && a[3] === undefined, "truncate length");
+    assert(!(3 in a) && (0 in a), "in operator");
+
+    var v = 1;
+    a = [v, v];
+    v = 2;
+    assert(a[0] == 1 && a[1] == 1, "elements are copied");
+
+    a = [];
+    a[0] = a;
+    a.push(a);
+    a.unshift(a);
+    a.splice(1, 0, a);
+    assert(a.length == 4 && a[0] === a && a[3] === a, "array contains itself");
+    Object.defineProperty(a, "4", {value: a});
+    assert(a[4] === a, "array defined to contain itself");
+
+    assert(Array.isArray([]) && !Array.isArray({}), "isArray");
+    assert(new Array(3).length == 3, "Array with length");
+    a = Array(1, 2);
+    assert(a.length == 2 && a[1] == 2, "Array with elements");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_array_methods()
+{
+    var a = [1, 2, 3, 4];
+    assert(a.join() == "1,2,3,4", "join");
+    assert(a.join("-") == "1-2-3-4", "join with separator");
+    assert(a.toString() == "1,2,3,4" && String([1, [2, 3]]) == "1,2,3", "toString");
+    assert([1, null, undefined, 2].join() == "1,,,2", "join empty elements");
+
+    var c = [1, 2];
+    c.push(c);
+    assert(c.join() == "1,2," && String(c) == "1,2,", "join cyclic array");
+    var d = [c, [c]];
+    assert(d.join("-") == "1,2,-1,2,", "join shared cyclic array");
+    var deep = [];
+    for (var i = 0; i < 10000; i++) {
+        deep = [deep];
+    }
+    var e;
+    try { deep.join(); } catch (x) { e = x; }
+    assert(e instanceof RangeError, "join deeply nested arrays");
+    assert(c.join() == "1,2,", "join after error");
+    assert(a.slice(1, 3).join() == "2,3", "slice");
+    assert(a.slice(-2).join() == "3,4", "slice negative");
+    assert(a.concat([5, 6], 7).join() == "1,2,3,4,5,6,7", "concat");
+    assert(a.length == 4, "concat keeps array");
+
+    var r = a.splice(1, 2, "a", "b", "c");
+    assert(r.join() == "2,3", "splice removed");
+    assert(a.join() == "1,a,b,c,4", "splice inserted");
+    a.splice(1, 3);
+    assert(a.join() == "1,4", "splice delete");
+
+    a = [1, 2, 3];
+    assert(a.reverse() === a && a.join() == "3,2,1", "reverse");
+    assert([1, 2, 1].indexOf(1) == 0 && [1, 2, 1].lastIndexOf(1) == 2, "indexOf");
+    assert([1, 2, 1].indexOf(1, 1) == 2, "indexOf from");
+    assert([1, 2].indexOf("1") == -1, "indexOf is strict");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_array_sort()
+{
+    var a = [3, 10, 1, 2];
+    a.sort();
+    assert(a.join() == "1,10,2,3", "default sort by string");
+    a.sort(function(x, y) { return x - y; });
+    assert(a.join() == "1,2,3,10", "sort with comparator");
+    a.sort(function(x, y) { return y - x; });
+    assert(a.join() == "10,3,2,1", "sort descending");
+
+    a = [{k: 1, v: "a"}, {k: 0, v: "b"}, {k: 1, v: "c"}, {k: 0, v: "d"}];
+    a.sort(function(x, y) { return x.k - y.k; });
+    assert(a[0].v == "b" && a[1].v == "d" && a[2].v == "a" && a[3].v == "c", "sort is stable");
+
+    a = [2, undefined, 1];
+    a.sort();
+    assert(a[0] == 1 && a[1] == 2 && a[2] === undefined, "undefined sorted last");
+
+    var r;
+    try {
+        [2, 1].sort(function(x, y) { throw "cmp"; });
+    } catch (e) {
+        r = e;
+    }
+    assert(r == "cmp", "exception from comparator");
+
+    r = null;
+    try {
+        [].reduce(function(x, y) { return x; });
+    } catch (e) {
+        r = e;
+    }
+    assert(r !== null, "reduce of empty array");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_array_iteration()
+{
+    var a = [1, 2, 3];
+    var sum = 0;
+    a.forEach(function(v, i, arr) { sum += v * i; assert(arr === a, "array argument"); });
+    assert(sum == 8, "forEach");
+    assert(a.map(function(v) { return v * 2; }).join() == "2,4,6", "map");
+    assert(a.filter(function(v) { return v != 2; }).join() == "1,3", "filter");
+    assert(a.some(function(v) { return v > 2; }), "some");
+    assert(!a.some(function(v) { return v > 3; }), "some false");
+    assert(a.every(function(v) { return v > 0; }), "every");
+    assert(!a.every(function(v) { return v > 1; }), "every false");
+    assert(a.reduce(function(x, y) { return x + y; }) == 6, "reduce");
+    assert(a.reduce(function(x, y) { return x + y; }, 10) == 16, "reduce with initial");
+
+    var obj = {n: 5};
+    var r = [1].map(function(v) { return this.n + v; }, obj);
+    assert(r[0] == 6, "callback this");
+
+    println("-------- END TESTING -----------");
+}
+
+test_array_basic();
+test_array_methods();
+test_array_sort();
+test_array_iteration();
@@ -0,0 +1,99 @@
+function test_date_construct()
+{
+    var t = Date.UTC(2026, 9, 18, 10, 5, 7, 89);
+    var d = new Date(t);
+    assert(d.getTime() == t && d.valueOf() == t, "time value");
+    assert(d.toISOString() == "2026-10-18T10:05:07.089Z", "toISOString");
+    assert(d.toUTCString() == "Sun, 18 Oct 2026 10:05:07 GMT", "toUTCString");
+    assert(new Date(d).getTime() == t, "copy of date");
+    assert(new Date(0).toISOString() == "1970-01-01T00:00:00.000Z", "epoch");
+    assert(new Date(-1).toISOString() == "1969-12-31T23:59:59.999Z", "before epoch");
+    assert(new Date(-62198755200000).toISOString() == "-000001-01-01T00:00:00.000Z", "extended year");
+    assert(isNaN(new Date(8.64e15 + 1).getTime()), "time clip");
+    assert(new Date(NaN).toString() == "Invalid Date", "invalid date");
+    assert(Date.UTC(99, 0) == Date.UTC(1999, 0), "two digits year");
+    assert(typeof Date() == "string", "Date called as function");
+    assert(typeof Date.now() == "number", "Date.now");
+    assert(new Date(5) - new Date(2) == 3, "dates subtract as numbers");
+    assert(new Date(0) < new Date(1) && new Date(1) > new Date(0), "dates compare by time value");
+    assert(new Date(1) <= new Date(1) && new Date(1) >= new Date(1), "equal dates compare by time value");
+    assert(new Date(2) > 1 && !(new Date(NaN) < new Date(0)), "dates compare with numbers");
+    var valued = {valueOf: function() { return 10; }};
+    assert(valued > 9 && valued < 11, "objects compare by valueOf");
+    assert("10" < "9" && !("10" < 9), "strings compare as strings only with strings");
+
+    var e;
+    try {
+        new Date(NaN).toISOString();
+    } catch (err) {
+        e = "range";
+    }
+    assert(e == "range", "toISOString of invalid date");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_date_parse()
+{
+    var t = Date.UTC(2026, 9, 18, 10, 5, 7, 89);
+    assert(Date.parse("2026-10-18T10:05:07.089Z") == t, "parse ISO");
+    assert(Date.parse("2026-10-18T12:05:07.089+02:00") == t, "parse ISO offset");
+    assert(Date.parse("2026-10-18") == Date.UTC(2026, 9, 18), "parse date only");
+    assert(Date.parse("2026-10") == Date.UTC(2026, 9, 1), "parse year and month");
+    assert(Date.parse("+002026-10-18T00:00Z") == Date.UTC(2026, 9, 18), "parse extended year");
+    assert(new Date("2000-02-29").getUTCDate() == 29, "leap day");
+    assert(isNaN(Date.parse("2026-13-01")), "parse bad month");
+    assert(isNaN(Date.parse("2020-02-31")), "parse day past the end of the month");
+    assert(isNaN(Date.parse("2021-02-29")), "parse leap day of a common year");
+    assert(Date.parse("2020-02-29") == Date.UTC(2020, 1, 29), "parse leap day");
+    assert(isNaN(Date.parse("2026-04-31")), "parse day 31 of a short month");
+    assert(isNaN(Date.parse("2026-10-00")), "parse day 0");
+    assert(isNaN(Date.parse("2026-00-10")), "parse month 0");
+    assert(isNaN(Date.parse("2026-10-18T25:00Z")), "parse bad hour");
+    assert(isNaN(Date.parse("2026-10-18T24:01Z")), "parse past the end of the day");
+    assert(Date.parse("2026-10-18T24:00Z") == Date.UTC(2026, 9, 19), "parse end of the day");
+    assert(isNaN(Date.parse("2026-10-18T10:60Z")), "parse bad minute");
+    assert(isNaN(Date.parse("2026-10-18T10:00:60Z")), "parse bad second");
+    assert(isNaN(Date.parse("2026-10-18T10:00+24:00")), "parse bad offset");
+    assert(isNaN(new Date("2020-02-31").getTime()), "new Date of a bad day");
+    assert(isNaN(Date.parse("garbage")), "parse garbage");
+
+    var d = new Date(t);
+    assert(Date.parse(d.toUTCString()) == t - 89, "parse toUTCString");
+    assert(Date.parse(d.toString()) == t - 89, "parse toString");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_date_fields()
+{
+    var d = new Date(Date.UTC(2026, 9, 18, 10, 5, 7, 89));
+    assert(d.getUTCFullYear() == 2026 && d.getUTCMonth() == 9 && d.getUTCDate() == 18, "UTC date fields");
+    assert(d.getUTCDay() == 0, "UTC week day");
+    assert(d.getUTCHours() == 10 && d.getUTCMinutes() == 5 && d.getUTCSeconds() == 7 && d.getUTCMilliseconds() == 89, "UTC time fields");
+    assert(d.getFullYear() == d.getUTCFullYear() + 0, "local year");
+
+    d.setUTCMonth(0, 31);
+    assert(d.toISOString() == "2026-01-31T10:05:07.089Z", "setUTCMonth");
+    d.setUTCHours(25);
+    assert(d.toISOString() == "2026-02-01T01:05:07.089Z", "setUTCHours overflows");
+    d.setUTCFullYear(2024, 1, 29);
+    assert(d.toISOString() == "2024-02-29T01:05:07.089Z", "setUTCFullYear");
+    d.setUTCMilliseconds(1500);
+    assert(d.getUTCSeconds() == 8 && d.getUTCMilliseconds() == 500, "setUTCMilliseconds");
+    var r = d.setTime(0);
+    assert(r === 0 && d.getTime() === 0, "setTime");
+
+    var n = new Date(NaN);
+    assert(isNaN(n.setUTCHours(1)), "set on invalid date");
+    n.setUTCFullYear(2001);
+    assert(n.toISOString() == "2001-01-01T00:00:00.000Z", "setFullYear on invalid date");
+
+    assert(JSON.stringify({d: new Date(0)}) == '{"d":"1970-01-01T00:00:00.000Z"}', "toJSON");
+
+    println("-------- END TESTING -----------");
+}
+
+test_date_construct();
+test_date_parse();
+test_date_fields();
@@ -0,0 +1,15 @@
+function test_debugger()
+{
+    var n = 0;
+    debug;
+    n = n + 1;
+    for (var i = 0; i < 3; i++) {
+        debug;
+        n++;
+    }
+    assert(n == 4, "debugger statement continues by default");
+
+    println("-------- END TESTING -----------");
+}
+
+test_debugger();
@@ -0,0 +1,109 @@
+var x = "global";
+
+function test_eval()
+{
+    var x = "local";
+    var r;
+
+    r = eval("1 + 2");
+    assert(r == 3, "eval expression");
+
+    r = eval("x");
+    assert(r == "local", "direct eval uses the caller scope");
+
+    var ind = eval;
+    r = ind("x");
+    assert(r == "global", "indirect eval uses the global scope");
+
+    eval("var y = 5");
+    assert(y == 5, "eval declares variables in the caller scope");
+
+    r = eval(42);
+    assert(r === 42, "eval of none string");
+
+    r = eval();
+    assert(r === undefined, "eval without argument");
+
+    r = eval("if (true) { 'a'; } else { 'b'; }");
+    assert(r == "a", "eval completion value");
+
+    var obj = {
+        v: 9,
+        m: function() {
+            return eval("this.v");
+        }
+    };
+    assert(obj.m() == 9, "direct eval keeps this");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_eval_errors()
+{
+    var r;
+    try {
+        eval("1 +");
+    } catch (e) {
+        r = e;
+    }
+    assert(r instanceof SyntaxError, "eval syntax error");
+    assert(r.message.indexOf("SyntaxError") < 0 && r.message.indexOf("(line 1, column 4)") > 0, "eval syntax error message");
+    assert(String(r).indexOf("SyntaxError: SyntaxError") < 0, "eval syntax error named once");
+    try {
+        eval("var a;\nbreak;");
+    } catch (e) {
+        r = e;
+    }
+    assert(r instanceof SyntaxError && r.message.indexOf("(line 2)") > 0, "eval early error");
+
+    var deep = "", i;
+    for (i = 0; i < 100000; i++) {
+        deep = deep + "(";
+    }
+    r = null;
+    try {
+        eval(deep);
+    } catch (e) {
+        r = e;
+    }
+    assert(r instanceof SyntaxError && r.message.indexOf("too deeply nested") == 0, "eval of deeply nested source");
+    var many = "";
+    for (i = 0; i < 10000; i++) {
+        many = many + "r = " + i + ";\n";
+    }
+    assert(eval(many) == 9999, "eval of many statements");
+
+    try {
+        eval("throw 3");
+    } catch (e) {
+        r = e;
+    }
+    assert(r === 3, "eval throw");
+
+    r = eval("try { undefinedVar; } catch (e) { 'caught'; }");
+    assert(r == "caught", "eval try catch of reference error");
+    r = eval("1; try { x = nope + 1; } catch (e) { } ");
+    assert(r == 1, "eval completion value after caught error");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_eval_loops()
+{
+    var n = 0;
+    eval("for (var k in {a: 1, b: 2, c: 3}) { if (k == 'x') { break; } n++; }");
+    assert(n == 3, "for in with break in eval");
+
+    n = 0;
+    eval("for (var k in {a: 1, b: 2, c: 3}) { n++; continue; }");
+    assert(n == 3, "for in with continue in eval");
+
+    var r = eval("for (var k in {a: 1}) { 'v'; break; }");
+    assert(r == "v", "completion value after break");
+
+    println("-------- END TESTING -----------");
+}
+
+test_eval();
+test_eval_errors();
+test_eval_loops();
@@ -0,0 +1,225 @@
+function make(msg)
+{
+    return new Exception(msg);
+}
+
+function thrower(msg)
+{
+    throw new Exception(msg);
+}
+
+function test_stack()
+{
+    var i, e1, e2, e3;
+    for (i = 0; i < 2; i++) {
+        e2 = e1;
+        e1 = make("a");
+    }
+    e3 = new Exception("a");
+    assert(typeof e1.stack == "string", "stack is string");
+    assert(e1.stack == e2.stack, "same creation site");
+    assert(e1.stack != e3.stack, "different creation site");
+    assert(e1.message() == "a", "message");
+
+    var s1, s2;
+    try {
+        thrower("b");
+    } catch (e) {
+        s1 = e.stack;
+    }
+    try {
+        thrower("b");
+    } catch (e) {
+        s2 = e.stack;
+    }
+    assert(typeof s1 == "string", "thrown stack is string");
+    assert(s1 != s2, "different call site");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_internal_stack()
+{
+    var s;
+    try {
+        var o;
+        o.x = 1;
+    } catch (e) {
+        s = e.stack;
+    }
+    assert(typeof s == "string", "internal exception stack");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_throw_values()
+{
+    var r;
+    try {
+        throw "oops";
+    } catch (e) {
+        r = e;
+    }
+    assert(r === "oops", "throw string");
+
+    try {
+        throw 42;
+    } catch (e) {
+        r = e;
+    }
+    assert(r === 42, "throw number");
+
+    var obj = {code: 1};
+    try {
+        (function() { throw obj; })();
+    } catch (e) {
+        r = e;
+    }
+    assert(r === obj && r.code == 1, "throw object from function");
+
+    var ex = new Exception("same");
+    try {
+        try {
+            throw ex;
+        } catch (e) {
+            throw e;
+        }
+    } catch (e2) {
+        r = e2;
+    }
+    assert(r === ex, "rethrow keeps identity");
+
+    try {
+        throw undefined;
+    } catch (e) {
+        r = e;
+    }
+    assert(r === undefined, "throw undefined");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_throw_in_catch()
+{
+    var e = "outer";
+    var r;
+    try {
+        try {
+            throw 1;
+        } catch (e) {
+            throw 2;
+        }
+    } catch (x) {
+        r = x;
+    }
+    assert(r === 2, "throw in catch");
+    assert(e === "outer", "catch scope restored");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_error_types()
+{
+    var e = new TypeError("bad type");
+    assert(e instanceof TypeError && e instanceof Error, "TypeError is an Error");
+    assert(e instanceof Exception, "errors inherit from Exception");
+    assert(!(e instanceof RangeError), "not a RangeError");
+    assert(e.message == "bad type" && e.name == "TypeError", "message and name");
+    assert(e.toString() == "TypeError: bad type", "toString");
+    assert(String(new Error()) == "Error", "toString without message");
+    assert(RangeError("r").message == "r", "call without new");
+    assert(typeof e.stack == "string", "error stack");
+
+    e.name = "MyError";
+    assert(e.toString() == "MyError: bad type", "custom name");
+
+    var r;
+    try {
+        throw new SyntaxError("s");
+    } catch (x) {
+        r = x;
+    }
+    assert(r instanceof SyntaxError && r.message == "s", "throw error");
+
+    println("-------- END TESTING -----------");
+}
+
+function error_of(f)
+{
+    try {
+        f();
+    } catch (e) {
+        return e;
+    }
+    return null;
+}
+
+function test_runtime_errors()
+{
+    var e = error_of(function() { return not_defined_name; });
+    assert(e instanceof ReferenceError, "unknown variable");
+    assert(e.message == "'not_defined_name' is not defined", "reference error message");
+
+    e = error_of(function() { var o; return o.x; });
+    assert(e instanceof TypeError, "property of undefined");
+
+    e = error_of(function() { var o = {}; o.f(); });
+    assert(e instanceof TypeError, "call non-function");
+
+    e = error_of(function() { return new 1; });
+    assert(e instanceof TypeError, "new non-function");
+
+    e = error_of(function() { return "x" in 1; });
+    assert(e instanceof TypeError, "in non-object");
+
+    e = error_of(function() { return {} instanceof {}; });
+    assert(e instanceof TypeError, "instanceof non-function");
+
+    e = error_of(function() { var a = []; a.length = -1; });
+    assert(e instanceof RangeError, "invalid array length");
+
+    e = error_of(function() { var a = []; a[4000000000] = 1; });
+    assert(e instanceof RangeError, "array index too large");
+
+    e = error_of(function() { var a = []; a.length = 4e9; });
+    assert(e instanceof RangeError, "array length too large");
+
+    e = error_of(function() { return new Array(4e9); });
+    assert(e instanceof RangeError, "Array constructor length too large");
+
+    e = error_of(function() { Object.defineProperty([], "length", {value: 4e9}); });
+    assert(e instanceof RangeError, "defined array length too large");
+
+    e = error_of(function() { function f() { return f(); } f(); });
+    assert(e instanceof RangeError, "deep recursion");
+
+    e = error_of(function() { return (1).toFixed(100); });
+    assert(e instanceof RangeError, "toFixed range");
+
+    e = error_of(function() { return JSON.parse("{"); });
+    assert(e instanceof SyntaxError, "JSON syntax");
+
+    e = error_of(function() { return new RegExp("("); });
+    assert(e instanceof SyntaxError, "RegExp syntax");
+
+    e = error_of(function() { return eval("var = 1"); });
+    assert(e instanceof SyntaxError, "eval syntax");
+
+    e = error_of(function() { Array.prototype.push.call({}, 1); });
+    assert(e instanceof TypeError, "builtin on wrong object");
+
+    println("-------- END TESTING -----------");
+}
+
+/* errors thrown by script level expressions are caught */
+var caught = 0;
+try { undefinedVar; } catch (e) { caught++; }
+try { x = nope + 1; } catch (e) { caught++; }
+assert(caught == 2, "script level try catch");
+
+test_stack();
+test_internal_stack();
+test_throw_values();
+test_throw_in_catch();
+test_error_types();
+test_runtime_errors();
@@ -0,0 +1,117 @@
+function test_function_call()
+{
+    var o = {base: 10};
+    function add(a, b) {
+        return this.base + a + b;
+    }
+    assert(add.call(o, 1, 2) == 13, "call");
+    assert(add.apply(o, [3, 4]) == 17, "apply with array");
+    assert(Math.max.apply(null, [1, 5, 3]) == 5, "apply on builtin");
+
+    function sum() {
+        var s = 0;
+        for (var i = 0; i < arguments.length; i++) {
+            s += arguments[i];
+        }
+        return s;
+    }
+    function forward() {
+        return sum.apply(null, arguments);
+    }
+    assert(forward(1, 2, 3) == 6, "apply with arguments object");
+    assert(sum.apply(null) == 0, "apply without arguments");
+    assert(Array.prototype.join.call([1, 2], "-") == "1-2", "call builtin method");
+
+    var err = false;
+    try {
+        sum.apply(null, 1);
+    } catch(e) {
+        err = true;
+    }
+    assert(err, "apply with invalid arguments");
+
+    assert(sum.apply(null, {length: 3, 0: 1, 1: 2, 2: 3}) == 6, "apply with array-like object");
+    err = null;
+    try {
+        sum.apply(null, {length: 4294967295});
+    } catch(e) {
+        err = e;
+    }
+    assert(err instanceof RangeError, "apply with huge length");
+
+    function fail() {
+        throw new TypeError("fail");
+    }
+    err = false;
+    try {
+        fail.call(o);
+    } catch(e) {
+        err = e instanceof TypeError;
+    }
+    assert(err, "exception through call");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_function_bind()
+{
+    var o = {base: 10};
+    function add(a, b) {
+        return this.base + a + b;
+    }
+    var bound = add.bind(o, 1);
+    assert(bound(2) == 13, "bind this and leading arguments");
+    assert(bound.call({base: 100}, 2) == 13, "bound this can't be changed");
+    assert(typeof bound == "function", "typeof bound function");
+    assert(bound.length == 1, "length of bound function");
+    assert(bound.name == "bound add", "name of bound function");
+
+    var twice = bound.bind(null, 5);
+    assert(twice() == 16, "bind a bound function");
+
+    function Point(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+    var XPoint = Point.bind(null, 7);
+    var p = new XPoint(8);
+    assert(p.x == 7 && p.y == 8, "new on bound function");
+    assert(p instanceof Point && p instanceof XPoint, "instanceof bound function");
+
+    var values = [1, 2, 3].map(add.bind({base: 0}, 10));
+    assert(values.join() == "11,12,13", "bound callback");
+
+    // long chains are unwrapped without a nesting level per bind
+    var chain = add;
+    var i;
+    for (i = 0; i < 100000; i++) {
+        chain = chain.bind(o);
+    }
+    assert(chain(1, 2) == 13, "call a long bind chain");
+    for (i = 0; i < 100000; i++) {
+        XPoint = XPoint.bind(null);
+    }
+    p = new XPoint(9);
+    assert(p.x == 7 && p.y == 9, "new on a long bind chain");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_function_properties()
+{
+    function three(a, b, c) {}
+    assert(three.length == 3, "length");
+    assert(three.name == "three", "name");
+    assert((function() {}).name == "", "anonymous name");
+    assert(Math.max.length == 0 && Math.abs.length == 1, "builtin length");
+    assert(three.hasOwnProperty("length"), "own length");
+
+    var d = Object.getOwnPropertyDescriptor(three, "length");
+    assert(d.value == 3 && !d.writable && !d.enumerable, "length descriptor");
+
+    println("-------- END TESTING -----------");
+}
+
+test_function_call();
+test_function_bind();
+test_function_properties();
@@ -0,0 +1,117 @@
+function test_json_parse()
+{
+    var o = JSON.parse('{"a": 1, "b": [true, false, null], "c": {"d": "x\\ny"}, "e": -1.5e2}');
+    assert(o.a === 1, "parse number");
+    assert(o.b.length == 3 && o.b[0] === true && o.b[2] === null, "parse array");
+    assert(o.c.d == "x\ny", "parse string escape");
+    assert(o.e === -150, "parse exponent");
+    assert(JSON.parse('"\\u00e9\\ud83d\\ude00"') == "é😀", "parse unicode escape");
+    assert(JSON.parse(" 42 ") === 42, "parse top level number");
+
+    var bad = ["{a: 1}", "[1,]", "01", "'x'", "[1] x", ""];
+    var i, n = 0;
+    for (i = 0; i < bad.length; i++) {
+        try {
+            JSON.parse(bad[i]);
+        } catch (e) {
+            n++;
+        }
+    }
+    assert(n == bad.length, "parse errors");
+
+    var r = JSON.parse('{"a": 1, "b": {"c": 2, "d": 3}}', function(k, v) {
+        if (k == "d") {
+            return undefined;
+        }
+        if (typeof v == "number") {
+            return v * 10;
+        }
+        return v;
+    });
+    assert(r.a == 10 && r.b.c == 20, "reviver");
+    assert(!("d" in r.b), "reviver deletes");
+    var a = JSON.parse("[1, 2, 3]", function(k, v) {
+        if (v === 2) {
+            return undefined;
+        }
+        return v;
+    });
+    assert(a.length == 3 && a[1] === undefined && a[2] === 3, "reviver deletes array elements");
+
+    var deep = "";
+    for (i = 0; i < 100000; i++) {
+        deep = deep + "[";
+    }
+    r = null;
+    try {
+        JSON.parse(deep);
+    } catch (e) {
+        r = e;
+    }
+    assert(r instanceof SyntaxError, "parse too deeply nested");
+    assert(JSON.parse("[[[[1]]]]")[0][0][0][0] === 1, "parse after nesting error");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_json_stringify()
+{
+    var o = {b: 1, a: "x", c: [1, "two", null, true], d: {e: undefined, f: function() {}}};
+    assert(JSON.stringify(o) == '{"b":1,"a":"x","c":[1,"two",null,true],"d":{}}', "stringify keeps insertion order");
+    assert(JSON.stringify("a\"b\n") == '"a\\"b\\n"', "stringify escapes");
+    assert(JSON.stringify([undefined, function() {}]) == "[null,null]", "stringify undefined in array");
+    assert(JSON.stringify(undefined) === undefined, "stringify undefined");
+    assert(JSON.stringify(1 / 0) == "null", "stringify Infinity");
+    assert(JSON.stringify({}) == "{}" && JSON.stringify([]) == "[]", "stringify empty");
+
+    assert(JSON.stringify({a: [1, 2], b: {}}, null, 2) == '{\n  "a": [\n    1,\n    2\n  ],\n  "b": {}\n}', "stringify indent");
+    assert(JSON.stringify([1], null, "--") == '[\n--1\n]', "stringify string indent");
+
+    var s = JSON.stringify({a: 1, b: 2, c: 3}, ["c", "a"]);
+    assert(s == '{"c":3,"a":1}', "stringify replacer list");
+    s = JSON.stringify({a: 1, b: "x"}, function(k, v) {
+        if (typeof v == "number") {
+            return v + 1;
+        }
+        return v;
+    });
+    assert(s == '{"a":2,"b":"x"}', "stringify replacer function");
+
+    var t = {toJSON: function(key) { return "T"; }};
+    assert(JSON.stringify({t: t}) == '{"t":"T"}', "stringify toJSON");
+
+    var c = {};
+    c.self = c;
+    var r = null;
+    try {
+        JSON.stringify(c);
+    } catch (e) {
+        r = e;
+    }
+    assert(r !== null, "stringify cycle");
+
+    var shared = {x: 1};
+    assert(JSON.stringify([shared, shared]) == '[{"x":1},{"x":1}]', "shared objects are not cycles");
+
+    assert(JSON.stringify(new Number(3)) == "3" && JSON.stringify(new String("s")) == '"s"', "stringify wrappers");
+    assert(JSON.stringify([new Boolean(false)]) == "[false]", "stringify boolean wrapper");
+
+    var deep = {};
+    var i;
+    for (i = 0; i < 10000; i++) {
+        deep = {a: deep};
+    }
+    r = null;
+    try {
+        JSON.stringify(deep);
+    } catch (e) {
+        r = e;
+    }
+    assert(r instanceof RangeError, "stringify too deeply nested");
+    assert(JSON.stringify({a: [1]}) == '{"a":[1]}', "stringify after nesting error");
+
+    println("-------- END TESTING -----------");
+}
+
+test_json_parse();
+test_json_stringify();
@@ -86,7 +86,7 @@ function test_for_in()
         tab.push(i);
     }
     a = tab.toString();
-    assert(a == "1, y" || a == "y, 1", "for_in 2");
+    assert(a == "1,y" || a == "y,1", "for_in 2");
     
     // TODO
     /* array optimization */
@@ -107,7 +107,7 @@ function test_for_in()
     for(var j in {x:1, y: 2}) {
         tab.push(j);
     }
-    assert(tab.toString() == "x, y" || tab.toString() == "y, x", "for_in 3");
+    assert(tab.toString() == "x,y" || tab.toString() == "y,x", "for_in 3");
 
     println("-------- END TESTING -----------");
 }
@@ -121,7 +121,7 @@ function test_for_in2()
             continue;
         tab.push(i);
     }
-    assert(tab.toString() == "x, z" || tab.toString() == "z, x", "for in 1");
+    assert(tab.toString() == "x,z" || tab.toString() == "z,x", "for in 1");
 
     tab = [];
     for(i in {x:1, y: 2, z:3}) {
@@ -129,7 +129,7 @@ function test_for_in2()
             break;
         tab.push(i);
     }
-    assert(tab.toString() == "x, y" || tab.toString() == "y, x" || tab.toString() == "y" || tab.toString() == "x" || tab.toString() == "" , "for in 2");
+    assert(tab.toString() == "x,y" || tab.toString() == "y,x" || tab.toString() == "y" || tab.toString() == "x" || tab.toString() == "" , "for in 2");
 
     println("-------- END TESTING -----------");
 }
@@ -0,0 +1,40 @@
+function test_math()
+{
+    assert(Math.PI > 3.14159 && Math.PI < 3.1416, "PI");
+    assert(Math.E > 2.718 && Math.E < 2.719, "E");
+    assert(Math.SQRT2 * Math.SQRT2 > 1.9999, "SQRT2");
+    assert(Math.abs(-3) == 3, "abs");
+    assert(Math.floor(1.7) == 1 && Math.floor(-1.2) == -2, "floor");
+    assert(Math.ceil(1.2) == 2 && Math.ceil(-1.7) == -1, "ceil");
+    assert(Math.round(2.5) == 3 && Math.round(-2.5) == -2 && Math.round(2.4) == 2, "round");
+    assert(Math.round(0.49999999999999994) == 0, "round below half");
+    assert(Math.max(1, 5, 3) == 5 && Math.min(4, 2, 8) == 2, "max min");
+    assert(Math.max() == -Infinity && Math.min() == Infinity, "max min without arguments");
+    var n = Math.max(1, NaN);
+    assert(n != n, "max with NaN");
+    assert(Math.pow(2, 10) == 1024, "pow");
+    n = Math.pow(1, NaN);
+    assert(n != n, "pow of NaN");
+    assert(Math.sqrt(16) == 4, "sqrt");
+    assert(Math.sin(0) == 0 && Math.cos(0) == 1, "sin cos");
+    assert(Math.atan2(1, 1) * 4 == Math.PI, "atan2");
+    assert(Math.log(Math.E) == 1 && Math.exp(0) == 1, "log exp");
+
+    var i, r;
+    for (i = 0; i < 100; i++) {
+        r = Math.random();
+        assert(r >= 0 && r < 1, "random range");
+    }
+
+    r = false;
+    try {
+        Math.PI = 3;
+    } catch (e) {
+        r = true;
+    }
+    assert(r && Math.PI > 3.14, "constants are read only");
+
+    println("-------- END TESTING -----------");
+}
+
+test_math();
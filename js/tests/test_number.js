@@ -0,0 +1,116 @@
+function test_number_format()
+{
+    assert(String(0.1 + 0.2) == "0.30000000000000004", "shortest round trip");
+    assert(String(1e21) == "1e+21", "large exponent");
+    assert(String(123456789012345680000) == "123456789012345680000", "below 1e21");
+    assert(String(0.000001) == "0.000001", "small decimal");
+    assert(String(1e-7) == "1e-7", "small exponent");
+    assert(String(-0) == "0", "negative zero");
+    assert(String(1/0) == "Infinity", "infinity");
+    assert(String(0/0) == "NaN", "nan");
+    assert(Number("  12.5 ") == 12.5, "string to number");
+    assert(Number("0x1f") == 31, "hex string to number");
+    assert(isNaN(Number("12px")), "invalid string to number");
+    assert(Number("") == 0, "empty string to number");
+    assert(Number() === 0, "Number without argument");
+    assert(isNaN(Number(undefined)), "Number of undefined");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_number_methods()
+{
+    assert((255).toString(16) == "ff", "toString radix 16");
+    assert((5).toString(2) == "101", "toString radix 2");
+    assert((-255).toString(36) == "-73", "toString radix 36");
+    assert(Math.pow(2, 60).toString(36) == "8rc4kbdvss1s", "toString radix of a large integer is exact");
+    assert((1e21).toString(7) == "5135235413265003022550266", "toString radix 7 of 1e21");
+    assert(Number.MAX_VALUE.toString(36).length == 199, "toString radix of MAX_VALUE");
+    var zeros = "";
+    for (var i = 0; i < 1073; i++) {
+        zeros = zeros + "0";
+    }
+    assert(Math.pow(2, 100).toString(2) == "1" + zeros.substring(0, 100), "toString radix 2 of a power of two");
+    assert((0.5).toString(2) == "0.1", "toString radix of a fraction");
+    assert((255.5).toString(16) == "ff.8", "toString radix with both parts");
+
+    // leading zeros of the fraction aren't significant digits
+    var s = (1.23e-18).toString(16);
+    assert(s.indexOf("0.00000000000000") == 0 && s.length == 30, "toString radix of a small fraction");
+    var x = 0;
+    for (var i = s.length - 1; i > 1; i--) {
+        x = (x + parseInt(s.charAt(i), 16)) / 16;
+    }
+    assert(Math.abs(x - 1.23e-18) < 1e-30, "toString radix of a small fraction keeps its value");
+    assert(Number.MIN_VALUE.toString(2) == "0." + zeros + "1", "toString radix 2 of MIN_VALUE");
+    assert((1.005).toFixed(2) == "1.00", "toFixed uses the exact value");
+    assert((2.5).toFixed(0) == "3", "toFixed rounds half up");
+    assert((1234.5678).toFixed(2) == "1234.57", "toFixed");
+    assert((1e21).toFixed(2) == "1e+21", "toFixed of large number");
+    assert((123.456).toExponential(2) == "1.23e+2", "toExponential");
+    assert((0.00015).toExponential() == "1.5e-4", "toExponential without digits");
+    assert((123.456).toPrecision(4) == "123.5", "toPrecision");
+    assert((0.000123).toPrecision(2) == "0.00012", "toPrecision small");
+    assert((123456).toPrecision(2) == "1.2e+5", "toPrecision exponent");
+    assert((7).valueOf() === 7, "valueOf");
+
+    var r;
+    try {
+        (1).toString(1);
+    } catch (e) {
+        r = "range";
+    }
+    assert(r == "range", "bad radix throws");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_number_constants()
+{
+    assert(Number.MAX_VALUE == 1.7976931348623157e308, "MAX_VALUE");
+    assert(Number.MIN_VALUE == 5e-324, "MIN_VALUE");
+    assert(isNaN(Number.NaN), "NaN");
+    assert(Number.POSITIVE_INFINITY == 1/0, "POSITIVE_INFINITY");
+    assert(Number.NEGATIVE_INFINITY == -1/0, "NEGATIVE_INFINITY");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_global_functions()
+{
+    assert(parseInt("42px") == 42, "parseInt prefix");
+    assert(parseInt("  -17") == -17, "parseInt sign");
+    assert(parseInt("0x1F") == 31, "parseInt hex");
+    assert(parseInt("ff", 16) == 255, "parseInt radix");
+    assert(parseInt("101", 2) == 5, "parseInt binary");
+    assert(isNaN(parseInt("px")), "parseInt without digits");
+    assert(isNaN(parseInt("1", 40)), "parseInt bad radix");
+    assert(parseFloat("3.14abc") == 3.14, "parseFloat prefix");
+    assert(parseFloat(" -2.5e3x") == -2500, "parseFloat exponent");
+    assert(parseFloat("1e") == 1, "parseFloat incomplete exponent");
+    assert(parseFloat("Infinityx") == 1/0, "parseFloat infinity");
+    assert(isNaN(parseFloat(".")), "parseFloat without digits");
+    assert(isNaN("abc"), "isNaN");
+    assert(!isNaN("12"), "isNaN of numeric string");
+    assert(isFinite(12), "isFinite");
+    assert(!isFinite(1/0), "isFinite of infinity");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_boolean()
+{
+    assert((true).toString() == "true", "boolean toString");
+    assert((false).valueOf() === false, "boolean valueOf");
+    assert(Boolean(1) === true, "Boolean of number");
+    assert(Boolean("") === false, "Boolean of empty string");
+    assert(Boolean({}) === true, "Boolean of object");
+
+    println("-------- END TESTING -----------");
+}
+
+test_number_format();
+test_number_methods();
+test_number_constants();
+test_global_functions();
+test_boolean();
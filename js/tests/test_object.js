@@ -0,0 +1,181 @@
+function test_object_keys()
+{
+    var o = {a:1, b:2};
+    o.c = 3;
+    assert(Object.keys(o).join() == "a,b,c", "keys in insertion order");
+    assert(Object.keys([5, 6]).join() == "0,1", "keys of array");
+    assert(Object.keys({}).length == 0, "keys of empty object");
+
+    var err = false;
+    try {
+        Object.keys(1);
+    } catch(e) {
+        err = true;
+    }
+    assert(err, "keys of non-object");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_object_create()
+{
+    var proto = {hello: function() { return "hi " + this.name; }};
+    var o = Object.create(proto);
+    o.name = "js";
+    assert(o.hello() == "hi js", "create with prototype");
+    assert(Object.getPrototypeOf(o) === proto, "getPrototypeOf");
+    assert(proto.isPrototypeOf(o), "isPrototypeOf");
+    assert(!o.isPrototypeOf(proto), "not isPrototypeOf");
+    assert(Object.prototype.isPrototypeOf(o), "Object.prototype is in chain");
+
+    var n = Object.create(null);
+    assert(Object.getPrototypeOf(n) === null, "create with null prototype");
+    assert(n.toString === undefined, "null prototype has no methods");
+
+    var p = Object.create(proto, {x: {value: 1, enumerable: true}, y: {value: 2}});
+    assert(p.x == 1 && p.y == 2, "create with properties");
+    assert(Object.keys(p).join() == "x", "non-enumerable by default");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_object_define()
+{
+    var o = {};
+    Object.defineProperty(o, "x", {value: 1});
+    var d = Object.getOwnPropertyDescriptor(o, "x");
+    assert(d.value == 1 && !d.writable && !d.enumerable && !d.configurable, "default attributes");
+
+    var err = false;
+    try {
+        o.x = 2;
+    } catch(e) {
+        err = true;
+    }
+    assert(err && o.x == 1, "read-only property");
+
+    err = false;
+    try {
+        Object.defineProperty(o, "x", {value: 2});
+    } catch(e) {
+        err = true;
+    }
+    assert(err, "redefine non-configurable");
+    Object.defineProperty(o, "x", {value: 1});
+    assert(delete o.x == false, "delete non-configurable");
+
+    var v = 0;
+    Object.defineProperty(o, "y", {
+        get: function() { return v * 2; },
+        set: function(n) { v = n; },
+        enumerable: true,
+        configurable: true
+    });
+    o.y = 5;
+    assert(o.y == 10, "accessor property");
+    d = Object.getOwnPropertyDescriptor(o, "y");
+    assert(typeof d.get == "function" && d.value === undefined && d.enumerable, "accessor descriptor");
+
+    Object.defineProperty(o, "y", {value: 3, writable: true});
+    assert(o.y == 3 && Object.getOwnPropertyDescriptor(o, "y").get === undefined, "accessor to data");
+
+    Object.defineProperties(o, {a: {value: "a", enumerable: true}, b: {value: "b"}});
+    assert(o.a == "a" && o.b == "b", "defineProperties");
+    assert(Object.keys(o).join() == "y,a", "enumerable keys");
+
+    err = false;
+    try {
+        Object.defineProperty(o, "z", {value: 1, get: function() {}});
+    } catch(e) {
+        err = true;
+    }
+    assert(err, "invalid descriptor");
+
+    assert(Object.getOwnPropertyDescriptor(o, "none") === undefined, "missing descriptor");
+    d = Object.getOwnPropertyDescriptor([1, 2], "length");
+    assert(d.value == 2 && d.writable && !d.enumerable, "array length descriptor");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_object_freeze()
+{
+    var o = {a: 1};
+    Object.seal(o);
+    assert(Object.isSealed(o) && !Object.isFrozen(o), "sealed");
+    o.a = 2;
+    assert(o.a == 2, "sealed property is writable");
+    assert(delete o.a == false, "sealed property can't be deleted");
+
+    var err = false;
+    try {
+        o.b = 1;
+    } catch(e) {
+        err = true;
+    }
+    assert(err && o.b === undefined, "sealed object is not extensible");
+
+    var f = Object.freeze({a: 1});
+    assert(Object.isFrozen(f) && Object.isSealed(f), "frozen");
+    err = false;
+    try {
+        f.a = 2;
+    } catch(e) {
+        err = true;
+    }
+    assert(err && f.a == 1, "frozen property is read-only");
+
+    var a = Object.freeze([1, 2]);
+    assert(Object.isFrozen(a), "frozen array");
+    err = false;
+    try {
+        a.push(3);
+    } catch(e) {
+        err = true;
+    }
+    assert(err && a.length == 2, "frozen array can't grow");
+    err = false;
+    try {
+        a[0] = 5;
+    } catch(e) {
+        err = true;
+    }
+    assert(err && a[0] == 1, "frozen array elements are read-only");
+
+    var s = Object.seal([1, 2]);
+    s[0] = 3;
+    s.sort();
+    assert(s.join() == "2,3", "sealed array elements are writable");
+    assert(Object.isSealed(s) && !Object.isFrozen(s), "sealed array");
+
+    assert(!Object.isFrozen({}), "plain object is not frozen");
+    assert(Object.isFrozen(Object.preventExtensions({})), "empty non-extensible object is frozen");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_object_prototype()
+{
+    var proto = {inherited: 1};
+    var o = Object.create(proto);
+    o.own = 2;
+    assert(o.hasOwnProperty("own"), "hasOwnProperty own");
+    assert(!o.hasOwnProperty("inherited"), "hasOwnProperty inherited");
+    assert([1].hasOwnProperty("0") && [1].hasOwnProperty("length"), "hasOwnProperty of array");
+    assert(o.propertyIsEnumerable("own"), "propertyIsEnumerable");
+    assert(!o.propertyIsEnumerable("inherited"), "inherited is not own enumerable");
+    assert(![1].propertyIsEnumerable("length"), "length is not enumerable");
+
+    // assignment shadows inherited properties
+    o.inherited = 3;
+    assert(o.inherited == 3 && proto.inherited == 1, "shadow inherited property");
+    assert(delete o.none, "delete missing property");
+
+    println("-------- END TESTING -----------");
+}
+
+test_object_keys();
+test_object_create();
+test_object_define();
+test_object_freeze();
+test_object_prototype();
@@ -0,0 +1,92 @@
+function test_folding()
+{
+    var r;
+    r = 1 + 2 * 3 - 4 / 2;
+    assert(r == 5, "1 + 2 * 3 - 4 / 2 === 5");
+
+    r = -(3 - 5) * +2;
+    assert(r == 4, "-(3 - 5) * +2 === 4");
+
+    r = 7 % 4 + 0.5;
+    assert(r == 3.5, "7 % 4 + 0.5 === 3.5");
+
+    r = 70000 + 1;
+    assert(r == 70001, "70000 + 1 === 70001");
+
+    r = 1 - 2;
+    assert(r == -1, "1 - 2 === -1");
+
+    r = 1 / 0;
+    assert(r == Infinity, "1 / 0 === Infinity");
+
+    r = "a" + 1 + 2;
+    assert(r == "a12", "\"a\" + 1 + 2 === \"a12\"");
+
+    println("-------- END TESTING -----------");
+}
+
+function dead_code(a)
+{
+    return a + 1;
+    a = 100;
+    return a;
+}
+
+function test_dead_code()
+{
+    assert(dead_code(1) == 2, "dead code after return");
+
+    var i = 0;
+    while (true) {
+        i++;
+        if (i == 10) {
+            break;
+        }
+    }
+    assert(i == 10, "while(true) loop");
+
+    i = 0;
+    do {
+        i++;
+        if (i < 5) {
+            continue;
+        }
+        break;
+    } while (false);
+    assert(i == 1, "do while(false) loop");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_jumps()
+{
+    var i, n = 0;
+    for (i = 0; i < 5; i++) {
+        if (i % 2 == 0) {
+            if (i == 4) {
+                n = n + 100;
+            } else {
+                n = n + 1;
+            }
+        } else {
+            n = n + 10;
+        }
+    }
+    assert(n == 122, "nested if in for");
+
+    n = 0;
+    try {
+        n = 1;
+        throw new Exception("fold");
+        n = 2;
+    } catch (e) {
+        n = n + 10;
+    }
+    assert(n == 11, "dead code after throw");
+
+    println("-------- END TESTING -----------");
+}
+
+test_folding();
+test_dead_code();
+test_jumps();
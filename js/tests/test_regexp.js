@@ -0,0 +1,131 @@
+function test_regexp_literal()
+{
+    var r = /a\/b[/]c/gi;
+    assert(r.source == "a\\/b[/]c", "source");
+    assert(r.global && r.ignoreCase && !r.multiline, "flags");
+    assert(r.lastIndex === 0, "lastIndex");
+    assert(String(/x/m) == "/x/m", "toString");
+
+    var x = 12 / 3 / 2;
+    assert(x == 2, "division is not a regexp");
+    var a = [8];
+    assert(a[0] / 2 / 2 == 2, "division after bracket");
+
+    var e;
+    try {
+        new RegExp("(a");
+    } catch (err) {
+        e = "syntax";
+    }
+    assert(e == "syntax", "invalid pattern");
+
+    var open = "", close = "", i;
+    for (i = 0; i < 50000; i++) {
+        open = open + "(";
+    }
+    e = null;
+    try {
+        new RegExp(open);
+    } catch (err) {
+        e = err;
+    }
+    assert(e instanceof SyntaxError, "too many nested groups");
+    open = "";
+    for (i = 0; i < 100; i++) {
+        open = open + "(?:";
+        close = close + ")";
+    }
+    assert(new RegExp(open + "a" + close).test("a"), "nested groups up to the limit");
+    assert(new RegExp("a/b").source == "a\\/b", "constructor escapes source");
+    assert(new RegExp(/q/g).global, "constructor from regexp");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_regexp_exec()
+{
+    var m = /(\d+)-(\d+)/.exec("call 555-1234 now");
+    assert(m[0] == "555-1234" && m[1] == "555" && m[2] == "1234", "groups");
+    assert(m.index == 5 && m.input == "call 555-1234 now", "index and input");
+    assert(/x/.exec("abc") === null, "no match");
+
+    m = /^(?:(a)|b)+$/.exec("ab");
+    assert(m[0] == "ab" && m[1] === undefined, "captures reset in repeats");
+    assert(/(a)\1/.test("aa") && !/(a)\1/.test("ab"), "back reference");
+    assert(/(?=(\w+))\w/.exec("abc")[1] == "abc", "lookahead capture");
+    assert(/a(?!b)/.exec("abac").index == 2, "negative lookahead");
+    assert(/x{2,3}/.exec("xxxx")[0] == "xxx", "counted repeat");
+    assert(/x+?/.exec("xxx")[0] == "x", "lazy repeat");
+    assert(/[^a-c\d]+/.exec("ab12xyz")[0] == "xyz", "negated class");
+    assert(/[\s\S]+/.exec("a\nb")[0] == "a\nb", "class escapes");
+    assert(/\bfoo\b/.test("a foo b") && !/\bfoo\b/.test("afoob"), "word boundary");
+    assert(/^b$/m.test("a\nb") && !/^b$/.test("a\nb"), "multiline");
+    assert(/ABC/i.test("xabcx"), "ignore case");
+    assert(/A\x42/.test("AB"), "hex escapes");
+    assert(/(a*)*b/.test("aaaaaaaaaaaaaaaaaaaab"), "nested repeats");
+
+    var g = /o/g;
+    assert(g.test("foo") && g.lastIndex == 2, "global test");
+    assert(g.test("foo") && g.lastIndex == 3, "global test again");
+    assert(!g.test("foo") && g.lastIndex == 0, "global test reset");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_regexp_string()
+{
+    assert("a  b   c".replace(/\s+/g, " ") == "a b c", "replace global");
+    assert("aaa".replace(/a/, "b") == "baa", "replace first");
+    assert("John Smith".replace(/(\w+)\s(\w+)/, "$2, $1") == "Smith, John", "replace groups");
+    assert("abc".replace(/(x)?b/, "[$1]") == "a[]c", "replace missing group");
+    var r = "a1b22".replace(/\d+/g, function(m, off) { return "<" + m + off + ">"; });
+    assert(r == "a<11>b<223>", "replace function");
+    assert("abc".replace(/x*/g, "-") == "-a-b-c-", "replace empty matches");
+
+    var m = "Hello World".match(/o/g);
+    assert(m.length == 2 && m[0] == "o", "match global");
+    assert("abc".match(/x/g) === null, "match global none");
+    m = "abc".match(/(b)(c)/);
+    assert(m[2] == "c" && m.index == 1, "match groups");
+    assert("a.c".match(".")[0] == "a", "match string is a pattern");
+
+    assert("Hello".search(/l+/) == 2, "search");
+    assert("Hello".search(/z/) == -1, "search none");
+
+    var a = "a1b2c3".split(/\d/);
+    assert(a.length == 4 && a[2] == "c" && a[3] == "", "split regexp");
+    a = "abc".split(/(b)/);
+    assert(a.length == 3 && a[1] == "b", "split captures");
+    a = "test".split(/(?:)/);
+    assert(a.length == 4 && a[3] == "t", "split empty regexp");
+    assert("".split(/x/).length == 1 && "".split(/(?:)/).length == 0, "split empty string");
+    a = "a,b,c".split(/,/, 2);
+    assert(a.length == 2 && a[1] == "b", "split limit");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_regexp_backtracking()
+{
+    var s = "";
+    for (var i = 0; i < 15; i++) {
+        s += s + "a";
+    }
+    assert(/(a|b)*/.exec(s)[0].length == s.length, "long input");
+    assert(/(?:ab)*$/.test("x" + s.replace(/a/g, "ab")), "long input with a group");
+
+    var e = null;
+    try {
+        /(a|a)*b/.test("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+    } catch (x) {
+        e = x;
+    }
+    assert(e instanceof RangeError, "exponential backtracking");
+
+    println("-------- END TESTING -----------");
+}
+
+test_regexp_literal();
+test_regexp_exec();
+test_regexp_string();
+test_regexp_backtracking();
@@ -0,0 +1,94 @@
+function test_string_basic()
+{
+    var s = "hello world";
+    assert(s.length == 11, "length");
+    assert(s[0] == "h" && s[10] == "d", "index");
+    assert(s[11] === undefined, "index out of range");
+    assert(s.charAt(4) == "o", "charAt");
+    assert(s.charAt(20) == "", "charAt out of range");
+    assert(s.charCodeAt(0) == 104, "charCodeAt");
+    assert(s.charCodeAt(20) != s.charCodeAt(20), "charCodeAt NaN");
+    assert(s.indexOf("o") == 4, "indexOf");
+    assert(s.indexOf("o", 5) == 7, "indexOf from");
+    assert(s.indexOf("x") == -1, "indexOf not found");
+    assert(s.lastIndexOf("o") == 7, "lastIndexOf");
+    assert(s.lastIndexOf("o", 6) == 4, "lastIndexOf from");
+    assert(s.toUpperCase() == "HELLO WORLD", "toUpperCase");
+    assert("ABC".toLowerCase() == "abc", "toLowerCase");
+    assert("  abc \n".trim() == "abc", "trim");
+    assert("a".concat("b", 1, "c") == "ab1c", "concat");
+    assert("a".localeCompare("b") == -1, "localeCompare");
+    assert(String(12) == "12", "String constructor");
+    assert(String.fromCharCode(104, 105) == "hi", "fromCharCode");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_string_sub()
+{
+    var s = "abcdef";
+    assert(s.slice(1, 3) == "bc", "slice");
+    assert(s.slice(-2) == "ef", "slice negative");
+    assert(s.slice(3, 1) == "", "slice empty");
+    assert(s.substring(3, 1) == "bc", "substring swaps");
+    assert(s.substring(-2, 2) == "ab", "substring negative");
+    assert(s.substr(1, 2) == "bc", "substr");
+    assert(s.substr(-3) == "def", "substr negative");
+
+    var a = "a,b,,c".split(",");
+    assert(a[0] == "a" && a[1] == "b" && a[2] == "" && a[3] == "c", "split");
+    assert(a[4] === undefined, "split count");
+    a = "abc".split("");
+    assert(a[0] == "a" && a[2] == "c", "split empty separator");
+    a = "a,b,c".split(",", 2);
+    assert(a[1] == "b" && a[2] === undefined, "split limit");
+    a = "abc".split();
+    assert(a[0] == "abc", "split undefined");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_string_replace()
+{
+    assert("aXbX".replace("X", "-") == "a-bX", "replace first");
+    assert("abc".replace("b", "[$&]") == "a[b]c", "replace pattern $&");
+    assert("abc".replace("b", "$`$'") == "aacc", "replace pattern context");
+    assert("abc".replace("b", "$$") == "a$c", "replace dollar");
+    assert("abc".replace("x", "y") == "abc", "replace not found");
+    var r = "abc".replace("b", function(m, pos) { return m.toUpperCase() + pos; });
+    assert(r == "aB1c", "replace function");
+    assert("abc".search("c") == 2, "search");
+    var m = "abcabc".match("ca");
+    assert(m[0] == "ca" && m.index == 2, "match");
+    assert("abc".match("x") === null, "match null");
+
+    println("-------- END TESTING -----------");
+}
+
+function test_string_utf16()
+{
+    var s = "héllo";
+    assert(s.length == 5, "non ascii length");
+    assert(s[1] == "é", "non ascii index");
+    assert(s.charCodeAt(1) == 233, "non ascii charCodeAt");
+    assert(s.indexOf("l") == 2, "non ascii indexOf");
+    assert(s.slice(1, 3) == "él", "non ascii slice");
+
+    var e = "a😀b";
+    assert(e.length == 4, "surrogate pair length");
+    assert(e.charCodeAt(1) == 55357 && e.charCodeAt(2) == 56832, "surrogate code units");
+    assert(e.indexOf("b") == 3, "index after surrogate pair");
+    assert(String.fromCharCode(55357, 56832) == "😀", "fromCharCode surrogate pair");
+    assert(e.charAt(4) == "" && isNaN(e.charCodeAt(-1)), "charAt out of range");
+
+    // lone surrogates can't be kept in strings
+    assert("😀".slice(0, 1).charCodeAt(0) == 65533, "lone surrogate is replaced");
+    assert("😀".charAt(1).charCodeAt(0) == 65533, "charAt of lone surrogate");
+
+    println("-------- END TESTING -----------");
+}
+
+test_string_basic();
+test_string_sub();
+test_string_replace();
+test_string_utf16();